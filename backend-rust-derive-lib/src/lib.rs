@@ -7,83 +7,152 @@
 
 use axum::Json;
 use chrono::NaiveDateTime;
-use std::collections::{BTreeSet, VecDeque};
+pub use serde_json;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 pub trait Typescriptable {
     fn name() -> String;
     #[must_use]
     fn code() -> BTreeSet<String> {
         BTreeSet::new()
     }
+    /// The JSON schema of this type. Named types are a `$ref` to their entry in [`Self::schemas`].
+    fn schema() -> Value;
+    /// The named schemas this type depends on, keyed by [`Self::name`].
+    #[must_use]
+    fn schemas() -> BTreeMap<String, Value> {
+        BTreeMap::new()
+    }
+    /// The schema of error responses, only `Result`s have one.
+    #[must_use]
+    fn error_schema() -> Option<Value> {
+        None
+    }
 }
 
 pub trait TypescriptRoute {
     fn code(path: &str) -> BTreeSet<String>;
+    /// The operation object of the route in the API description.
+    fn operation() -> Value;
+    fn schemas() -> BTreeMap<String, Value>;
+}
+
+/// A reference to a schema in `#/components/schemas`.
+#[must_use]
+pub fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// Turns the name of a type parameter into a part of an identifier, so generic types get names
+/// like `WithTucanUrl_Course_CourseGroupList_CourseEventList`.
+#[must_use]
+pub fn identifier_part(name: &str) -> String {
+    name.replace("[]", "List")
+        .replace(" | null", "OrNull")
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
 }
 
 impl Typescriptable for u32 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "integer", "format": "int32", "minimum": 0 })
+    }
 }
 
 impl Typescriptable for i64 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "integer", "format": "int64" })
+    }
 }
 
 impl Typescriptable for String {
     fn name() -> String {
         "string".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
 }
 
 impl Typescriptable for NaiveDateTime {
     fn name() -> String {
         "string".to_string()
     }
+    fn schema() -> Value {
+        // serialized without an offset so not a valid `date-time`
+        json!({ "type": "string", "description": "UTC, e.g. 2023-01-16T09:15:00" })
+    }
 }
 
 impl Typescriptable for () {
     fn name() -> String {
         "null".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "null" })
+    }
 }
 
 impl Typescriptable for bool {
     fn name() -> String {
         "boolean".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
 }
 
 impl Typescriptable for i16 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "integer" })
+    }
 }
 
 impl Typescriptable for u8 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0, "maximum": 255 })
+    }
 }
 
 impl Typescriptable for f32 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "number", "format": "float" })
+    }
 }
 
 impl Typescriptable for f64 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "number", "format": "double" })
+    }
 }
 
 impl Typescriptable for i32 {
     fn name() -> String {
         "number".to_string()
     }
+    fn schema() -> Value {
+        json!({ "type": "integer", "format": "int32" })
+    }
 }
 
 impl<T: Typescriptable> Typescriptable for Json<T> {
@@ -93,6 +162,12 @@ impl<T: Typescriptable> Typescriptable for Json<T> {
     fn code() -> BTreeSet<String> {
         T::code()
     }
+    fn schema() -> Value {
+        T::schema()
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        T::schemas()
+    }
 }
 
 impl<T1: Typescriptable, T2: Typescriptable> Typescriptable for (T1, T2) {
@@ -104,6 +179,19 @@ impl<T1: Typescriptable, T2: Typescriptable> Typescriptable for (T1, T2) {
         val.extend(T2::code());
         val
     }
+    fn schema() -> Value {
+        json!({
+            "type": "array",
+            "prefixItems": [T1::schema(), T2::schema()],
+            "minItems": 2,
+            "maxItems": 2,
+        })
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        let mut val = T1::schemas();
+        val.extend(T2::schemas());
+        val
+    }
 }
 
 impl<T1: Typescriptable, T2: Typescriptable, T3: Typescriptable> Typescriptable for (T1, T2, T3) {
@@ -116,6 +204,20 @@ impl<T1: Typescriptable, T2: Typescriptable, T3: Typescriptable> Typescriptable
         val.extend(T3::code());
         val
     }
+    fn schema() -> Value {
+        json!({
+            "type": "array",
+            "prefixItems": [T1::schema(), T2::schema(), T3::schema()],
+            "minItems": 3,
+            "maxItems": 3,
+        })
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        let mut val = T1::schemas();
+        val.extend(T2::schemas());
+        val.extend(T3::schemas());
+        val
+    }
 }
 
 impl<T: Typescriptable> Typescriptable for Vec<T> {
@@ -125,6 +227,12 @@ impl<T: Typescriptable> Typescriptable for Vec<T> {
     fn code() -> BTreeSet<String> {
         T::code()
     }
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        T::schemas()
+    }
 }
 
 impl<T: Typescriptable> Typescriptable for VecDeque<T> {
@@ -134,6 +242,12 @@ impl<T: Typescriptable> Typescriptable for VecDeque<T> {
     fn code() -> BTreeSet<String> {
         T::code()
     }
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        T::schemas()
+    }
 }
 
 impl<T: Typescriptable> Typescriptable for Option<T> {
@@ -143,13 +257,33 @@ impl<T: Typescriptable> Typescriptable for Option<T> {
     fn code() -> BTreeSet<String> {
         T::code()
     }
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        T::schemas()
+    }
 }
 
-impl<T: Typescriptable, E> Typescriptable for Result<T, E> {
+/// Only the success type is named, the error type is emitted so the client can type its errors.
+impl<T: Typescriptable, E: Typescriptable> Typescriptable for Result<T, E> {
     fn name() -> String {
         T::name()
     }
     fn code() -> BTreeSet<String> {
-        T::code()
+        let mut val = T::code();
+        val.extend(E::code());
+        val
+    }
+    fn schema() -> Value {
+        T::schema()
+    }
+    fn schemas() -> BTreeMap<String, Value> {
+        let mut val = T::schemas();
+        val.extend(E::schemas());
+        val
+    }
+    fn error_schema() -> Option<Value> {
+        Some(E::schema())
     }
 }
@@ -10,11 +10,12 @@ use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
     parse::Nothing, parse_macro_input, spanned::Spanned, Data, DataEnum, DataStruct, DeriveInput,
-    Error, ItemFn, Meta, NestedMeta, Pat, PatIdent, PatType, TypeParam,
+    Error, ItemFn, Lit, Meta, MetaNameValue, NestedMeta, Pat, PatIdent, PatType, TypeParam,
 };
 
 // RUSTFLAGS="-Z macro-backtrace" cargo test
 
+#[allow(clippy::too_many_lines)]
 fn handle_item_fn(node: &ItemFn) -> syn::Result<TokenStream> {
     let return_type = match node.sig.output {
         syn::ReturnType::Default => {
@@ -66,6 +67,39 @@ fn handle_item_fn(node: &ItemFn) -> syn::Result<TokenStream> {
             <#return_type as tucant_derive_lib::Typescriptable>::code()
         };
 
+        let typescriptable_arg_type_schema = quote_spanned! {arg_type.span()=>
+            <#arg_type as tucant_derive_lib::Typescriptable>::schema()
+        };
+
+        let typescriptable_arg_type_schemas = quote_spanned! {arg_type.span()=>
+            <#arg_type as tucant_derive_lib::Typescriptable>::schemas()
+        };
+
+        let typescriptable_return_type_schema = quote_spanned! {return_type.span()=>
+            <#return_type as tucant_derive_lib::Typescriptable>::schema()
+        };
+
+        let typescriptable_return_type_schemas = quote_spanned! {return_type.span()=>
+            <#return_type as tucant_derive_lib::Typescriptable>::schemas()
+        };
+
+        let typescriptable_return_type_error_schema = quote_spanned! {return_type.span()=>
+            <#return_type as tucant_derive_lib::Typescriptable>::error_schema()
+        };
+
+        // the doc comment of the handler becomes the description of the operation
+        let description = node
+            .attrs
+            .iter()
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::NameValue(MetaNameValue { path, lit: Lit::Str(lit), .. })) if path.is_ident("doc") => {
+                    Some(lit.value().trim().to_string())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
         Ok(quote! {
             #node
 
@@ -83,6 +117,42 @@ fn handle_item_fn(node: &ItemFn) -> syn::Result<TokenStream> {
                     result.extend(#typescriptable_return_type_code);
                     result
                 }
+
+                fn operation() -> tucant_derive_lib::serde_json::Value {
+                    let mut operation = tucant_derive_lib::serde_json::json!({
+                        "operationId": #name_string,
+                        "description": #description,
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": { "schema": #typescriptable_arg_type_schema },
+                            },
+                        },
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": {
+                                    "application/json": { "schema": #typescriptable_return_type_schema },
+                                },
+                            },
+                        },
+                    });
+                    if let Some(error_schema) = #typescriptable_return_type_error_schema {
+                        operation["responses"]["default"] = tucant_derive_lib::serde_json::json!({
+                            "description": "Error",
+                            "content": {
+                                "application/json": { "schema": error_schema },
+                            },
+                        });
+                    }
+                    operation
+                }
+
+                fn schemas() -> ::std::collections::BTreeMap<String, tucant_derive_lib::serde_json::Value> {
+                    let mut result = #typescriptable_arg_type_schemas;
+                    result.extend(#typescriptable_return_type_schemas);
+                    result
+                }
             }
         })
     })
@@ -93,10 +163,10 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
     let name = &input.ident;
     let name_string = input.ident.to_string();
 
-    let (members, members_code) = match &input.data {
+    let (members, members_code, members_schemas, schema) = match &input.data {
         Data::Struct(DataStruct { fields, .. }) => match fields {
             syn::Fields::Named(fields_named) => {
-                let (members, members_code) = fields_named
+                let (members, members_code, members_schemas, properties) = fields_named
                 .named
                 .iter()
                 .map(|field| {
@@ -147,6 +217,14 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                         <#field_type as tucant_derive_lib::Typescriptable>::code()
                     };
 
+                    let typescriptable_field_type_schema = quote_spanned! {field_type.span()=>
+                        <#field_type as tucant_derive_lib::Typescriptable>::schema()
+                    };
+
+                    let typescriptable_field_type_schemas = quote_spanned! {field_type.span()=>
+                        <#field_type as tucant_derive_lib::Typescriptable>::schemas()
+                    };
+
                     Ok((
                         quote! {
                            "  " + #ident_string + ": " + &#typescriptable_field_type_name + ",\n"
@@ -154,11 +232,18 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                         quote! {
                             result.extend(#typescriptable_field_type_code);
                         },
+                        quote! {
+                            result.extend(#typescriptable_field_type_schemas);
+                        },
+                        quote! {
+                            properties.insert(#ident_string.to_string(), #typescriptable_field_type_schema);
+                            required.push(#ident_string);
+                        },
                     ))
                 })
                 .collect::<Result<Vec<_>, _>>()?
                 .iter()
-                .fold((quote! {}, quote! {}), |(accx, accy), (x, y)| {
+                .fold((quote! {}, quote! {}, quote! {}, quote! {}), |(accx, accy, accz, accw), (x, y, z, w)| {
                     (
                         quote! {
                             #accx + #x
@@ -167,14 +252,38 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                             #accy
                             #y
                         },
+                        quote! {
+                            #accz
+                            #z
+                        },
+                        quote! {
+                            #accw
+                            #w
+                        },
                     )
                 });
-                (quote! {
-                    + "{\n"
-                    #members
-                    + "}"
-                }, members_code)
-            },
+                (
+                    quote! {
+                        + "{\n"
+                        #members
+                        + "}"
+                    },
+                    members_code,
+                    members_schemas,
+                    quote! {
+                        {
+                            let mut properties = tucant_derive_lib::serde_json::Map::new();
+                            let mut required: Vec<&str> = Vec::new();
+                            #properties
+                            tucant_derive_lib::serde_json::json!({
+                                "type": "object",
+                                "properties": properties,
+                                "required": required,
+                            })
+                        }
+                    },
+                )
+            }
             err => {
                 return Err(Error::new(
                     err.span(),
@@ -182,7 +291,8 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                 ))
             }
         },
-        Data::Enum(DataEnum { variants, .. }) => variants
+        Data::Enum(DataEnum { variants, .. }) => {
+            let (members, members_code, members_schemas, variants) = variants
             .iter()
             .map(|variant| match &variant.fields {
                 syn::Fields::Named(err) => Err(Error::new(
@@ -194,20 +304,35 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                     iter.next().map_or_else(|| Err(Error::new(
                             variant.fields.span(),
                             r#"exactly one field in enum allowed"#,
-                        )), |field| iter.next().map_or(Ok((variant, field)), |field| Err(Error::new(
+                        )), |field| iter.next().map_or(Ok((variant, Some(field))), |field| Err(Error::new(
                                 field.span(),
                                 r#"exactly one field in enum allowed"#,
                             ))))
                 }
-                syn::Fields::Unit => Err(Error::new(
-                    variant.fields.span(),
-                    r#"only enums with unnamed fields allowed"#,
-                )),
+                // serialized as `{ type: "Variant" }` by serde's adjacently tagged representation
+                syn::Fields::Unit => Ok((variant, None)),
             })
             .collect::<Result<Vec<_>, _>>()?
             .iter()
             .map(|(variant, field)| {
                 let ident_string = variant.ident.to_string();
+
+                let Some(field) = field else {
+                    return (
+                        quote! {
+                           "{ type: \"" + #ident_string + "\" }\n"
+                        },
+                        quote! {},
+                        quote! {},
+                        quote! {
+                            variants.push(tucant_derive_lib::serde_json::json!({
+                                "type": "object",
+                                "properties": { "type": { "const": #ident_string } },
+                                "required": ["type"],
+                            }));
+                        },
+                    );
+                };
                 let field_type = &field.ty;
 
                 let typescriptable_field_type_name = quote_spanned! {field_type.span()=>
@@ -218,6 +343,14 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                     <#field_type as tucant_derive_lib::Typescriptable>::code()
                 };
 
+                let typescriptable_field_type_schema = quote_spanned! {field_type.span()=>
+                    <#field_type as tucant_derive_lib::Typescriptable>::schema()
+                };
+
+                let typescriptable_field_type_schemas = quote_spanned! {field_type.span()=>
+                    <#field_type as tucant_derive_lib::Typescriptable>::schemas()
+                };
+
                 (
                     quote! {
                        "{ type: \"" + #ident_string + "\", value: " + &#typescriptable_field_type_name + " }\n"
@@ -225,9 +358,22 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                     quote! {
                         result.extend(#typescriptable_field_type_code);
                     },
+                    quote! {
+                        result.extend(#typescriptable_field_type_schemas);
+                    },
+                    quote! {
+                        variants.push(tucant_derive_lib::serde_json::json!({
+                            "type": "object",
+                            "properties": {
+                                "type": { "const": #ident_string },
+                                "value": #typescriptable_field_type_schema,
+                            },
+                            "required": ["type", "value"],
+                        }));
+                    },
                 )
             })
-            .fold((quote! {}, quote! {}), |(accx, accy), (x, y)| {
+            .fold((quote! {}, quote! {}, quote! {}, quote! {}), |(accx, accy, accz, accw), (x, y, z, w)| {
                 (
                     quote! {
                         #accx + " | " + #x
@@ -236,8 +382,29 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                         #accy
                         #y
                     },
+                    quote! {
+                        #accz
+                        #z
+                    },
+                    quote! {
+                        #accw
+                        #w
+                    },
                 )
-            }),
+            });
+            (
+                members,
+                members_code,
+                members_schemas,
+                quote! {
+                    {
+                        let mut variants = Vec::new();
+                        #variants
+                        tucant_derive_lib::serde_json::json!({ "oneOf": variants })
+                    }
+                },
+            )
+        }
         Data::Union(err) => {
             return Err(Error::new(
                 err.union_token.span(),
@@ -259,12 +426,7 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
         })
         .fold(quote! {}, |acc, val| {
             quote! {
-                #acc + &base64::encode_engine(
-                    #val,
-                    &base64::engine::fast_portable::FastPortable::from(
-                        &base64::alphabet::URL_SAFE,
-                        base64::engine::fast_portable::NO_PAD),
-                )
+                #acc + "_" + &tucant_derive_lib::identifier_part(&#val)
             }
         });
 
@@ -282,6 +444,17 @@ fn typescriptable_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
                 #members_code
                 result
             }
+
+            fn schema() -> tucant_derive_lib::serde_json::Value {
+                tucant_derive_lib::schema_ref(&#name #ty_generics_turbofish::name())
+            }
+
+            fn schemas() -> ::std::collections::BTreeMap<String, tucant_derive_lib::serde_json::Value> {
+                let mut result = ::std::collections::BTreeMap::new();
+                #members_schemas
+                result.insert(#name #ty_generics_turbofish::name(), #schema);
+                result
+            }
         }
     })
 }
@@ -318,4 +491,13 @@ mod tests {
         let output = prettyplease::unparse(&output);
         println!("{output}");
     }
+
+    #[test]
+    fn unit_variants() {
+        let input: DeriveInput = syn::parse_str("enum Test { Empty, WithValue(String) }").unwrap();
+        let output = typescriptable_impl(&input).unwrap();
+        let output = prettyplease::unparse(&syn::parse2::<syn::File>(output).unwrap());
+        assert!(output.contains(r#""{ type: \"""#));
+        assert!(output.contains("<String as tucant_derive_lib::Typescriptable>::name()"));
+    }
 }
@@ -0,0 +1,17 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tucant::url::parse_tucan_url;
+
+// cargo +nightly fuzz run parse_tucan_url
+fuzz_target!(|data: &str| {
+    let _ = parse_tucan_url(data);
+    // most random inputs aren't urls at all so also exercise the argument parsing directly
+    let _ = parse_tucan_url(&format!(
+        "https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&{data}"
+    ));
+});
@@ -0,0 +1,11 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+ALTER TABLE courses_unfinished
+    DROP COLUMN content_en,
+    DROP COLUMN title_en;
+
+ALTER TABLE modules_unfinished
+    DROP COLUMN content_en,
+    DROP COLUMN title_en;
@@ -0,0 +1,15 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+ALTER TABLE modules_unfinished
+    ADD COLUMN title_en TEXT NOT NULL DEFAULT '',
+    ADD COLUMN content_en TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE courses_unfinished
+    ADD COLUMN title_en TEXT NOT NULL DEFAULT '',
+    ADD COLUMN content_en TEXT NOT NULL DEFAULT '';
+
+-- refetch everything so the english texts get scraped too
+UPDATE modules_unfinished SET done = FALSE WHERE module_id != 'TUCANSCHEISS';
+UPDATE courses_unfinished SET done = FALSE;
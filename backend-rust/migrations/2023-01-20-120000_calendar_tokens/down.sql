@@ -0,0 +1,5 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DROP TABLE calendar_tokens;
@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- one secret per user for the calendar subscription feed, deleting it revokes the feed
+CREATE TABLE calendar_tokens (
+    token TEXT NOT NULL PRIMARY KEY,
+    matriculation_number INTEGER NOT NULL UNIQUE REFERENCES users_unfinished (matriculation_number),
+    created TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
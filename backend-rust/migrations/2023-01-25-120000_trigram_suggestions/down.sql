@@ -0,0 +1,16 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DROP TRIGGER exams_unfinished_examinator ON exams_unfinished;
+DROP TRIGGER course_groups_events_lecturers ON course_groups_events;
+DROP TRIGGER course_events_lecturers ON course_events;
+DROP FUNCTION insert_examinator();
+DROP FUNCTION insert_event_lecturers();
+DROP TABLE lecturers;
+
+DROP INDEX modules_unfinished_title_en_trgm;
+DROP INDEX modules_unfinished_title_trgm;
+DROP INDEX modules_unfinished_module_id_trgm;
+
+-- pg_trgm stays installed, it may have been installed before this migration
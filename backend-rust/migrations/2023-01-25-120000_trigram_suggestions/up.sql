@@ -0,0 +1,54 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX modules_unfinished_module_id_trgm ON modules_unfinished USING GIN (module_id gin_trgm_ops);
+CREATE INDEX modules_unfinished_title_trgm ON modules_unfinished USING GIN (title gin_trgm_ops);
+CREATE INDEX modules_unfinished_title_en_trgm ON modules_unfinished USING GIN (title_en gin_trgm_ops);
+
+-- the names of lecturers of events and examiners, kept up to date by the triggers below
+-- so they don't have to be split out of the events on every request
+CREATE TABLE lecturers (
+    name TEXT NOT NULL PRIMARY KEY
+);
+
+CREATE INDEX lecturers_name_trgm ON lecturers USING GIN (name gin_trgm_ops);
+
+CREATE FUNCTION insert_event_lecturers() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO lecturers (name)
+    SELECT names.name FROM regexp_split_to_table(trim(NEW.teachers), '\s*;\s*') AS names (name)
+    WHERE names.name <> ''
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION insert_examinator() RETURNS TRIGGER AS $$
+BEGIN
+    IF trim(NEW.examinator) <> '' THEN
+        INSERT INTO lecturers (name) VALUES (trim(NEW.examinator)) ON CONFLICT DO NOTHING;
+    END IF;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER course_events_lecturers AFTER INSERT OR UPDATE OF teachers ON course_events
+    FOR EACH ROW EXECUTE FUNCTION insert_event_lecturers();
+CREATE TRIGGER course_groups_events_lecturers AFTER INSERT OR UPDATE OF teachers ON course_groups_events
+    FOR EACH ROW EXECUTE FUNCTION insert_event_lecturers();
+CREATE TRIGGER exams_unfinished_examinator AFTER INSERT OR UPDATE OF examinator ON exams_unfinished
+    FOR EACH ROW EXECUTE FUNCTION insert_examinator();
+
+INSERT INTO lecturers (name)
+SELECT DISTINCT names.name FROM (
+    SELECT regexp_split_to_table(trim(teachers), '\s*;\s*') FROM course_events
+  UNION
+    SELECT regexp_split_to_table(trim(teachers), '\s*;\s*') FROM course_groups_events
+  UNION
+    SELECT trim(examinator) FROM exams_unfinished WHERE examinator IS NOT NULL
+) AS names (name)
+WHERE names.name <> ''
+ON CONFLICT DO NOTHING;
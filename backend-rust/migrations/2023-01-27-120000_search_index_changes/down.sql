@@ -0,0 +1,14 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DROP TRIGGER module_courses_search_index ON module_courses;
+DROP TRIGGER course_exams_search_index ON course_exams;
+DROP TRIGGER module_menu_module_search_index ON module_menu_module;
+DROP TRIGGER module_exams_search_index ON module_exams;
+DROP TRIGGER courses_search_index_update ON courses_unfinished;
+DROP TRIGGER courses_search_index ON courses_unfinished;
+DROP TRIGGER modules_search_index_update ON modules_unfinished;
+DROP TRIGGER modules_search_index ON modules_unfinished;
+DROP FUNCTION record_search_index_change();
+DROP TABLE search_index_changes;
@@ -0,0 +1,48 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- modules and courses whose search documents have to be updated, filled by the triggers below
+-- and emptied by the opensearch_indexer
+CREATE TABLE search_index_changes (
+    kind TEXT NOT NULL CHECK (kind IN ('module', 'course')),
+    tucan_id BYTEA NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, tucan_id)
+);
+
+-- the first argument is the kind, the second the column containing its id
+CREATE FUNCTION record_search_index_change() RETURNS TRIGGER AS $$
+DECLARE
+    changed JSONB := to_jsonb(CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END);
+BEGIN
+    INSERT INTO search_index_changes (kind, tucan_id)
+    VALUES (TG_ARGV[0], decode(substring(changed ->> TG_ARGV[1] FROM 3), 'hex'))
+    ON CONFLICT (kind, tucan_id) DO UPDATE SET changed_at = CURRENT_TIMESTAMP;
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+-- crawling updates tucan_last_checked every time, that alone doesn't change the documents
+CREATE TRIGGER modules_search_index AFTER INSERT OR DELETE ON modules_unfinished
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('module', 'tucan_id');
+CREATE TRIGGER modules_search_index_update AFTER UPDATE ON modules_unfinished
+    FOR EACH ROW WHEN ((OLD.title, OLD.module_id, OLD.credits, OLD.content, OLD.title_en, OLD.content_en)
+        IS DISTINCT FROM (NEW.title, NEW.module_id, NEW.credits, NEW.content, NEW.title_en, NEW.content_en))
+    EXECUTE FUNCTION record_search_index_change('module', 'tucan_id');
+CREATE TRIGGER courses_search_index AFTER INSERT OR DELETE ON courses_unfinished
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('course', 'tucan_id');
+CREATE TRIGGER courses_search_index_update AFTER UPDATE ON courses_unfinished
+    FOR EACH ROW WHEN ((OLD.title, OLD.course_id, OLD.sws, OLD.content, OLD.title_en, OLD.content_en)
+        IS DISTINCT FROM (NEW.title, NEW.course_id, NEW.sws, NEW.content, NEW.title_en, NEW.content_en))
+    EXECUTE FUNCTION record_search_index_change('course', 'tucan_id');
+
+-- the semesters and menus in the documents
+CREATE TRIGGER module_exams_search_index AFTER INSERT OR DELETE ON module_exams
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('module', 'module_id');
+CREATE TRIGGER module_menu_module_search_index AFTER INSERT OR DELETE ON module_menu_module
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('module', 'module_id');
+CREATE TRIGGER course_exams_search_index AFTER INSERT OR DELETE ON course_exams
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('course', 'course_id');
+CREATE TRIGGER module_courses_search_index AFTER INSERT OR DELETE ON module_courses
+    FOR EACH ROW EXECUTE FUNCTION record_search_index_change('course', 'course');
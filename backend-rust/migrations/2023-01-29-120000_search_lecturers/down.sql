@@ -0,0 +1,7 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DROP TRIGGER exams_search_index ON exams_unfinished;
+DROP TRIGGER course_events_search_index ON course_events;
+DROP FUNCTION record_lecturer_search_index_changes();
@@ -0,0 +1,34 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- the search documents contain the lecturers of a course's events and the examiners of the
+-- exams of modules and courses, the first argument is the column containing the course or exam
+CREATE FUNCTION record_lecturer_search_index_changes() RETURNS TRIGGER AS $$
+DECLARE
+    changed JSONB := to_jsonb(CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END);
+    changed_id BYTEA := decode(substring(changed ->> TG_ARGV[0] FROM 3), 'hex');
+BEGIN
+    IF TG_TABLE_NAME = 'course_events' THEN
+        INSERT INTO search_index_changes (kind, tucan_id)
+            SELECT 'course', changed_id
+          UNION
+            SELECT 'module', mc.module FROM module_courses mc WHERE mc.course = changed_id
+        ON CONFLICT (kind, tucan_id) DO UPDATE SET changed_at = CURRENT_TIMESTAMP;
+    ELSE
+        INSERT INTO search_index_changes (kind, tucan_id)
+            SELECT 'module', me.module_id FROM module_exams me WHERE me.exam = changed_id
+          UNION
+            SELECT 'course', ce.course_id FROM course_exams ce WHERE ce.exam = changed_id
+        ON CONFLICT (kind, tucan_id) DO UPDATE SET changed_at = CURRENT_TIMESTAMP;
+    END IF;
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER course_events_search_index AFTER INSERT OR DELETE OR UPDATE OF teachers ON course_events
+    FOR EACH ROW EXECUTE FUNCTION record_lecturer_search_index_changes('course');
+-- new exams are recorded when they are added to a module or course
+CREATE TRIGGER exams_search_index AFTER UPDATE OF examinator ON exams_unfinished
+    FOR EACH ROW WHEN (OLD.examinator IS DISTINCT FROM NEW.examinator)
+    EXECUTE FUNCTION record_lecturer_search_index_changes('tucan_id');
@@ -0,0 +1,17 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DELETE FROM sessions;
+
+DROP INDEX sessions_matriculation_number;
+
+ALTER TABLE sessions
+    DROP COLUMN id,
+    DROP COLUMN handle,
+    DROP COLUMN created,
+    DROP COLUMN last_used,
+    DROP COLUMN expires,
+    DROP COLUMN user_agent;
+
+ALTER TABLE sessions ADD PRIMARY KEY (matriculation_number, session_nr, session_id);
@@ -0,0 +1,20 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- the session cookie only contains the random id, TUCaN's session number and id stay here.
+-- the old cookies contain the TUCaN session itself and can't be mapped to a row
+DELETE FROM sessions;
+
+ALTER TABLE sessions DROP CONSTRAINT sessions_pkey;
+
+ALTER TABLE sessions
+    ADD COLUMN id TEXT NOT NULL PRIMARY KEY,
+    -- identifies the session when listing and revoking, unlike the id it isn't secret
+    ADD COLUMN handle BIGINT GENERATED ALWAYS AS IDENTITY UNIQUE,
+    ADD COLUMN created TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN last_used TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN expires TIMESTAMP WITH TIME ZONE NOT NULL,
+    ADD COLUMN user_agent TEXT;
+
+CREATE INDEX sessions_matriculation_number ON sessions (matriculation_number);
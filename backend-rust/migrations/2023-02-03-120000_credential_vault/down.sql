@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DROP TABLE credential_audit_log;
+DROP TABLE stored_credentials;
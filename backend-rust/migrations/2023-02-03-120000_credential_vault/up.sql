@@ -0,0 +1,23 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- TUCaN credentials users agreed to store for logging in without them, see src/credentials.rs.
+-- data_key is the random key that encrypted ciphertext, itself encrypted with the server key.
+CREATE TABLE stored_credentials (
+    matriculation_number INTEGER NOT NULL PRIMARY KEY REFERENCES users_unfinished (matriculation_number),
+    data_key BYTEA NOT NULL,
+    ciphertext BYTEA NOT NULL,
+    consented TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used TIMESTAMP WITH TIME ZONE
+);
+
+-- kept after the credentials are revoked so users can see what happened with them
+CREATE TABLE credential_audit_log (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    matriculation_number INTEGER NOT NULL REFERENCES users_unfinished (matriculation_number),
+    event TEXT NOT NULL CHECK (event IN ('stored', 'used', 'login_failed', 'revoked')),
+    created TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX credential_audit_log_matriculation_number ON credential_audit_log (matriculation_number);
@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- the ids can't be recovered from their hashes
+DELETE FROM sessions;
@@ -0,0 +1,6 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- the cookie keeps the id, only its SHA-256 hash is stored so leaked rows can't be used as cookies
+UPDATE sessions SET id = encode(sha256(convert_to(id, 'UTF8')), 'hex');
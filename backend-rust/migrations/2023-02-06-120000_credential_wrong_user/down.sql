@@ -0,0 +1,10 @@
+-- SPDX-FileCopyrightText: The tucant Contributors
+--
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+DELETE FROM credential_audit_log WHERE event = 'wrong_user';
+
+ALTER TABLE credential_audit_log DROP CONSTRAINT credential_audit_log_event_check;
+
+ALTER TABLE credential_audit_log ADD CONSTRAINT credential_audit_log_event_check
+    CHECK (event IN ('stored', 'used', 'login_failed', 'revoked'));
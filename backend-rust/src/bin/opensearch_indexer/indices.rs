@@ -0,0 +1,269 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use opensearch::indices::{
+    IndicesCreateParts, IndicesDeleteParts, IndicesGetAliasParts, IndicesGetParts,
+};
+use serde_json::{json, Value};
+use tucant::search_backend::{OpensearchSearch, COURSES_ALIAS, MODULES_ALIAS};
+
+// https://codarium.substack.com/p/designing-an-optimal-multi-language
+// https://opensearch.org/docs/latest/opensearch/query-dsl/full-text/
+// https://opensearch.org/docs/latest/opensearch/query-dsl/text-analyzers
+
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-htmlstrip-charfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-lang-analyzer.html#english-analyzer
+
+// TODO FIXME searching for "Funktional" doesnt highlight body because of no matches?
+// no it seems like its some other weird stuff
+
+// TODO https://www.elastic.co/guide/en/elasticsearch/reference/current/test-analyzer.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-dict-decomp-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-hyp-decomp-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stemmer-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-snowball-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-hunspell-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-multiplexer-tokenfilter.html
+// https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-remove-duplicates-tokenfilter.html
+
+// maybe multiplex, then german and english and then combine?
+
+// http://localhost:5601/app/dev_tools#/console
+/*
+GET tucant_modules/_analyze
+{
+  "analyzer" : "my_german",
+  "text" : "Funktional Funktionale Funktionalen"
+}
+*/
+
+/// The German and English analyzers used by all indices.
+fn settings() -> Value {
+    json!({
+        "analysis": {
+            "analyzer": {
+                "my_english": {
+                    "tokenizer": "standard",
+                    "filter": [
+                        "english_possessive_stemmer",
+                        "lowercase",
+                        "english_stop",
+                        "german_stop",
+                        "english_stemmer"
+                    ],
+                    "char_filter": [
+                        "html_strip"
+                    ]
+                },
+                "my_german": {
+                    "tokenizer": "standard",
+                    "filter": [
+                        "lowercase",
+                        "german_stop",
+                        "english_stop",
+                        // "german_normalization",
+                        "german_hunspell",
+                        "german_snowball"
+                    ],
+                    "char_filter": [
+                        "html_strip"
+                    ]
+                }
+            },
+            "filter": {
+                "english_stop": {
+                    "type": "stop",
+                    "stopwords": "_english_"
+                },
+                "english_stemmer": {
+                    "type": "stemmer",
+                    "language": "english"
+                },
+                "english_possessive_stemmer": {
+                    "type": "stemmer",
+                    "language": "possessive_english"
+                },
+                "german_stop": {
+                    "type": "stop",
+                    "stopwords": "_german_"
+                },
+                "german_stemmer": {
+                    "type": "stemmer",
+                    "language": "german"
+                },
+                "german_snowball": {
+                    "type": "snowball",
+                    "language": "German"
+                },
+                "german_hunspell": {
+                    "type": "hunspell",
+                    "locale": "de_DE",
+                }
+            }
+        }
+    })
+}
+
+/// A text with a German and an English version.
+fn localized_text() -> Value {
+    json!({
+        "properties": {
+            "de": {
+                "term_vector": "with_positions_offsets",
+                "type": "text",
+                "analyzer": "my_german"
+            },
+            "en": {
+                "term_vector": "with_positions_offsets",
+                "type": "text",
+                "analyzer": "my_english"
+            },
+        }
+    })
+}
+
+fn modules_index() -> Value {
+    json!({
+        "settings": settings(),
+        "mappings": {
+            "properties": {
+                "module_id": { "type": "keyword" },
+                "credits": { "type": "integer" },
+                "semesters": { "type": "keyword" },
+                "menus": { "type": "keyword" },
+                "lecturers": { "type": "text" },
+                "suggest": { "type": "completion" },
+                "content": localized_text(),
+                "title": localized_text(),
+            }
+        }
+    })
+}
+
+fn courses_index() -> Value {
+    json!({
+        "settings": settings(),
+        "mappings": {
+            "properties": {
+                "course_id": { "type": "keyword" },
+                "sws": { "type": "short" },
+                "semesters": { "type": "keyword" },
+                "menus": { "type": "keyword" },
+                "lecturers": { "type": "text" },
+                "content": localized_text(),
+                "title": localized_text(),
+            }
+        }
+    })
+}
+
+async fn check(response: opensearch::http::response::Response) -> anyhow::Result<()> {
+    if let Some(exception) = response.exception().await? {
+        anyhow::bail!("{exception:?}");
+    }
+    Ok(())
+}
+
+/// Creates the indices `opensearch` writes to.
+pub async fn create(opensearch: &OpensearchSearch) -> anyhow::Result<()> {
+    for (index, body) in [
+        (opensearch.modules_index(), modules_index()),
+        (opensearch.courses_index(), courses_index()),
+    ] {
+        let response = opensearch
+            .client()
+            .indices()
+            .create(IndicesCreateParts::Index(index))
+            .body(body)
+            .send()
+            .await?;
+        check(response).await?;
+    }
+    Ok(())
+}
+
+/// The indices the alias points to, empty if it doesn't exist.
+async fn alias_targets(opensearch: &OpensearchSearch, alias: &str) -> anyhow::Result<Vec<String>> {
+    let response = opensearch
+        .client()
+        .indices()
+        .get_alias(IndicesGetAliasParts::Name(&[alias]))
+        .send()
+        .await?;
+    if response.status_code().as_u16() == 404 {
+        return Ok(Vec::new());
+    }
+    let response_body = response.error_for_status_code()?.json::<Value>().await?;
+    Ok(response_body
+        .as_object()
+        .map(|indices| indices.keys().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// Whether both aliases exist, otherwise there is nothing to update incrementally.
+pub async fn aliases_exist(opensearch: &OpensearchSearch) -> anyhow::Result<bool> {
+    Ok(!alias_targets(opensearch, MODULES_ALIAS).await?.is_empty()
+        && !alias_targets(opensearch, COURSES_ALIAS).await?.is_empty())
+}
+
+/// Points both aliases to the indices `opensearch` writes to in one request, so searches never
+/// see a half filled index or modules and courses from different rebuilds.
+pub async fn switch_aliases(opensearch: &OpensearchSearch) -> anyhow::Result<()> {
+    let mut actions = Vec::new();
+    for (alias, index) in [
+        (MODULES_ALIAS, opensearch.modules_index()),
+        (COURSES_ALIAS, opensearch.courses_index()),
+    ] {
+        for old in alias_targets(opensearch, alias).await? {
+            actions.push(json!({ "remove": { "index": old, "alias": alias } }));
+        }
+        actions.push(json!({ "add": { "index": index, "alias": alias } }));
+    }
+
+    let response = opensearch
+        .client()
+        .indices()
+        .update_aliases()
+        .body(json!({ "actions": actions }))
+        .send()
+        .await?;
+    check(response).await
+}
+
+/// Deletes the indices of previous rebuilds, the aliases have to be switched before.
+pub async fn delete_old(opensearch: &OpensearchSearch) -> anyhow::Result<Vec<String>> {
+    let patterns = [format!("{MODULES_ALIAS}_*"), format!("{COURSES_ALIAS}_*")];
+    let response = opensearch
+        .client()
+        .indices()
+        .get(IndicesGetParts::Index(&[&patterns[0], &patterns[1]]))
+        .send()
+        .await?
+        .error_for_status_code()?;
+    let response_body = response.json::<Value>().await?;
+
+    let old: Vec<String> = response_body
+        .as_object()
+        .into_iter()
+        .flat_map(|indices| indices.keys())
+        .filter(|index| {
+            *index != opensearch.modules_index() && *index != opensearch.courses_index()
+        })
+        .cloned()
+        .collect();
+    if old.is_empty() {
+        return Ok(old);
+    }
+
+    let response = opensearch
+        .client()
+        .indices()
+        .delete(IndicesDeleteParts::Index(
+            &old.iter().map(String::as_str).collect::<Vec<_>>(),
+        ))
+        .send()
+        .await?;
+    check(response).await?;
+    Ok(old)
+}
@@ -2,278 +2,197 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use diesel::QueryDsl;
+mod indices;
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use diesel::{ExpressionMethods, QueryDsl};
 use diesel_async::RunQueryDsl;
-use itertools::Itertools;
-use opensearch::{
-    http::request::JsonBody, indices::IndicesCreateParts, params::Refresh, BulkParts,
-};
+use log::info;
 use rand::Rng;
 
-use serde_json::{json, Value};
 use tucant::{
-    models::{Module, MODULES_UNFINISHED},
-    schema::modules_unfinished,
-    tucan::Tucan,
+    config::Config,
+    ids::{CourseId, ModuleId},
+    schema::search_index_changes,
+    search_backend::{course_documents, module_documents, OpensearchSearch, SearchBackend},
+    telemetry::Traced,
+    tucan::{DbPool, Tucan},
+    MyError,
 };
 
-// $HOME/.cargo/bin/diesel database reset && cargo run --bin test_client
-#[allow(clippy::too_many_lines)]
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+/// How many changed modules or courses are indexed at once.
+const BATCH_SIZE: i64 = 500;
+
+/// Keeps the OpenSearch indices in sync with the database
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Rebuild the indices from scratch and switch the aliases to them, this also happens if
+    /// the aliases don't exist yet
+    #[arg(long)]
+    full: bool,
+
+    /// Keep running and index the changes every this many seconds
+    #[arg(long)]
+    watch: Option<u64>,
+}
 
-    // Dashboard index pattern needs to not include timestamp
+fn error(err: MyError) -> anyhow::Error {
+    anyhow::anyhow!("{err}")
+}
 
-    env_logger::init();
+/// A row of `search_index_changes`: the kind, the id and when it changed.
+type Change = (String, Vec<u8>, DateTime<Utc>);
+
+/// The oldest `limit` changes, all if `limit` is `None`.
+async fn pending_changes(pool: &DbPool, limit: Option<i64>) -> anyhow::Result<Vec<Change>> {
+    let mut connection = pool.get().await?;
+    let mut query = search_index_changes::table
+        .select((
+            search_index_changes::kind,
+            search_index_changes::tucan_id,
+            search_index_changes::changed_at,
+        ))
+        .order_by(search_index_changes::changed_at)
+        .into_boxed();
+    if let Some(limit) = limit {
+        query = query.limit(limit);
+    }
+    Ok(query.load::<Change>(&mut connection).traced().await?)
+}
 
-    // https://codarium.substack.com/p/designing-an-optimal-multi-language
-    // https://opensearch.org/docs/latest/opensearch/query-dsl/full-text/
-    // https://opensearch.org/docs/latest/opensearch/query-dsl/text-analyzers
+/// Removes exactly these changes, so changes of the same module or course that were made after
+/// reading them stay.
+async fn remove_changes(pool: &DbPool, changes: &[Change]) -> anyhow::Result<()> {
+    let mut connection = pool.get().await?;
+    for (kind, tucan_id, changed_at) in changes {
+        diesel::delete(search_index_changes::table)
+            .filter(search_index_changes::kind.eq(kind))
+            .filter(search_index_changes::tucan_id.eq(tucan_id))
+            .filter(search_index_changes::changed_at.eq(changed_at))
+            .execute(&mut connection)
+            .traced()
+            .await?;
+    }
+    Ok(())
+}
 
-    let tucan = Tucan::new()?;
+/// Fills new indices with everything in the database and switches the aliases to them.
+async fn rebuild(opensearch: &OpensearchSearch, pool: &DbPool) -> anyhow::Result<()> {
+    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
 
     let mut rng = rand::thread_rng();
-
-    let rand_string: String = (0..10)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
+    let suffix: String = (0..10)
+        .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
         .collect();
+    let target = opensearch.clone().with_indices(
+        format!("{}_{suffix}", opensearch.modules_index()),
+        format!("{}_{suffix}", opensearch.courses_index()),
+    );
+
+    // the changes are read before the documents so they are all contained in the new indices,
+    // changes that are committed later may be missing and stay for the next run
+    let changes = pending_changes(pool, None).await?;
+
+    indices::create(&target).await?;
+    let modules = module_documents(pool, None).await.map_err(error)?;
+    target.index_modules(&modules).await.map_err(error)?;
+    let courses = course_documents(pool, None).await.map_err(error)?;
+    target.index_courses(&courses).await.map_err(error)?;
+    info!(
+        "indexed {} modules and {} courses into {} and {}",
+        modules.len(),
+        courses.len(),
+        target.modules_index(),
+        target.courses_index()
+    );
+
+    indices::switch_aliases(&target).await?;
+    let deleted = indices::delete_old(&target).await?;
+    info!("switched the aliases and deleted {deleted:?}");
+
+    remove_changes(pool, &changes).await
+}
 
-    let index_name: String = format!("tucant_modules_{rand_string}");
-
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-htmlstrip-charfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-lang-analyzer.html#english-analyzer
-
-    // TODO FIXME searching for "Funktional" doesnt highlight body because of no matches?
-    // no it seems like its some other weird stuff
-
-    // TODO https://www.elastic.co/guide/en/elasticsearch/reference/current/test-analyzer.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-dict-decomp-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-hyp-decomp-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-stemmer-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-snowball-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-hunspell-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-multiplexer-tokenfilter.html
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/analysis-remove-duplicates-tokenfilter.html
-
-    // maybe multiplex, then german and english and then combine?
-
-    // http://localhost:5601/app/dev_tools#/console
-    /*
-    GET tucant_modules/_analyze
-    {
-      "analyzer" : "my_german",
-      "text" : "Funktional Funktionale Funktionalen"
-    }
-    */
+/// Indexes or deletes the modules and courses in `search_index_changes` and removes them from
+/// it, unless they changed again in the meantime.
+async fn apply_changes(opensearch: &OpensearchSearch, pool: &DbPool) -> anyhow::Result<usize> {
+    let mut applied = 0;
+    loop {
+        let changes = pending_changes(pool, Some(BATCH_SIZE)).await?;
+
+        let module_ids = changes
+            .iter()
+            .filter(|(kind, _, _)| kind == "module")
+            .map(|(_, id, _)| ModuleId::from_bytes(id.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let modules = module_documents(pool, Some(&module_ids))
+            .await
+            .map_err(error)?;
+        opensearch.index_modules(&modules).await.map_err(error)?;
+        for id in &module_ids {
+            if !modules
+                .iter()
+                .any(|document| &document.module.tucan_id == id)
+            {
+                opensearch.delete_module(id).await.map_err(error)?;
+            }
+        }
 
-    let response = tucan
-        .opensearch
-        .indices()
-        .create(IndicesCreateParts::Index(&index_name))
-        .body(json!({
-            "settings": {
-                "analysis": {
-                    "analyzer": {
-                        "my_english": {
-                            "tokenizer": "standard",
-                            "filter": [
-                                "english_possessive_stemmer",
-                                "lowercase",
-                                "english_stop",
-                                "german_stop",
-                                "english_stemmer"
-                            ],
-                            "char_filter": [
-                                "html_strip"
-                            ]
-                        },
-                        "my_german": {
-                            "tokenizer": "standard",
-                            "filter": [
-                                "lowercase",
-                                "german_stop",
-                                "english_stop",
-                                // "german_normalization",
-                                "german_hunspell",
-                                "german_snowball"
-                            ],
-                            "char_filter": [
-                                "html_strip"
-                            ]
-                        }
-                    },
-                    "filter": {
-                        "english_stop": {
-                            "type": "stop",
-                            "stopwords": "_english_"
-                        },
-                        "english_stemmer": {
-                            "type": "stemmer",
-                            "language": "english"
-                        },
-                        "english_possessive_stemmer": {
-                            "type": "stemmer",
-                            "language": "possessive_english"
-                        },
-                        "german_stop": {
-                            "type": "stop",
-                            "stopwords": "_german_"
-                        },
-                        "german_stemmer": {
-                            "type": "stemmer",
-                            "language": "german"
-                        },
-                        "german_snowball": {
-                            "type": "snowball",
-                            "language": "German"
-                        },
-                        "german_hunspell": {
-                            "type": "hunspell",
-                            "locale": "de_DE",
-                        }
-                    }
-                }
-            },
-            "mappings": {
-                "properties": {
-                    "content": {
-                        "type": "text",
-                        "term_vector": "with_positions_offsets",
-                        "fields": {
-                            "de": {
-                                "term_vector": "with_positions_offsets",
-                                "type": "text",
-                                "analyzer": "my_german"
-                            },
-                            "en": {
-                                "term_vector": "with_positions_offsets",
-                                "type": "text",
-                                "analyzer": "my_english"
-                            },
-                        }
-                    },
-                    "title": {
-                        "type": "text",
-                        "term_vector": "with_positions_offsets",
-                        "fields": {
-                            "de": {
-                                "term_vector": "with_positions_offsets",
-                                "type": "text",
-                                "analyzer": "my_german"
-                            },
-                            "en": {
-                                "term_vector": "with_positions_offsets",
-                                "type": "text",
-                                "analyzer": "my_english"
-                            },
-                        }
-                    }
-                }
+        let course_ids = changes
+            .iter()
+            .filter(|(kind, _, _)| kind == "course")
+            .map(|(_, id, _)| CourseId::from_bytes(id.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let courses = course_documents(pool, Some(&course_ids))
+            .await
+            .map_err(error)?;
+        opensearch.index_courses(&courses).await.map_err(error)?;
+        for id in &course_ids {
+            if !courses
+                .iter()
+                .any(|document| &document.course.tucan_id == id)
+            {
+                opensearch.delete_course(id).await.map_err(error)?;
             }
         }
-        ))
-        .send()
-        .await?;
 
-    let exception = response.exception().await?;
-    if let Some(exception) = exception {
-        Err(anyhow::anyhow!("{:?}", exception))?;
-    }
+        remove_changes(pool, &changes).await?;
 
-    // let response_body = response.json::<Value>().await?;
-    //println!("{:?}", response_body);
+        applied += changes.len();
+        if changes.len() < usize::try_from(BATCH_SIZE)? {
+            return Ok(applied);
+        }
+    }
+}
 
-    let mut connection = tucan.pool.get().await?;
-    let modules: Vec<Module> = modules_unfinished::table
-        .select(MODULES_UNFINISHED)
-        .load::<Module>(&mut connection)
-        .await?;
+// $HOME/.cargo/bin/diesel database reset && cargo run --bin test_client
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Dashboard index pattern needs to not include timestamp
 
-    let body: Vec<JsonBody<_>> = modules
-        .into_iter()
-        .flat_map(|m| {
-            let base64_tucan_id = base64::encode_engine(
-                &m.tucan_id,
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD,
-                ),
-            );
-            [
-                json!({"index": {"_id": base64_tucan_id}}).into(),
-                json!({
-                    "id": base64_tucan_id,
-                    "last_checked": m.tucan_last_checked,
-                    "title": m.title,
-                    "module_id": m.module_id,
-                    "credits": m.credits,
-                    "content": m.content
-                })
-                .into(),
-            ]
-            .into_iter()
-        })
-        .collect_vec();
+    env_logger::init();
+    let cli = Cli::parse();
 
-    let response = tucan
-        .opensearch
-        .bulk(BulkParts::Index(&index_name))
-        .refresh(Refresh::WaitFor)
-        .body(body)
-        .send()
-        .await?;
+    let config = Config::load()?;
+    let tucan = Tucan::new(&config)?;
+    let opensearch = OpensearchSearch::new(&config.opensearch, tucan.pool.clone())?;
 
-    let exception = response.exception().await?;
-    if let Some(exception) = exception {
-        Err(anyhow::anyhow!("{:?}", exception))?;
+    if cli.full || !indices::aliases_exist(&opensearch).await? {
+        rebuild(&opensearch, &tucan.pool).await?;
     }
 
-    // https://www.elastic.co/guide/en/elasticsearch/reference/current/search-suggesters.html#completion-suggester
-
-    let response = tucan
-        .opensearch
-        .indices()
-        .update_aliases()
-        .body(json!({
-              "actions": [
-          {
-            "remove": {
-              "index": "tucant_modules_*",
-              "alias": "tucant_modules"
-            }
-          },
-          {
-              "add": {
-                  "index": index_name,
-                  "alias": "tucant_modules"
-              }
-          }
-        ]
-          }))
-        .send()
-        .await?;
-
-    let exception = response.exception().await?;
-    if let Some(exception) = exception {
-        Err(anyhow::anyhow!("{:?}", exception))?;
+    loop {
+        let applied = apply_changes(&opensearch, &tucan.pool).await?;
+        if applied > 0 {
+            info!("indexed {applied} changes");
+        }
+        let Some(seconds) = cli.watch else {
+            return Ok(());
+        };
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
     }
-
-    let response = tucan
-        .opensearch
-        .indices()
-        .get(opensearch::indices::IndicesGetParts::Index(&[
-            "tucant_modules_*",
-        ]))
-        .send()
-        .await?;
-
-    let _response_body = response.json::<Value>().await?;
-    //println!("{}", response_body);
-    // TODO FIXME delete indexes here
-
-    Ok(())
 }
@@ -1,27 +1,40 @@
 // SPDX-FileCopyrightText: The tucant Contributors
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
+mod s_calendar;
 mod s_course;
 mod s_coursegroup;
+mod s_credentials;
 mod s_exam;
 mod s_get_modules;
+mod s_graphql;
+mod s_health;
 mod s_module;
 mod s_my_courses;
 mod s_my_exams;
 mod s_my_modules;
+mod s_search;
 mod s_search_course;
 mod s_search_module;
+mod s_sessions;
 mod s_setup;
+mod s_takeout;
 mod utils;
 
+use axum::body::Body;
 use axum::Json;
 
 use axum::extract::FromRef;
+use axum::extract::MatchedPath;
+use axum::middleware::{from_fn, from_fn_with_state};
 
 use axum::extract::Query;
 use axum::extract::State;
 
+use axum::http::header::USER_AGENT;
+use axum::http::HeaderMap;
 use axum::http::HeaderValue;
+use axum::http::Request;
 use axum::response::IntoResponse;
 use axum::response::IntoResponseParts;
 use axum::response::Redirect;
@@ -34,9 +47,7 @@ use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::cookie::Key;
 use axum_extra::extract::PrivateCookieJar;
 use diesel::{Connection, PgConnection};
-use diesel_migrations::FileBasedMigrations;
 use diesel_migrations::MigrationHarness;
-use dotenvy::dotenv;
 
 use file_lock::FileLock;
 use file_lock::FileOptions;
@@ -46,35 +57,44 @@ use reqwest::header::ACCEPT;
 use reqwest::header::AUTHORIZATION;
 use reqwest::header::CONTENT_TYPE;
 use reqwest::Method;
-use reqwest::StatusCode;
+use s_calendar::calendar;
+use s_calendar::create_calendar_token;
+use s_calendar::revoke_calendar_token;
+use s_calendar::CreateCalendarTokenTs;
+use s_calendar::RevokeCalendarTokenTs;
 use s_course::course;
 use s_get_modules::get_modules;
+use s_graphql::graphql;
+use s_health::{healthz, readyz};
 use s_module::module;
 use s_my_courses::my_courses;
 use s_my_courses::MyCoursesTs;
 use s_my_modules::my_modules;
+use s_search::search;
+use s_search::SearchTs;
+use s_search::{similar_modules, SimilarModulesTs};
+use s_search::{suggest, SuggestTs};
 use s_search_course::search_course;
 use s_search_course::SearchCourseTs;
 use s_search_module::search_module;
-use s_search_module::SearchModuleOpensearchTs;
 
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
-use std::net::SocketAddr;
+use std::collections::{BTreeMap, BTreeSet};
 
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
-use tracing::warn;
-use tucant::schema::{sessions, users_unfinished};
-use tucant::MyError;
+use tracing::{debug, warn};
+use tucant::config::Config;
+use tucant::csrf::{require_csrf_header, CSRF_HEADER};
+use tucant::graphql::TucantSchema;
+use tucant::schema::users_unfinished;
+use tucant::session::{self, reissue_cookie, SessionId, SessionKeys, SESSION_COOKIE};
+use tucant::telemetry::{self, Traced};
+use tucant::{MyError, TucanError, MIGRATIONS};
 
 use tucant::models::{TucanSession, UndoneUser};
 
-use tokio::{
-    fs::{self, OpenOptions},
-    io::AsyncWriteExt,
-};
 use tucant::typescript::TypescriptableApp;
 
 use std::io::Write;
@@ -87,6 +107,12 @@ use tucant_derive_lib::Typescriptable;
 use crate::s_course::CourseTs;
 use crate::s_coursegroup::course_group;
 use crate::s_coursegroup::CourseGroupTs;
+use crate::s_credentials::credentials;
+use crate::s_credentials::revoke_credentials;
+use crate::s_credentials::store_credentials;
+use crate::s_credentials::CredentialsTs;
+use crate::s_credentials::RevokeCredentialsTs;
+use crate::s_credentials::StoreCredentialsTs;
 use crate::s_exam::exam;
 use crate::s_exam::ExamTs;
 use crate::s_get_modules::GetModulesTs;
@@ -94,9 +120,16 @@ use crate::s_module::ModuleTs;
 use crate::s_my_exams::my_exams;
 use crate::s_my_exams::MyExamsTs;
 use crate::s_my_modules::MyModulesTs;
-use crate::s_search_module::search_module_opensearch;
 use crate::s_search_module::SearchModuleTs;
+use crate::s_sessions::revoke_session;
+use crate::s_sessions::sessions;
+use crate::s_sessions::RevokeSessionTs;
+use crate::s_sessions::SessionsTs;
 use crate::s_setup::setup;
+use crate::s_takeout::delete_account;
+use crate::s_takeout::takeout;
+use crate::s_takeout::DeleteAccountTs;
+use crate::s_takeout::TakeoutTs;
 
 #[derive(Serialize, Typescriptable)]
 pub struct WithTucanUrl<T: Typescriptable> {
@@ -134,26 +167,52 @@ impl<H: IntoResponseParts, V: IntoResponse + Typescriptable> Typescriptable for
     fn code() -> BTreeSet<String> {
         V::code()
     }
+    fn schema() -> serde_json::Value {
+        V::schema()
+    }
+    fn schemas() -> BTreeMap<String, serde_json::Value> {
+        V::schemas()
+    }
 }
 
 // https://docs.rs/axum-extra/latest/axum_extra/extract/struct.PrivateCookieJar.html
 #[ts]
 async fn login(
     cookie_jar: PrivateCookieJar,
+    headers: HeaderMap,
     tucan: State<Tucan>,
+    config: State<Config>,
     input: Json<Login>,
 ) -> Result<TsHide<PrivateCookieJar, Json<LoginResult>>, MyError> {
-    let tucan_user = tucan.login(&input.username, &input.password).await?;
-    let cookie_jar = cookie_jar.add(Cookie::new(
-        "session",
-        serde_json::to_string(&tucan_user.session)?,
-    ));
+    let tucan_user = match tucan.login(&input.username, &input.password).await {
+        Ok(tucan_user) => tucan_user,
+        Err(err) if err.downcast_ref() == Some(&TucanError::InvalidCredentials) => {
+            return Ok(TsHide {
+                hidden: cookie_jar,
+                visible: Json(LoginResult { success: false }),
+            });
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let id = session::create(
+        &tucan.pool,
+        &config,
+        &tucan_user.session,
+        user_agent(&headers),
+    )
+    .await?;
     Ok(TsHide {
-        hidden: cookie_jar,
+        hidden: cookie_jar.add(session::cookie(id)),
         visible: Json(LoginResult { success: true }),
     })
 }
 
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct LoginHack {
     pub session_nr: Option<i64>,
@@ -163,12 +222,14 @@ struct LoginHack {
 
 async fn login_hack(
     mut cookie_jar: PrivateCookieJar,
+    headers: HeaderMap,
     tucan: State<Tucan>,
+    config: State<Config>,
     input: Query<LoginHack>,
 ) -> Result<Response, MyError> {
     use diesel_async::RunQueryDsl;
 
-    println!("{input:?}");
+    debug!("login hack {input:?}");
 
     let mut connection = tucan.pool.get().await?;
 
@@ -181,78 +242,42 @@ async fn login_hack(
         let tucan_user = tucan
             .tucan_session_from_session_data(session_nr, session_id)
             .await?;
-        let tucan_session = tucan_user.session.clone();
-        let user = UndoneUser::new(tucan_user.session.matriculation_number);
-        connection
-            .build_transaction()
-            .run(|mut connection| {
-                Box::pin(async move {
-                    diesel::insert_into(users_unfinished::table)
-                        .values(user)
-                        .on_conflict(users_unfinished::matriculation_number)
-                        .do_nothing()
-                        .execute(&mut connection)
-                        .await?;
-
-                    diesel::insert_into(sessions::table)
-                        .values(tucan_session)
-                        .on_conflict((
-                            sessions::matriculation_number,
-                            sessions::session_nr,
-                            sessions::session_id,
-                        ))
-                        .do_nothing()
-                        .execute(&mut connection)
-                        .await?;
-
-                    Ok::<(), diesel::result::Error>(())
-                })
-            })
+        diesel::insert_into(users_unfinished::table)
+            .values(UndoneUser::new(tucan_user.session.matriculation_number))
+            .on_conflict(users_unfinished::matriculation_number)
+            .do_nothing()
+            .execute(&mut connection)
+            .traced()
             .await?;
-        cookie_jar = cookie_jar.add(Cookie::new(
-            "session",
-            serde_json::to_string(&tucan_user.session)?,
-        ));
+        let id = session::create(
+            &tucan.pool,
+            &config,
+            &tucan_user.session,
+            user_agent(&headers),
+        )
+        .await?;
+        cookie_jar = cookie_jar.add(session::cookie(id));
     }
 
-    let url = match parse_tucan_url(&input.redirect).program {
-        tucant::url::TucanProgram::Registration(registration) => Redirect::to(&format!(
-            "http://localhost:5173/modules/{}",
-            base64::encode_engine(
-                registration.path,
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD
-                )
-            )
-        )),
+    let redirect = parse_tucan_url(&input.redirect)?;
+
+    let url = match redirect.program {
+        tucant::url::TucanProgram::Registration(registration) => {
+            Redirect::to(&config.frontend(&format!("/modules/{}", registration.path)))
+        }
         tucant::url::TucanProgram::RootRegistration(_) => {
-            Redirect::to("http://localhost:5173/modules/")
+            Redirect::to(&config.frontend("/modules/"))
         }
-        tucant::url::TucanProgram::Moduledetails(module_details) => Redirect::to(&format!(
-            "http://localhost:5173/module/{}",
-            base64::encode_engine(
-                module_details.id,
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD
-                )
-            )
-        )),
-        tucant::url::TucanProgram::Coursedetails(course_details) => Redirect::to(&format!(
-            "http://localhost:5173/course/{}",
-            base64::encode_engine(
-                course_details.id,
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD
-                )
-            )
-        )),
-        tucant::url::TucanProgram::Externalpages(_) => Redirect::to("http://localhost:5173/"),
+        tucant::url::TucanProgram::Moduledetails(module_details) => {
+            Redirect::to(&config.frontend(&format!("/module/{}", module_details.id)))
+        }
+        tucant::url::TucanProgram::Coursedetails(course_details) => {
+            Redirect::to(&config.frontend(&format!("/course/{}", course_details.id)))
+        }
+        tucant::url::TucanProgram::Externalpages(_) => Redirect::to(&config.frontend("/")),
         other => {
-            println!("{other:?}");
-            return Ok(StatusCode::NOT_FOUND.into_response());
+            debug!("login hack to unsupported page {other:?}");
+            return Err(MyError::NotFound);
         }
     };
 
@@ -260,14 +285,22 @@ async fn login_hack(
 }
 
 #[ts]
-#[allow(clippy::unused_async)]
 async fn logout(
+    session_id: Option<SessionId>,
     cookie_jar: PrivateCookieJar,
+    tucan: State<Tucan>,
     _input: Json<()>,
 ) -> Result<TsHide<PrivateCookieJar, Json<()>>, MyError> {
-    let cookie_jar = cookie_jar.remove(Cookie::named("session"));
+    if let Some(SessionId(id)) = session_id {
+        if let Some(session) = session::delete(&tucan.pool, &id).await? {
+            // the session is gone for us anyway, TUCaN expires it eventually
+            if let Err(err) = tucan.continue_session(session).logout().await {
+                warn!("failed to log out at TUCaN: {err:#}");
+            }
+        }
+    }
     Ok(TsHide {
-        hidden: cookie_jar,
+        hidden: cookie_jar.remove(Cookie::named(SESSION_COOKIE)),
         visible: Json(()),
     })
 }
@@ -285,79 +318,69 @@ async fn index(
     )))
 }
 
+#[allow(clippy::unused_async)]
+async fn metrics() -> Result<Response, MyError> {
+    let headers = [(CONTENT_TYPE, prometheus::TEXT_FORMAT)];
+    Ok((headers, telemetry::render()?).into_response())
+}
+
 #[derive(Clone, FromRef)]
 struct AppState {
-    key: Key,
+    session_keys: SessionKeys,
     tucan: Tucan,
+    config: Config,
+    graphql_schema: TucantSchema,
+}
+
+/// For [`PrivateCookieJar`], which can only decrypt cookies with the current key.
+impl FromRef<AppState> for Key {
+    fn from_ref(state: &AppState) -> Self {
+        state.session_keys.current.clone()
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    dotenv().ok();
-    env_logger::init();
-
-    /*
-        // https://crates.io/crates/tracing
-
-        let tracer = opentelemetry_otlp::new_pipeline()
-            .tracing()
-            .with_exporter(opentelemetry_otlp::new_exporter().tonic()) // with_endpoint("http://localhost:")
-            .install_batch(opentelemetry::runtime::Tokio)?;
-
-        // Create a tracing layer with the configured tracer
-        let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-
-        // Use the tracing subscriber `Registry`, or any other subscriber
-        // that impls `LookupSpan`
-        let subscriber = Registry::default().with(telemetry);
-
-        tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-    */
+    let config = Config::load()?;
+    telemetry::init(&config)?;
 
     warn!("Starting server...");
 
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let migrations = FileBasedMigrations::find_migrations_directory()?;
-    let mut connection = PgConnection::establish(&database_url)
-        .unwrap_or_else(|_| panic!("Error connecting to {database_url}"));
-    connection.run_pending_migrations(migrations).unwrap();
-
-    let random_secret_key = Key::generate();
-
-    let file = OpenOptions::new()
-        .create_new(true)
-        .write(true)
-        .append(true)
-        .open("sessions.key")
-        .await;
-    if let Ok(mut file) = file {
-        file.write_all(random_secret_key.master()).await?;
-        drop(file);
-    }
+    let mut connection = PgConnection::establish(&config.database_url)
+        .unwrap_or_else(|_| panic!("Error connecting to {}", config.database_url));
+    connection.run_pending_migrations(MIGRATIONS).unwrap();
 
-    let secret_key_raw = fs::read("sessions.key").await?;
-    let secret_key = Key::from(&secret_key_raw);
+    let session_keys = SessionKeys::load(&config)?;
 
-    let tucan = Tucan::new()?;
+    let tucan = Tucan::new(&config)?;
 
     let app_state = AppState {
-        key: secret_key,
+        session_keys: session_keys.clone(),
+        graphql_schema: tucant::graphql::schema(tucan.pool.clone()),
         tucan,
+        config: config.clone(),
     };
 
     let app: Router<AppState> = Router::new()
         .with_state(app_state.clone())
-        .route("/setup", post(setup))
-        .route("/login-hack", get(login_hack));
+        .route("/setup", post(setup).layer(from_fn(require_csrf_header)))
+        .route("/login-hack", get(login_hack))
+        .route(
+            "/graphql",
+            post(graphql).layer(from_fn(require_csrf_header)),
+        )
+        .route("/calendar/:token", get(calendar))
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz));
 
     let app = TypescriptableApp {
         app,
         codes: BTreeSet::new(),
+        paths: BTreeMap::new(),
+        schemas: BTreeMap::new(),
     };
 
-    // TODO FIXME csrf protection
-
-    // TODO FIXME these settings are dangerous
     let cors = CorsLayer::new()
         // allow `GET` and `POST` when accessing the resource
         .allow_methods([Method::GET, Method::POST])
@@ -366,24 +389,31 @@ async fn main() -> anyhow::Result<()> {
             AUTHORIZATION,
             ACCEPT,
             CONTENT_TYPE,
-            "x-csrf-protection".parse::<HeaderName>().unwrap(),
+            CSRF_HEADER.parse::<HeaderName>().unwrap(),
         ])
-        // allow requests from any origin
-        .allow_origin([
-            "http://127.0.0.1:5173".parse::<HeaderValue>().unwrap(),
-            "http://localhost:5173".parse::<HeaderValue>().unwrap(),
-        ]);
+        // validated when loading the config
+        .allow_origin(
+            config
+                .cors_origins
+                .iter()
+                .map(|origin| origin.parse::<HeaderValue>().unwrap())
+                .collect::<Vec<_>>(),
+        );
 
     let app = app
         .route::<IndexTs>("/", post(index))
         .route::<LoginTs>("/login", post(login))
         .route::<LogoutTs>("/logout", post(logout))
+        .route::<SessionsTs>("/sessions", post(sessions))
+        .route::<RevokeSessionTs>("/revoke-session", post(revoke_session))
+        .route::<CredentialsTs>("/credentials", post(credentials))
+        .route::<StoreCredentialsTs>("/store-credentials", post(store_credentials))
+        .route::<RevokeCredentialsTs>("/revoke-credentials", post(revoke_credentials))
         .route::<GetModulesTs>("/modules", post(get_modules))
         .route::<SearchModuleTs>("/search-modules", post(search_module))
-        .route::<SearchModuleOpensearchTs>(
-            "/search-modules-opensearch",
-            post(search_module_opensearch),
-        )
+        .route::<SearchTs>("/search", post(search))
+        .route::<SuggestTs>("/suggest", post(suggest))
+        .route::<SimilarModulesTs>("/similar-modules", post(similar_modules))
         .route::<SearchCourseTs>("/search-course", post(search_course))
         .route::<CourseTs>("/course", post(course))
         .route::<CourseGroupTs>("/course-group", post(course_group))
@@ -391,47 +421,69 @@ async fn main() -> anyhow::Result<()> {
         .route::<ExamTs>("/exam", post(exam))
         .route::<MyExamsTs>("/my-exams", post(my_exams))
         .route::<MyModulesTs>("/my-modules", post(my_modules))
-        .route::<MyCoursesTs>("/my-courses", post(my_courses));
-
-    let should_we_block = true;
-    let lock_for_writing = FileOptions::new().write(true).create(true).truncate(true);
-
-    let mut filelock = match FileLock::lock(
-        "../frontend-react/src/api.ts",
-        should_we_block,
-        lock_for_writing,
-    ) {
-        Ok(lock) => lock,
-        Err(err) => panic!("Error getting write lock: {err}"),
-    };
-
-    filelock
-        .file
-        .write_all(
-            (r#"// This file is automatically generated at startup. Do not modify.
+        .route::<MyCoursesTs>("/my-courses", post(my_courses))
+        .route::<CreateCalendarTokenTs>("/calendar-token", post(create_calendar_token))
+        .route::<RevokeCalendarTokenTs>("/revoke-calendar-token", post(revoke_calendar_token))
+        .route::<TakeoutTs>("/takeout", post(takeout))
+        .route::<DeleteAccountTs>("/delete-account", post(delete_account));
+
+    let openapi = app.openapi();
+
+    if let Some(typescript_output) = &config.typescript_output {
+        let should_we_block = true;
+        let lock_for_writing = FileOptions::new().write(true).create(true).truncate(true);
+
+        let mut filelock =
+            match FileLock::lock(typescript_output, should_we_block, lock_for_writing) {
+                Ok(lock) => lock,
+                Err(err) => panic!("Error getting write lock: {err}"),
+            };
+
+        filelock
+            .file
+            .write_all(
+                (r#"// This file is automatically generated at startup. Do not modify.
 import { genericFetch } from "./api_base"
 "#
-            .to_string()
-                + &app.codes.into_iter().join("\n"))
-                .as_bytes(),
-        )
-        .unwrap();
+                .to_string()
+                    + &app.codes.into_iter().join("\n"))
+                    .as_bytes(),
+            )
+            .unwrap();
 
-    filelock.unlock().unwrap();
+        filelock.unlock().unwrap();
+    }
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
+    let addr = config.listen_address;
     tracing::debug!("listening on {}", addr);
     axum::Server::bind(&addr)
         .serve(
             app.app
+                .route("/openapi.json", get(|| async { Json(openapi) }))
                 .with_state::<()>(app_state)
+                .layer(from_fn_with_state(session_keys, reissue_cookie))
                 .layer(cors)
                 //.layer(CompressionLayer::new()) // https://github.com/tower-rs/tower-http/issues/292
-                .layer(TraceLayer::new_for_http())
+                .layer(
+                    TraceLayer::new_for_http().make_span_with(|request: &Request<Body>| {
+                        // the route instead of the path so spans of the same handler are grouped
+                        let route = request
+                            .extensions()
+                            .get::<MatchedPath>()
+                            .map_or(request.uri().path(), MatchedPath::as_str);
+                        tracing::info_span!(
+                            "request",
+                            method = %request.method(),
+                            route,
+                            otel.name = %format!("{} {route}", request.method()),
+                        )
+                    }),
+                )
                 .into_make_service(),
         )
         .await
         .unwrap();
 
+    telemetry::shutdown();
     Ok(())
 }
@@ -0,0 +1,255 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::AppState;
+use tucant::MyError;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Json;
+use chrono::Utc;
+use diesel::upsert::excluded;
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use tucant::config::Config;
+use tucant::ical::{Calendar, Event};
+use tucant::models::{
+    CalendarToken, Course, CourseEvent, Exam, Language, Localize, Module, TucanSession,
+    COURSES_UNFINISHED, MODULES_UNFINISHED,
+};
+use tucant::schema::{
+    calendar_tokens, course_events, course_exams, course_groups_events, course_groups_unfinished,
+    courses_unfinished, exams_unfinished, module_exams, modules_unfinished, user_course_groups,
+    user_courses, user_exams,
+};
+use tucant::telemetry::Traced;
+use tucant::tucan::Tucan;
+use tucant_derive::ts;
+
+/// Creates a new secret url for the calendar subscription, the old one stops working.
+/// Returns the absolute url that calendar apps subscribe to.
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn create_calendar_token(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    config: State<Config>,
+    _input: Json<()>,
+) -> Result<Json<String>, MyError> {
+    let mut connection = tucan.pool.get().await?;
+
+    let token = CalendarToken {
+        token: rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+        matriculation_number: session.matriculation_number,
+        created: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(calendar_tokens::table)
+        .values(&token)
+        .on_conflict(calendar_tokens::matriculation_number)
+        .do_update()
+        .set((
+            calendar_tokens::token.eq(excluded(calendar_tokens::token)),
+            calendar_tokens::created.eq(excluded(calendar_tokens::created)),
+        ))
+        .execute(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(Json(
+        config.public(&format!("/calendar/{}.ics", token.token)),
+    ))
+}
+
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn revoke_calendar_token(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<Json<()>, MyError> {
+    let mut connection = tucan.pool.get().await?;
+
+    diesel::delete(calendar_tokens::table)
+        .filter(calendar_tokens::matriculation_number.eq(session.matriculation_number))
+        .execute(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(Json(()))
+}
+
+fn course_event(title: String, event: CourseEvent) -> Event {
+    Event {
+        uid: format!(
+            "course-{}-{}-{}-{}@tucant",
+            event.course,
+            event.timestamp_start.timestamp(),
+            event.timestamp_end.timestamp(),
+            event.room
+        ),
+        start: event.timestamp_start,
+        end: Some(event.timestamp_end),
+        summary: title,
+        location: Some(event.room),
+        description: Some(event.teachers),
+    }
+}
+
+fn exam_events(title: &str, exam: Exam, language: Language) -> Vec<Event> {
+    let (registration_end, unregistration_end) = match language {
+        Language::German => ("Anmeldeschluss", "Abmeldeschluss"),
+        Language::English => ("Registration deadline", "Unregistration deadline"),
+    };
+
+    let mut events = vec![
+        Event {
+            uid: format!("exam-{}-registration-end@tucant", exam.tucan_id),
+            start: exam.registration_end,
+            end: None,
+            summary: format!("{registration_end}: {title} ({})", exam.exam_type),
+            location: None,
+            description: None,
+        },
+        Event {
+            uid: format!("exam-{}-unregistration-end@tucant", exam.tucan_id),
+            start: exam.unregistration_end,
+            end: None,
+            summary: format!("{unregistration_end}: {title} ({})", exam.exam_type),
+            location: None,
+            description: None,
+        },
+    ];
+
+    if let Some(start) = exam.exam_time_start {
+        events.push(Event {
+            uid: format!("exam-{}@tucant", exam.tucan_id),
+            start,
+            end: exam.exam_time_end,
+            summary: format!("{title} ({})", exam.exam_type),
+            location: exam.room,
+            description: exam.examinator,
+        });
+    }
+
+    events
+}
+
+/// The calendar subscription, authenticated only by the secret token in the url
+/// so calendar apps can fetch it without a session.
+pub async fn calendar(
+    tucan: State<Tucan>,
+    language: Language,
+    Path(token): Path<String>,
+) -> Result<Response, MyError> {
+    let mut connection = tucan.pool.get().await?;
+
+    let token = token.trim_end_matches(".ics");
+
+    let Some(matriculation_number) = calendar_tokens::table
+        .filter(calendar_tokens::token.eq(token))
+        .select(calendar_tokens::matriculation_number)
+        .get_result::<i32>(&mut connection)
+        .traced()
+        .await
+        .optional()?
+    else {
+        return Err(MyError::NotFound);
+    };
+
+    let course_events = user_courses::table
+        .filter(user_courses::user_id.eq(matriculation_number))
+        .inner_join(courses_unfinished::table.inner_join(course_events::table))
+        .select((COURSES_UNFINISHED, course_events::all_columns))
+        .load::<(Course, CourseEvent)>(&mut connection)
+        .traced()
+        .await?;
+
+    let course_group_events = user_course_groups::table
+        .filter(user_course_groups::user_id.eq(matriculation_number))
+        .inner_join(
+            course_groups_unfinished::table
+                .inner_join(course_groups_events::table)
+                .inner_join(courses_unfinished::table),
+        )
+        .select((
+            COURSES_UNFINISHED,
+            course_groups_unfinished::title,
+            course_groups_events::all_columns,
+        ))
+        // the columns are the same as for course events
+        .load::<(Course, String, CourseEvent)>(&mut connection)
+        .traced()
+        .await?;
+
+    let module_exams = user_exams::table
+        .filter(user_exams::matriculation_number.eq(matriculation_number))
+        .inner_join(
+            exams_unfinished::table
+                .inner_join(module_exams::table.inner_join(modules_unfinished::table)),
+        )
+        .select((MODULES_UNFINISHED, exams_unfinished::all_columns))
+        .load::<(Module, Exam)>(&mut connection)
+        .traced()
+        .await?;
+
+    let course_exams = user_exams::table
+        .filter(user_exams::matriculation_number.eq(matriculation_number))
+        .inner_join(
+            exams_unfinished::table
+                .inner_join(course_exams::table.inner_join(courses_unfinished::table)),
+        )
+        .select((COURSES_UNFINISHED, exams_unfinished::all_columns))
+        .load::<(Course, Exam)>(&mut connection)
+        .traced()
+        .await?;
+
+    let mut events = course_events
+        .into_iter()
+        .map(|(course, event)| course_event(course.localize(language).title, event))
+        .chain(
+            course_group_events
+                .into_iter()
+                .map(|(course, course_group, event)| {
+                    course_event(
+                        format!("{} ({course_group})", course.localize(language).title),
+                        event,
+                    )
+                }),
+        )
+        .chain(module_exams.into_iter().flat_map(|(module, exam)| {
+            exam_events(&module.localize(language).title, exam, language)
+        }))
+        .chain(course_exams.into_iter().flat_map(|(course, exam)| {
+            exam_events(&course.localize(language).title, exam, language)
+        }))
+        .collect::<Vec<_>>();
+
+    // an exam can belong to a module and a course at the same time
+    events.sort_by(|a, b| a.uid.cmp(&b.uid));
+    events.dedup_by(|a, b| a.uid == b.uid);
+
+    let calendar = Calendar {
+        name: "TUCaN".to_string(),
+        timestamp: Utc::now().naive_utc(),
+        events,
+    };
+
+    Ok((
+        [(CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar.to_string(),
+    )
+        .into_response())
+}
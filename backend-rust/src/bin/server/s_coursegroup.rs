@@ -8,8 +8,11 @@ use crate::WithTucanUrl;
 use axum::extract::State;
 use axum::Json;
 
+use tucant::ids::CourseId;
 use tucant::models::CourseGroup;
 use tucant::models::CourseGroupEvent;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::TucanSession;
 
 use tucant::url::Coursedetails;
@@ -23,33 +26,21 @@ use tucant_derive::ts;
 pub async fn course_group(
     session: TucanSession,
     tucan: State<Tucan>,
-    input: Json<String>,
+    language: Language,
+    input: Json<CourseId>,
 ) -> Result<Json<WithTucanUrl<(Course, CourseGroup, Vec<CourseGroupEvent>)>>, MyError> {
-    let binary_path = base64::decode_engine(
-        input.as_bytes(),
-        &base64::engine::fast_portable::FastPortable::from(
-            &base64::alphabet::URL_SAFE,
-            base64::engine::fast_portable::NO_PAD,
-        ),
-    )
-    .unwrap();
-
     let tucan = tucan.continue_session(session.clone());
 
-    let url = Coursedetails {
-        id: binary_path.clone(),
-    };
+    let url = Coursedetails::new(input.0);
 
     let course_group = tucan.course_group(url.clone()).await?;
     let course = tucan
-        .course(Coursedetails {
-            id: course_group.0.course.clone(),
-        })
+        .course(Coursedetails::new(course_group.0.course.clone()))
         .await?;
 
     Ok(Json(WithTucanUrl {
         tucan_url: Into::<TucanProgram>::into(url)
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap())),
-        inner: (course.0, course_group.0, course_group.1),
+            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
+        inner: (course.0, course_group.0, course_group.1).localize(language),
     }))
 }
@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::AppState;
+use tucant::MyError;
+
+use axum::extract::State;
+use axum::Json;
+use serde::Deserialize;
+use tracing::warn;
+use tucant::credentials::{self, CredentialEvent, Credentials};
+use tucant::models::{CredentialStatus, TucanSession};
+use tucant::tucan::Tucan;
+use tucant::TucanError;
+use tucant_derive::{ts, Typescriptable};
+
+#[derive(Deserialize, Typescriptable)]
+pub struct StoreCredentials {
+    username: String,
+    password: String,
+    /// The user agreed that tucant logs in with these credentials on its own.
+    consent: bool,
+}
+
+impl std::fmt::Debug for StoreCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreCredentials")
+            .field("username", &self.username)
+            .field("consent", &self.consent)
+            .finish_non_exhaustive()
+    }
+}
+
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn credentials(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<Json<CredentialStatus>, MyError> {
+    Ok(Json(
+        credentials::status(&tucan.pool, session.matriculation_number).await?,
+    ))
+}
+
+/// Stores the TUCaN credentials so background jobs can log in again once the session expired.
+/// They are checked first and have to belong to the logged in user.
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn store_credentials(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    input: Json<StoreCredentials>,
+) -> Result<Json<()>, MyError> {
+    if !input.consent {
+        return Err(MyError::BadRequest(
+            "storing credentials needs consent".to_string(),
+        ));
+    }
+    let key = tucan
+        .credential_key
+        .as_ref()
+        .ok_or_else(|| MyError::BadRequest("storing credentials is disabled".to_string()))?;
+
+    let user = match tucan.login(&input.username, &input.password).await {
+        Ok(user) => user,
+        Err(err) if err.downcast_ref() == Some(&TucanError::InvalidCredentials) => {
+            return Err(MyError::BadRequest(
+                "invalid username or password".to_string(),
+            ));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let matches = user.session.matriculation_number == session.matriculation_number;
+    if let Err(err) = user.logout().await {
+        warn!("failed to log out at TUCaN: {err:#}");
+    }
+    if !matches {
+        return Err(MyError::BadRequest(
+            "the credentials belong to another user".to_string(),
+        ));
+    }
+
+    credentials::store(
+        &tucan.pool,
+        key,
+        session.matriculation_number,
+        &Credentials {
+            username: input.0.username,
+            password: input.0.password,
+        },
+    )
+    .await?;
+    Ok(Json(()))
+}
+
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn revoke_credentials(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<Json<()>, MyError> {
+    credentials::revoke(
+        &tucan.pool,
+        session.matriculation_number,
+        CredentialEvent::Revoked,
+    )
+    .await?;
+    Ok(Json(()))
+}
@@ -8,8 +8,11 @@ use crate::WithTucanUrl;
 use axum::extract::State;
 use axum::Json;
 
+use tucant::ids::ExamId;
 use tucant::models::Course;
 use tucant::models::Exam;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::Module;
 use tucant::models::TucanSession;
 
@@ -24,28 +27,18 @@ use tucant_derive::ts;
 pub async fn exam(
     session: TucanSession,
     tucan: State<Tucan>,
-    input: Json<String>,
+    language: Language,
+    input: Json<ExamId>,
 ) -> Result<Json<WithTucanUrl<(Exam, Vec<Module>, Vec<Course>)>>, MyError> {
-    let binary_path = base64::decode_engine(
-        input.as_bytes(),
-        &base64::engine::fast_portable::FastPortable::from(
-            &base64::alphabet::URL_SAFE,
-            base64::engine::fast_portable::NO_PAD,
-        ),
-    )
-    .unwrap();
-
     let tucan = tucan.continue_session(session.clone());
 
-    let url = Examdetails {
-        id: binary_path.clone(),
-    };
+    let url = Examdetails::new(input.0);
 
     let result = tucan.exam_details(url.clone()).await?;
 
     Ok(Json(WithTucanUrl {
         tucan_url: Into::<TucanProgram>::into(url)
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap())),
-        inner: result,
+            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
+        inner: result.localize(language),
     }))
 }
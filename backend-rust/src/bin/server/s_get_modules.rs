@@ -16,9 +16,13 @@ use diesel::sql_types::Bytea;
 
 use diesel_async::RunQueryDsl;
 
+use tucant::ids::MenuPath;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::ModuleMenuPathPart;
 use tucant::models::ModuleMenuResponse;
 use tucant::models::TucanSession;
+use tucant::telemetry::Traced;
 use tucant::tucan::Tucan;
 use tucant::url::Registration;
 use tucant::url::RootRegistration;
@@ -29,7 +33,8 @@ use tucant_derive::ts;
 pub async fn get_modules(
     session: TucanSession,
     tucan: State<Tucan>,
-    input: Json<Option<String>>,
+    language: Language,
+    input: Json<Option<MenuPath>>,
 ) -> Result<Json<WithTucanUrl<ModuleMenuResponse>>, MyError> {
     let tucan = tucan.continue_session(session.clone());
 
@@ -46,19 +51,8 @@ pub async fn get_modules(
             }
         }
         Some(ref input) => {
-            let binary_path = base64::decode_engine(
-                input.as_bytes(),
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD,
-                ),
-            )
-            .unwrap();
-            let (module_menu, subentries) = tucan
-                .registration(Registration {
-                    path: binary_path.clone(),
-                })
-                .await?;
+            let (module_menu, subentries) =
+                tucan.registration(Registration::new(input.clone())).await?;
 
             let mut connection = tucan.tucan.pool.get().await?;
 
@@ -75,37 +69,31 @@ pub async fn get_modules(
                         SELECT * FROM search_tree;
         "#,
             )
-            .bind::<Bytea, _>(binary_path)
+            .bind::<Bytea, _>(input)
             .load::<ModuleMenuPathPart>(&mut connection)
+            .traced()
             .await?;
 
             let paths = calculate_paths(&path_to_root);
 
             ModuleMenuResponse {
                 module_menu,
-                entries: subentries,
+                entries: tucant::models::Registration {
+                    modules_and_courses: subentries.modules_and_courses.localize(language),
+                    ..subentries
+                },
                 path: paths,
             }
         }
     };
 
     let url: TucanProgram = input.0.as_ref().map_or_else(
-        || RootRegistration {}.into(),
-        |input| {
-            let binary_path = base64::decode_engine(
-                input.as_bytes(),
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD,
-                ),
-            )
-            .unwrap();
-            Registration { path: binary_path }.into()
-        },
+        || RootRegistration::default().into(),
+        |input| Registration::new(input.clone()).into(),
     );
 
     Ok(Json(WithTucanUrl {
-        tucan_url: url.to_tucan_url(Some(session.session_nr.try_into().unwrap())),
+        tucan_url: url.to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
         inner: value,
     }))
 }
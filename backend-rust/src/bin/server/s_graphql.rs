@@ -0,0 +1,24 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::AppState;
+
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::State;
+use tucant::graphql::TucantSchema;
+use tucant::models::{Language, TucanSession};
+
+/// Not a `#[ts]` route because the frontend would use a GraphQL client for it.
+#[axum::debug_handler(state=AppState)]
+pub async fn graphql(
+    session: Option<TucanSession>,
+    language: Language,
+    schema: State<TucantSchema>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    schema
+        .execute(request.into_inner().data(language).data(session))
+        .await
+        .into()
+}
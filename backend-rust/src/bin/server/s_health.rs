@@ -0,0 +1,29 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Value};
+use tucant::config::Config;
+use tucant::health::readiness;
+use tucant::tucan::Tucan;
+
+/// Answers as long as the process is running, without looking at any dependency.
+#[allow(clippy::unused_async)]
+pub async fn healthz() -> Json<Value> {
+    Json(json!({ "ok": true }))
+}
+
+/// The checks of all dependencies, with status 503 if one of them failed.
+pub async fn readyz(tucan: State<Tucan>, config: State<Config>) -> Response {
+    let readiness = readiness(&tucan, &config).await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
@@ -14,9 +14,13 @@ use diesel::sql_types::Bytea;
 use diesel::sql_query;
 use diesel_async::RunQueryDsl;
 
+use tucant::ids::ModuleId;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::ModuleMenuPathPart;
 use tucant::models::ModuleResponse;
 use tucant::models::TucanSession;
+use tucant::telemetry::Traced;
 use tucant::tucan::Tucan;
 use tucant::url::Moduledetails;
 use tucant::url::TucanProgram;
@@ -27,27 +31,14 @@ use tucant_derive::ts;
 pub async fn module(
     session: TucanSession,
     tucan: State<Tucan>,
-    input: Json<String>,
+    language: Language,
+    input: Json<ModuleId>,
 ) -> Result<Json<WithTucanUrl<ModuleResponse>>, MyError> {
     let mut connection = tucan.pool.get().await?;
 
-    let binary_path = base64::decode_engine(
-        input.as_bytes(),
-        &base64::engine::fast_portable::FastPortable::from(
-            &base64::alphabet::URL_SAFE,
-            base64::engine::fast_portable::NO_PAD,
-        ),
-    )
-    .unwrap();
-
     let tucan = tucan.continue_session(session.clone());
 
-    let result = tucan
-        .module(Moduledetails {
-            id: binary_path.clone(),
-        })
-        .await?
-        .0;
+    let result = tucan.module(Moduledetails::new(input.0.clone())).await?.0;
 
     let path_to_root: Vec<ModuleMenuPathPart> = sql_query(
             r#"
@@ -62,25 +53,24 @@ pub async fn module(
                 SELECT * FROM search_tree;
 "#,
         )
-        .bind::<Bytea, _>(binary_path.clone())
+        .bind::<Bytea, _>(&input.0)
         .load::<ModuleMenuPathPart>(&mut connection)
+        .traced()
         .await?;
 
     let paths = calculate_paths(&path_to_root);
 
     let result = ModuleResponse {
-        module: result,
+        module: result.localize(language),
         path: paths,
     };
 
     Ok(Json(WithTucanUrl {
-        tucan_url: if *input == "TUCANSCHEISS" {
+        tucan_url: if input.is_tucanscheiss() {
             "https://github.com/mohe2015/tucant/issues/104".to_string()
         } else {
-            Into::<TucanProgram>::into(Moduledetails {
-                id: binary_path.clone(),
-            })
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))
+            Into::<TucanProgram>::into(Moduledetails::new(input.0))
+                .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?
         },
         inner: result,
     }))
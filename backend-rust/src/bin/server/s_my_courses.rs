@@ -9,6 +9,8 @@ use tucant::MyError;
 use axum::extract::State;
 use axum::Json;
 
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::TucanSession;
 
 use tucant::tucan::Tucan;
@@ -22,6 +24,7 @@ use tucant_derive::ts;
 pub async fn my_courses(
     session: TucanSession,
     tucan: State<Tucan>,
+    language: Language,
     _input: Json<()>,
 ) -> Result<Json<WithTucanUrl<Vec<CourseOrCourseGroup>>>, MyError> {
     let tucan = tucan.continue_session(session.clone());
@@ -29,8 +32,8 @@ pub async fn my_courses(
     let result = tucan.my_courses().await?;
 
     Ok(Json(WithTucanUrl {
-        tucan_url: Into::<TucanProgram>::into(Profcourses)
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap())),
-        inner: result,
+        tucan_url: Into::<TucanProgram>::into(Profcourses::default())
+            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
+        inner: result.localize(language),
     }))
 }
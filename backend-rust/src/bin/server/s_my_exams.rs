@@ -10,6 +10,8 @@ use axum::extract::State;
 use axum::Json;
 use tucant::models::Course;
 use tucant::models::Exam;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::Module;
 use tucant::models::TucanSession;
 use tucant::tucan::Tucan;
@@ -22,6 +24,7 @@ use tucant_derive::ts;
 pub async fn my_exams(
     session: TucanSession,
     tucan: State<Tucan>,
+    language: Language,
     _input: Json<()>,
 ) -> Result<Json<WithTucanUrl<(Vec<(Module, Exam)>, Vec<(Course, Exam)>)>>, MyError> {
     let tucan = tucan.continue_session(session.clone());
@@ -29,8 +32,8 @@ pub async fn my_exams(
     let result = tucan.my_exams().await?;
 
     Ok(Json(WithTucanUrl {
-        tucan_url: Into::<TucanProgram>::into(Mymodules)
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap())),
-        inner: result,
+        tucan_url: Into::<TucanProgram>::into(Mymodules::default())
+            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
+        inner: result.localize(language),
     }))
 }
@@ -8,6 +8,8 @@ use tucant::MyError;
 
 use axum::extract::State;
 use axum::Json;
+use tucant::models::Language;
+use tucant::models::Localize;
 use tucant::models::Module;
 use tucant::models::TucanSession;
 use tucant::tucan::Tucan;
@@ -20,6 +22,7 @@ use tucant_derive::ts;
 pub async fn my_modules(
     session: TucanSession,
     tucan: State<Tucan>,
+    language: Language,
     _input: Json<()>,
 ) -> Result<Json<WithTucanUrl<Vec<Module>>>, MyError> {
     let tucan = tucan.continue_session(session.clone());
@@ -27,8 +30,8 @@ pub async fn my_modules(
     let result = tucan.my_modules().await?;
 
     Ok(Json(WithTucanUrl {
-        tucan_url: Into::<TucanProgram>::into(Mymodules)
-            .to_tucan_url(Some(session.session_nr.try_into().unwrap())),
-        inner: result,
+        tucan_url: Into::<TucanProgram>::into(Mymodules::default())
+            .to_tucan_url(Some(session.session_nr.try_into().unwrap()))?,
+        inner: result.localize(language),
     }))
 }
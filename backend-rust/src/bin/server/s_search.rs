@@ -0,0 +1,70 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use tucant::MyError;
+
+use axum::extract::State;
+use axum::Json;
+
+use tucant::{
+    ids::ModuleId,
+    models::{Language, TucanSession},
+    search::{
+        SearchHit, SimilarModules, SimilarModulesQuery, Suggestion, UnifiedSearchQuery,
+        UnifiedSearchResponse,
+    },
+    tucan::Tucan,
+};
+use tucant_derive::ts;
+
+/// Searches modules, courses, course groups, exams and lecturers at once.
+#[ts]
+pub async fn search(
+    _: TucanSession,
+    tucan: State<Tucan>,
+    input: Json<UnifiedSearchQuery>,
+) -> Result<Json<UnifiedSearchResponse>, MyError> {
+    Ok(Json(tucan.search.search_unified(&input.0).await?))
+}
+
+/// Completions for what was typed into the search field so far.
+#[ts]
+pub async fn suggest(
+    _: TucanSession,
+    tucan: State<Tucan>,
+    language: Language,
+    input: Json<String>,
+) -> Result<Json<Vec<Suggestion>>, MyError> {
+    Ok(Json(tucan.search.suggest(&input.0, language).await?))
+}
+
+/// Modules similar to a module, e.g. to find alternatives to a module the user liked.
+#[ts]
+pub async fn similar_modules(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    language: Language,
+    input: Json<SimilarModulesQuery>,
+) -> Result<Json<Vec<SearchHit<ModuleId>>>, MyError> {
+    let user = tucan.continue_session(session);
+    let menu = if input.registrable_only {
+        Some(user.root_registration().await?.tucan_id)
+    } else {
+        None
+    };
+    let excluded = if input.exclude_registered {
+        user.my_modules()
+            .await?
+            .into_iter()
+            .map(|module| module.tucan_id)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let similar = SimilarModules::new(&input.0, menu, excluded);
+    Ok(Json(
+        tucan.search.similar_modules(&similar, language).await?,
+    ))
+}
@@ -0,0 +1,45 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::AppState;
+use tucant::MyError;
+
+use axum::extract::State;
+use axum::Json;
+use tracing::warn;
+use tucant::models::{SessionInfo, TucanSession};
+use tucant::session::{self, SessionId};
+use tucant::tucan::Tucan;
+use tucant_derive::ts;
+
+/// The sessions of the user, including the one making the request.
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn sessions(
+    SessionId(session_id): SessionId,
+    session: TucanSession,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<Json<Vec<SessionInfo>>, MyError> {
+    Ok(Json(
+        session::list(&tucan.pool, session.matriculation_number, &session_id).await?,
+    ))
+}
+
+/// Ends the session with the handle from [`sessions`], also at TUCaN.
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn revoke_session(
+    session: TucanSession,
+    tucan: State<Tucan>,
+    input: Json<i64>,
+) -> Result<Json<()>, MyError> {
+    let revoked = session::revoke(&tucan.pool, session.matriculation_number, input.0).await?;
+
+    if let Err(err) = tucan.continue_session(revoked).logout().await {
+        warn!("failed to log out at TUCaN: {err:#}");
+    }
+
+    Ok(Json(()))
+}
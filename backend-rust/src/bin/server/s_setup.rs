@@ -59,9 +59,7 @@ async fn fetch_registration(
         fetch_registration(
             stream,
             tucan_clone.clone(),
-            Registration {
-                path: menu.tucan_id,
-            },
+            Registration::new(menu.tucan_id),
             modules_or_courses,
         )
         .await;
@@ -71,9 +69,7 @@ async fn fetch_registration(
         match modules_or_courses {
             ModulesOrCourses::Modules => {
                 let module = tucan
-                    .module(Moduledetails {
-                        id: module.0.tucan_id.clone(),
-                    })
+                    .module(Moduledetails::new(module.0.tucan_id.clone()))
                     .await
                     .unwrap();
                 stream
@@ -86,9 +82,7 @@ async fn fetch_registration(
 
                 for course in module.1 {
                     match tucan
-                        .course_or_course_group(Coursedetails {
-                            id: course.tucan_id.clone(),
-                        })
+                        .course_or_course_group(Coursedetails::new(course.tucan_id.clone()))
                         .await
                         .unwrap()
                     {
@@ -122,9 +116,7 @@ pub async fn setup(
         fetch_registration(
             &mut stream,
             tucan,
-            Registration {
-                path: root.tucan_id,
-            },
+            Registration::new(root.tucan_id),
             ModulesOrCourses::Modules,
         )
         .await;
@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::AppState;
+use crate::TsHide;
+use tucant::MyError;
+
+use axum::extract::State;
+use axum::Json;
+use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::PrivateCookieJar;
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use serde::Serialize;
+use tracing::warn;
+use tucant::credentials;
+use tucant::models::{
+    CalendarToken, Course, CourseGroup, CredentialStatus, Exam, Module, SessionInfo, TucanSession,
+    User, COURSES_UNFINISHED, MODULES_UNFINISHED, USERS_UNFINISHED,
+};
+use tucant::schema::{
+    calendar_tokens, course_groups_unfinished, courses_unfinished, credential_audit_log,
+    exams_unfinished, modules_unfinished, sessions, stored_credentials, user_course_groups,
+    user_courses, user_exams, user_modules, users_unfinished,
+};
+use tucant::session::{self, SessionId, SESSION_COOKIE};
+use tucant::telemetry::Traced;
+use tucant::tucan::Tucan;
+use tucant_derive::{ts, Typescriptable};
+
+/// Everything we store that is linked to a matriculation number.
+/// The modules, courses and exams themselves are shared by all users.
+#[derive(Serialize, Debug, Typescriptable)]
+pub struct Takeout {
+    user: Option<User>,
+    sessions: Vec<SessionInfo>,
+    /// Whether TUCaN credentials are stored, they themselves are not included.
+    credentials: CredentialStatus,
+    calendar_token: Option<CalendarToken>,
+    modules: Vec<Module>,
+    courses: Vec<Course>,
+    course_groups: Vec<CourseGroup>,
+    exams: Vec<Exam>,
+}
+
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn takeout(
+    SessionId(session_id): SessionId,
+    session: TucanSession,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<Json<Takeout>, MyError> {
+    let mut connection = tucan.pool.get().await?;
+    let matriculation_number = session.matriculation_number;
+
+    let user = users_unfinished::table
+        .filter(users_unfinished::matriculation_number.eq(matriculation_number))
+        .select(USERS_UNFINISHED)
+        .get_result::<User>(&mut connection)
+        .traced()
+        .await
+        .optional()?;
+
+    let sessions = session::list(&tucan.pool, matriculation_number, &session_id).await?;
+
+    let credentials = credentials::status(&tucan.pool, matriculation_number).await?;
+
+    let calendar_token = calendar_tokens::table
+        .filter(calendar_tokens::matriculation_number.eq(matriculation_number))
+        .get_result::<CalendarToken>(&mut connection)
+        .traced()
+        .await
+        .optional()?;
+
+    let modules = user_modules::table
+        .filter(user_modules::user_id.eq(matriculation_number))
+        .inner_join(modules_unfinished::table)
+        .select(MODULES_UNFINISHED)
+        .load::<Module>(&mut connection)
+        .traced()
+        .await?;
+
+    let courses = user_courses::table
+        .filter(user_courses::user_id.eq(matriculation_number))
+        .inner_join(courses_unfinished::table)
+        .select(COURSES_UNFINISHED)
+        .load::<Course>(&mut connection)
+        .traced()
+        .await?;
+
+    let course_groups = user_course_groups::table
+        .filter(user_course_groups::user_id.eq(matriculation_number))
+        .inner_join(course_groups_unfinished::table)
+        .select(course_groups_unfinished::all_columns)
+        .load::<CourseGroup>(&mut connection)
+        .traced()
+        .await?;
+
+    let exams = user_exams::table
+        .filter(user_exams::matriculation_number.eq(matriculation_number))
+        .inner_join(exams_unfinished::table)
+        .select(exams_unfinished::all_columns)
+        .load::<Exam>(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(Json(Takeout {
+        user,
+        sessions,
+        credentials,
+        calendar_token,
+        modules,
+        courses,
+        course_groups,
+        exams,
+    }))
+}
+
+/// Deletes the user, their sessions, stored credentials and which modules, courses and exams they
+/// are registered for. The sessions are also ended at TUCaN.
+/// The catalog data stays as other users share it.
+#[ts]
+#[axum::debug_handler(state=AppState)]
+pub async fn delete_account(
+    session: TucanSession,
+    cookie_jar: PrivateCookieJar,
+    tucan: State<Tucan>,
+    _input: Json<()>,
+) -> Result<TsHide<PrivateCookieJar, Json<()>>, MyError> {
+    let mut connection = tucan.pool.get().await?;
+    let matriculation_number = session.matriculation_number;
+
+    let tucan_sessions = connection
+        .build_transaction()
+        .run(|mut connection| {
+            Box::pin(async move {
+                diesel::delete(calendar_tokens::table)
+                    .filter(calendar_tokens::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(user_exams::table)
+                    .filter(user_exams::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(user_modules::table)
+                    .filter(user_modules::user_id.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(user_courses::table)
+                    .filter(user_courses::user_id.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(user_course_groups::table)
+                    .filter(user_course_groups::user_id.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(stored_credentials::table)
+                    .filter(stored_credentials::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(credential_audit_log::table)
+                    .filter(credential_audit_log::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                let tucan_sessions = diesel::delete(sessions::table)
+                    .filter(sessions::matriculation_number.eq(matriculation_number))
+                    .returning((
+                        sessions::matriculation_number,
+                        sessions::session_nr,
+                        sessions::session_id,
+                    ))
+                    .load::<TucanSession>(&mut connection)
+                    .traced()
+                    .await?;
+
+                diesel::delete(users_unfinished::table)
+                    .filter(users_unfinished::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+
+                Ok::<Vec<TucanSession>, diesel::result::Error>(tucan_sessions)
+            })
+        })
+        .await?;
+
+    // the account is gone for us anyway, TUCaN expires the sessions eventually
+    for tucan_session in tucan_sessions {
+        if let Err(err) = tucan.continue_session(tucan_session).logout().await {
+            warn!("failed to log out at TUCaN: {err:#}");
+        }
+    }
+
+    Ok(TsHide {
+        hidden: cookie_jar.remove(Cookie::named(SESSION_COOKIE)),
+        visible: Json(()),
+    })
+}
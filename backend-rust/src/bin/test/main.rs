@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use tucant::config::Config;
 use tucant::tucan::Tucan;
 
 // $HOME/.cargo/bin/diesel database reset && cargo run --bin test
@@ -9,7 +10,7 @@ use tucant::tucan::Tucan;
 async fn main() -> anyhow::Result<()> {
     env_logger::init();
 
-    let tucan = Tucan::new()?;
+    let tucan = Tucan::new(&Config::load()?)?;
     let tucan = tucan
         .login(
             &std::env::var("TUCAN_USERNAME").unwrap(),
@@ -0,0 +1,396 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+mod output;
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use tucant::{
+    config::Config,
+    ids::{CourseId, MenuPath, ModuleId},
+    models::{Course, CourseGroup, Exam, Language, Module, ModuleMenu, TucanSession},
+    search::{SearchQuery, DEFAULT_PAGE_SIZE},
+    snapshot::Snapshot,
+    tucan::Tucan,
+    tucan_user::{CourseOrCourseGroup, TucanUser},
+    url::{parse_tucan_url, Coursedetails, Moduledetails, Registration, TucanProgram},
+    TucanError,
+};
+
+use crate::output::{print_rows, OutputFormat};
+
+/// Command-line client for TUCaN
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// How results are printed
+    #[arg(long, short, value_enum, global = true, default_value_t)]
+    format: OutputFormat,
+
+    /// Where the session is cached, defaults to `$XDG_CACHE_HOME/tucant/session.json`
+    #[arg(long, env = "TUCANT_SESSION_FILE", global = true)]
+    session_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Log in and cache the session for the other commands
+    Login {
+        #[arg(long, env = "TUCAN_USERNAME")]
+        username: String,
+        #[arg(long, env = "TUCAN_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// Forget the cached session
+    Logout,
+    /// List the submenus and modules of a registration menu, the root menu by default
+    Modules { path: Option<MenuPath> },
+    /// Show a module and its courses
+    Module { id: ModuleId },
+    /// Show a course or course group
+    Course { id: CourseId },
+    /// List the courses you are registered for
+    Courses,
+    /// List the exams you are registered for
+    Exams,
+    /// Search the already fetched modules or courses with the configured search backend
+    Search {
+        query: String,
+        /// Search courses instead of modules
+        #[arg(long)]
+        courses: bool,
+        /// At most 100
+        #[arg(long, default_value_t = DEFAULT_PAGE_SIZE)]
+        limit: u32,
+        /// The language of the titles, `de` or `en`
+        #[arg(long, default_value = "de", value_parser = parse_language)]
+        language: Language,
+    },
+    /// Convert between TUCaN urls and their parsed representation
+    Url {
+        #[command(subcommand)]
+        command: UrlCommand,
+    },
+    /// Fetch the whole registration tree into the database
+    Crawl {
+        /// Also fetch the details of every course
+        #[arg(long)]
+        courses: bool,
+    },
+    /// Export or import the public catalog data in the database
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// Write all modules, courses, menus, events and exams to a compressed file
+    Export { file: PathBuf },
+    /// Load a snapshot into a database without catalog data
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+enum UrlCommand {
+    /// Parse a TUCaN url and print the program as JSON
+    Decode { url: String },
+    /// Build a TUCaN url from a program in the JSON format printed by `decode`
+    Encode {
+        program: String,
+        #[arg(long)]
+        session_nr: Option<u64>,
+    },
+}
+
+/// One row of a listing that mixes different kinds of entities.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry<'a> {
+    Menu(&'a ModuleMenu),
+    Module(&'a Module),
+    Course(&'a Course),
+    CourseGroup(&'a CourseGroup),
+}
+
+#[derive(Serialize)]
+struct ExamRow<'a> {
+    of: &'a str,
+    title: &'a str,
+    #[serde(flatten)]
+    exam: &'a Exam,
+}
+
+fn parse_language(code: &str) -> Result<Language, String> {
+    Language::from_code(code).ok_or_else(|| format!("unknown language {code}"))
+}
+
+fn default_session_file() -> anyhow::Result<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .context("neither XDG_CACHE_HOME nor HOME are set, use --session-file")?;
+    Ok(cache.join("tucant").join("session.json"))
+}
+
+fn load_session(tucan: &Tucan, session_file: &Path) -> anyhow::Result<TucanUser> {
+    let session = std::fs::read_to_string(session_file)
+        .with_context(|| format!("not logged in, run `tucant login` first ({session_file:?})"))?;
+    let session: TucanSession = serde_json::from_str(&session)?;
+    Ok(tucan.continue_session(session))
+}
+
+/// Only the user may read the file as the session id is as good as the password until it expires.
+fn save_session(session_file: &Path, session: &TucanSession) -> anyhow::Result<()> {
+    if let Some(parent) = session_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(session_file)
+        .with_context(|| format!("failed to write {session_file:?}"))?;
+    // the mode only applies to new files
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(serde_json::to_string(session)?.as_bytes())?;
+    Ok(())
+}
+
+#[async_recursion::async_recursion]
+async fn crawl(tucan: &TucanUser, path: MenuPath, courses: bool) -> anyhow::Result<usize> {
+    let (menu, entries) = tucan.registration(Registration::new(path)).await?;
+    eprintln!("menu {}", menu.name);
+
+    let mut modules = 0;
+    for submenu in entries.submenus {
+        modules += crawl(tucan, submenu.tucan_id, courses).await?;
+    }
+
+    for (module, module_courses) in entries.modules_and_courses {
+        let (module, _) = tucan.module(Moduledetails::new(module.tucan_id)).await?;
+        eprintln!("module {}", module.title);
+        modules += 1;
+
+        if courses {
+            // some history modules have multiple courses per module so we fetch all of them
+            for course in module_courses {
+                tucan
+                    .course_or_course_group(Coursedetails::new(course.tucan_id))
+                    .await?;
+                eprintln!("course {}", course.title);
+            }
+        }
+    }
+
+    Ok(modules)
+}
+
+async fn crawl_root(tucan: &TucanUser, courses: bool) -> anyhow::Result<usize> {
+    let root = tucan.root_registration().await?;
+    crawl(tucan, root.tucan_id, courses).await
+}
+
+/// Logs in with the stored credentials if `err` is because TUCaN ended the session, so long
+/// running commands don't need the password again. Returns `err` otherwise.
+async fn relogin(
+    tucan: &Tucan,
+    session_file: &Path,
+    user: &TucanUser,
+    err: anyhow::Error,
+) -> anyhow::Result<TucanUser> {
+    if err.downcast_ref() != Some(&TucanError::SessionExpired) {
+        return Err(err);
+    }
+    let Some(user) = tucan.relogin(user.session.matriculation_number).await? else {
+        return Err(err.context("no stored credentials to log in again, run `tucant login`"));
+    };
+    save_session(session_file, &user.session)?;
+    eprintln!("the session expired, logged in again with the stored credentials");
+    Ok(user)
+}
+
+#[allow(clippy::too_many_lines)]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    let cli = Cli::parse();
+    let format = cli.format;
+
+    // these don't need a database or a session
+    if let Command::Url { command } = &cli.command {
+        match command {
+            UrlCommand::Decode { url } => {
+                let url = parse_tucan_url(url)?;
+                println!("{}", serde_json::to_string_pretty(&url)?);
+            }
+            UrlCommand::Encode {
+                program,
+                session_nr,
+            } => {
+                let program: TucanProgram = serde_json::from_str(program)?;
+                println!("{}", program.to_tucan_url(*session_nr)?);
+            }
+        }
+        return Ok(());
+    }
+
+    let session_file = cli.session_file.map_or_else(default_session_file, Ok)?;
+
+    if let Command::Logout = cli.command {
+        if session_file.exists() {
+            std::fs::remove_file(&session_file)?;
+        }
+        return Ok(());
+    }
+
+    let tucan = Tucan::new(&Config::load()?)?;
+
+    match cli.command {
+        Command::Login { username, password } => {
+            let user = tucan.login(&username, &password).await?;
+            save_session(&session_file, &user.session)?;
+            eprintln!("logged in as {}", user.session.matriculation_number);
+        }
+        Command::Modules { path } => {
+            let user = load_session(&tucan, &session_file)?;
+            let path = match path {
+                Some(path) => path,
+                None => user.root_registration().await?.tucan_id,
+            };
+            let (menu, entries) = user.registration(Registration::new(path)).await?;
+            let rows = std::iter::once(Entry::Menu(&menu))
+                .chain(entries.submenus.iter().map(Entry::Menu))
+                .chain(
+                    entries
+                        .modules_and_courses
+                        .iter()
+                        .map(|(module, _)| Entry::Module(module)),
+                )
+                .collect::<Vec<_>>();
+            print_rows(format, &rows)?;
+        }
+        Command::Module { id } => {
+            let user = load_session(&tucan, &session_file)?;
+            let (module, courses) = user.module(Moduledetails::new(id)).await?;
+            let rows = std::iter::once(Entry::Module(&module))
+                .chain(courses.iter().map(Entry::Course))
+                .collect::<Vec<_>>();
+            print_rows(format, &rows)?;
+        }
+        Command::Course { id } => {
+            let user = load_session(&tucan, &session_file)?;
+            let rows = match user.course_or_course_group(Coursedetails::new(id)).await? {
+                CourseOrCourseGroup::Course((course, course_groups, _)) => {
+                    std::iter::once(Entry::Course(&course))
+                        .chain(course_groups.iter().map(Entry::CourseGroup))
+                        .map(serde_json::to_value)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                CourseOrCourseGroup::CourseGroup((course_group, _)) => {
+                    vec![serde_json::to_value(Entry::CourseGroup(&course_group))?]
+                }
+            };
+            print_rows(format, &rows)?;
+        }
+        Command::Courses => {
+            let user = load_session(&tucan, &session_file)?;
+            let courses = user.my_courses().await?;
+            let rows = courses
+                .iter()
+                .map(|course| match course {
+                    CourseOrCourseGroup::Course((course, _, _)) => Entry::Course(course),
+                    CourseOrCourseGroup::CourseGroup((course_group, _)) => {
+                        Entry::CourseGroup(course_group)
+                    }
+                })
+                .collect::<Vec<_>>();
+            print_rows(format, &rows)?;
+        }
+        Command::Exams => {
+            let user = load_session(&tucan, &session_file)?;
+            let (module_exams, course_exams) = user.my_exams().await?;
+            let rows = module_exams
+                .iter()
+                .map(|(module, exam)| ExamRow {
+                    of: &module.module_id,
+                    title: &module.title,
+                    exam,
+                })
+                .chain(course_exams.iter().map(|(course, exam)| ExamRow {
+                    of: &course.course_id,
+                    title: &course.title,
+                    exam,
+                }))
+                .collect::<Vec<_>>();
+            print_rows(format, &rows)?;
+        }
+        Command::Search {
+            query,
+            courses,
+            limit,
+            language,
+        } => {
+            let search = SearchQuery {
+                query,
+                page_size: Some(limit),
+                ..SearchQuery::default()
+            };
+            if courses {
+                let response = tucan.search.search_courses(&search, language).await?;
+                print_rows(format, &response.results)?;
+            } else {
+                let response = tucan.search.search_modules(&search, language).await?;
+                print_rows(format, &response.results)?;
+            }
+        }
+        Command::Crawl { courses } => {
+            let user = load_session(&tucan, &session_file)?;
+            let modules = match crawl_root(&user, courses).await {
+                Ok(modules) => modules,
+                Err(err) => {
+                    // already fetched entities are in the database, so starting over is cheap
+                    let user = relogin(&tucan, &session_file, &user, err).await?;
+                    crawl_root(&user, courses).await?
+                }
+            };
+            eprintln!("fetched {modules} modules");
+        }
+        Command::Snapshot { command } => {
+            let mut connection = tucan.pool.get().await?;
+            match command {
+                SnapshotCommand::Export { file } => {
+                    let snapshot = Snapshot::export(&mut connection).await?;
+                    snapshot.write(BufWriter::new(File::create(file)?))?;
+                    eprintln!(
+                        "exported {} modules and {} courses",
+                        snapshot.modules.len(),
+                        snapshot.courses.len()
+                    );
+                }
+                SnapshotCommand::Import { file } => {
+                    let snapshot = Snapshot::read(BufReader::new(File::open(file)?))?;
+                    snapshot.import(&mut connection).await?;
+                }
+            }
+        }
+        Command::Url { .. } | Command::Logout => unreachable!(),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+const MAX_CELL_WIDTH: usize = 50;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum OutputFormat {
+    /// Aligned columns for reading in a terminal
+    #[default]
+    Table,
+    /// The full data as pretty printed JSON
+    Json,
+    /// One line per row, nested values are JSON encoded
+    Csv,
+}
+
+/// Prints `rows` to stdout. For table and CSV output every row is flattened into its
+/// top-level fields, rows with different fields share the union of all columns.
+pub fn print_rows<T: Serialize>(format: OutputFormat, rows: &[T]) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    if matches!(format, OutputFormat::Json) {
+        serde_json::to_writer_pretty(&mut stdout, rows)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    let rows = rows
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut columns: Vec<String> = Vec::new();
+    for row in &rows {
+        if let Value::Object(object) = row {
+            for key in object.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+    }
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| match row.get(column) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(string)) => string.clone(),
+                    Some(other) => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout);
+            writer.write_record(&columns)?;
+            for row in cells {
+                writer.write_record(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table => {
+            let cells = cells
+                .into_iter()
+                .map(|row| row.into_iter().map(|cell| table_cell(&cell)).collect())
+                .collect::<Vec<Vec<_>>>();
+
+            let widths = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    cells
+                        .iter()
+                        .map(|row| row[i].chars().count())
+                        .chain(std::iter::once(column.chars().count()))
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+
+            for row in std::iter::once(&columns).chain(&cells) {
+                let line = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:width$}"))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                writeln!(stdout, "{}", line.trim_end())?;
+            }
+        }
+        OutputFormat::Json => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn table_cell(value: &str) -> String {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if value.chars().count() > MAX_CELL_WIDTH {
+        value.chars().take(MAX_CELL_WIDTH - 1).collect::<String>() + "…"
+    } else {
+        value
+    }
+}
@@ -0,0 +1,441 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Deployment configuration.
+//!
+//! Values are read from the TOML file in `TUCANT_CONFIG` (default `tucant.toml`, which may be
+//! missing) and can be overridden by environment variables, see [`Config::apply_overrides`].
+//! Everything defaults to the development setup so `cargo run` keeps working without any file.
+
+use std::{fmt::Display, net::SocketAddr, path::PathBuf, str::FromStr};
+
+use anyhow::Context;
+use axum::http::HeaderValue;
+use reqwest::Url;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Also read from `DATABASE_URL` like the diesel cli does.
+    pub database_url: String,
+    pub listen_address: SocketAddr,
+    /// Where clients reach this server, used for links that are opened outside of the frontend
+    /// like the calendar subscription.
+    pub public_url: String,
+    /// Where the frontend is served, `login-hack` redirects there.
+    pub frontend_url: String,
+    /// Origins that may send credentialed requests, usually only the frontend.
+    pub cors_origins: Vec<String>,
+    /// Key for the private session cookies, generated on first start.
+    pub session_key_file: PathBuf,
+    /// Keys that were replaced, cookies encrypted with them are still accepted and reissued with
+    /// the current key. To rotate the key move `session_key_file` here and remove it once
+    /// `session_lifetime_hours` passed.
+    pub previous_session_key_files: Vec<PathBuf>,
+    /// Sessions that were not used for this long have to log in again.
+    pub session_lifetime_hours: u32,
+    /// Server key encrypting the TUCaN credentials users chose to store, generated on first
+    /// start. Storing credentials is disabled if unset.
+    pub credential_key_file: Option<PathBuf>,
+    /// Logins go through single sign-on starting at this url instead of sending the credentials
+    /// to `LOGINCHECK` if set, see [`crate::sso`].
+    pub sso_start_url: Option<String>,
+    /// The host of the identity provider, the credentials are only submitted to a login form
+    /// that posts to it with https.
+    pub sso_idp_host: String,
+    /// Where the TypeScript client is written on startup, nothing is written if unset.
+    pub typescript_output: Option<PathBuf>,
+    pub search_backend: SearchBackendKind,
+    /// Only used if `search_backend` is `opensearch` and by the indexer.
+    pub opensearch: OpensearchConfig,
+    /// Spans are exported to this OpenTelemetry collector with OTLP over gRPC if set.
+    pub otlp_endpoint: Option<String>,
+    /// `/readyz` also fails if TUCaN can't be reached. Off by default as TUCaN being down only
+    /// breaks what isn't crawled yet.
+    pub readyz_checks_tucan: bool,
+}
+
+/// Which engine answers the search routes.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackendKind {
+    /// The full text search of the database, needs nothing else to be deployed.
+    #[default]
+    Postgres,
+    Opensearch,
+}
+
+impl FromStr for SearchBackendKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "postgres" => Ok(Self::Postgres),
+            "opensearch" => Ok(Self::Opensearch),
+            _ => Err(format!("unknown search backend {value:?}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpensearchConfig {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// The development container uses a self-signed certificate.
+    pub accept_invalid_certificates: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: String::new(),
+            listen_address: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            public_url: "http://localhost:8080".to_string(),
+            frontend_url: "http://localhost:5173".to_string(),
+            cors_origins: vec![
+                "http://127.0.0.1:5173".to_string(),
+                "http://localhost:5173".to_string(),
+            ],
+            session_key_file: PathBuf::from("sessions.key"),
+            previous_session_key_files: Vec::new(),
+            session_lifetime_hours: 24 * 7,
+            credential_key_file: None,
+            sso_start_url: None,
+            sso_idp_host: "login.tu-darmstadt.de".to_string(),
+            typescript_output: Some(PathBuf::from("../frontend-react/src/api.ts")),
+            search_backend: SearchBackendKind::default(),
+            opensearch: OpensearchConfig::default(),
+            otlp_endpoint: None,
+            readyz_checks_tucan: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for OpensearchConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpensearchConfig")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field(
+                "accept_invalid_certificates",
+                &self.accept_invalid_certificates,
+            )
+            .finish()
+    }
+}
+
+impl Default for OpensearchConfig {
+    fn default() -> Self {
+        Self {
+            url: "https://localhost:9200".to_string(),
+            username: "admin".to_string(),
+            password: "admin".to_string(),
+            accept_invalid_certificates: true,
+        }
+    }
+}
+
+fn set<T: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    target: &mut T,
+) -> anyhow::Result<()>
+where
+    T::Err: Display,
+{
+    if let Some(value) = var(name) {
+        *target = value
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid value for {name}: {err}"))?;
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Loads the configuration file and environment variables, including `.env`, and validates the result.
+    pub fn load() -> anyhow::Result<Self> {
+        dotenvy::dotenv().ok();
+
+        let path = std::env::var("TUCANT_CONFIG").ok();
+        let mut config = match std::fs::read_to_string(path.as_deref().unwrap_or("tucant.toml")) {
+            Ok(file) => Self::from_toml(&file)?,
+            // only the default file is optional
+            Err(err) if path.is_none() && err.kind() == std::io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(err) => return Err(err).context("failed to read the configuration file"),
+        };
+
+        config.apply_overrides(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_toml(file: &str) -> anyhow::Result<Self> {
+        toml::from_str(file).context("invalid configuration file")
+    }
+
+    /// Overrides values with the environment variables `DATABASE_URL`, `TUCANT_LISTEN_ADDRESS`,
+    /// `TUCANT_PUBLIC_URL`, `TUCANT_FRONTEND_URL`, `TUCANT_CORS_ORIGINS` (comma separated), `TUCANT_SESSION_KEY_FILE`,
+    /// `TUCANT_PREVIOUS_SESSION_KEY_FILES` (comma separated), `TUCANT_SESSION_LIFETIME_HOURS`,
+    /// `TUCANT_CREDENTIAL_KEY_FILE` (empty to disable), `TUCANT_SSO_START_URL` (empty to disable),
+    /// `TUCANT_SSO_IDP_HOST`, `TUCANT_TYPESCRIPT_OUTPUT` (empty to disable), `TUCANT_SEARCH_BACKEND`,
+    /// `TUCANT_OPENSEARCH_URL`, `TUCANT_OPENSEARCH_USERNAME`, `TUCANT_OPENSEARCH_PASSWORD`,
+    /// `TUCANT_OPENSEARCH_ACCEPT_INVALID_CERTIFICATES`, `TUCANT_OTLP_ENDPOINT` (empty to
+    /// disable) and `TUCANT_READYZ_CHECKS_TUCAN`.
+    pub fn apply_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        set(&var, "DATABASE_URL", &mut self.database_url)?;
+        set(&var, "TUCANT_LISTEN_ADDRESS", &mut self.listen_address)?;
+        set(&var, "TUCANT_PUBLIC_URL", &mut self.public_url)?;
+        set(&var, "TUCANT_FRONTEND_URL", &mut self.frontend_url)?;
+        if let Some(origins) = var("TUCANT_CORS_ORIGINS") {
+            self.cors_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+        set(&var, "TUCANT_SESSION_KEY_FILE", &mut self.session_key_file)?;
+        if let Some(files) = var("TUCANT_PREVIOUS_SESSION_KEY_FILES") {
+            self.previous_session_key_files = files
+                .split(',')
+                .map(str::trim)
+                .filter(|file| !file.is_empty())
+                .map(PathBuf::from)
+                .collect();
+        }
+        set(
+            &var,
+            "TUCANT_SESSION_LIFETIME_HOURS",
+            &mut self.session_lifetime_hours,
+        )?;
+        if let Some(file) = var("TUCANT_CREDENTIAL_KEY_FILE") {
+            self.credential_key_file =
+                Some(PathBuf::from(file)).filter(|file| !file.as_os_str().is_empty());
+        }
+        if let Some(url) = var("TUCANT_SSO_START_URL") {
+            self.sso_start_url = Some(url).filter(|url| !url.is_empty());
+        }
+        set(&var, "TUCANT_SSO_IDP_HOST", &mut self.sso_idp_host)?;
+        if let Some(output) = var("TUCANT_TYPESCRIPT_OUTPUT") {
+            self.typescript_output =
+                Some(PathBuf::from(output)).filter(|output| !output.as_os_str().is_empty());
+        }
+        set(&var, "TUCANT_SEARCH_BACKEND", &mut self.search_backend)?;
+        set(&var, "TUCANT_OPENSEARCH_URL", &mut self.opensearch.url)?;
+        set(
+            &var,
+            "TUCANT_OPENSEARCH_USERNAME",
+            &mut self.opensearch.username,
+        )?;
+        set(
+            &var,
+            "TUCANT_OPENSEARCH_PASSWORD",
+            &mut self.opensearch.password,
+        )?;
+        set(
+            &var,
+            "TUCANT_OPENSEARCH_ACCEPT_INVALID_CERTIFICATES",
+            &mut self.opensearch.accept_invalid_certificates,
+        )?;
+        if let Some(endpoint) = var("TUCANT_OTLP_ENDPOINT") {
+            self.otlp_endpoint = Some(endpoint).filter(|endpoint| !endpoint.is_empty());
+        }
+        set(
+            &var,
+            "TUCANT_READYZ_CHECKS_TUCAN",
+            &mut self.readyz_checks_tucan,
+        )?;
+        Ok(())
+    }
+
+    /// Fails early for values that would otherwise only break the first request using them.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.database_url.is_empty(),
+            "database_url or DATABASE_URL must be set"
+        );
+        Url::parse(&self.database_url).context("database_url is not a valid url")?;
+
+        for (name, url) in [
+            ("public_url", &self.public_url),
+            ("frontend_url", &self.frontend_url),
+        ] {
+            let url = Url::parse(url).with_context(|| format!("{name} is not a valid url"))?;
+            anyhow::ensure!(
+                url.scheme() == "http" || url.scheme() == "https",
+                "{name} must be a http or https url"
+            );
+        }
+
+        for origin in &self.cors_origins {
+            origin
+                .parse::<HeaderValue>()
+                .with_context(|| format!("cors origin {origin:?} is not a valid header value"))?;
+        }
+
+        if self.search_backend == SearchBackendKind::Opensearch {
+            Url::parse(&self.opensearch.url).context("opensearch.url is not a valid url")?;
+        }
+
+        anyhow::ensure!(
+            self.session_lifetime_hours > 0,
+            "session_lifetime_hours must be positive"
+        );
+
+        if let Some(endpoint) = &self.otlp_endpoint {
+            Url::parse(endpoint).context("otlp_endpoint is not a valid url")?;
+        }
+        if let Some(url) = &self.sso_start_url {
+            Url::parse(url).context("sso_start_url is not a valid url")?;
+            let idp = Url::parse(&format!("https://{}/", self.sso_idp_host)).ok();
+            anyhow::ensure!(
+                idp.as_ref().and_then(Url::host_str) == Some(self.sso_idp_host.as_str()),
+                "sso_idp_host must be a host name without scheme, port or path"
+            );
+        }
+        Ok(())
+    }
+
+    /// An absolute url to `path` in the frontend.
+    #[must_use]
+    pub fn frontend(&self, path: &str) -> String {
+        format!("{}{path}", self.frontend_url.trim_end_matches('/'))
+    }
+
+    /// An absolute url to `path` on this server.
+    #[must_use]
+    pub fn public(&self, path: &str) -> String {
+        format!("{}{path}", self.public_url.trim_end_matches('/'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use super::{Config, SearchBackendKind};
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::from_toml("database_url = \"postgres://localhost/tucant\"").unwrap();
+        assert_eq!(config.listen_address.port(), 8080);
+        assert!(config.validate().is_ok());
+        assert!(Config::default().validate().is_err());
+        assert!(Config::from_toml("unknown = 1").is_err());
+        assert_eq!(config.search_backend, SearchBackendKind::Postgres);
+        assert!(Config::from_toml("search_backend = \"elasticsearch\"").is_err());
+        assert_eq!(config.otlp_endpoint, None);
+        assert_eq!(config.credential_key_file, None);
+        assert_eq!(config.sso_start_url, None);
+        assert_eq!(config.sso_idp_host, "login.tu-darmstadt.de");
+        assert!(!config.readyz_checks_tucan);
+        let mut secret = config;
+        secret.opensearch.password = "hunter2".to_string();
+        assert!(!format!("{secret:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut config = Config::from_toml(
+            r#"
+            database_url = "postgres://localhost/tucant"
+            frontend_url = "https://tucant.example/"
+            public_url = "https://api.tucant.example"
+            otlp_endpoint = "http://localhost:4317"
+
+            [opensearch]
+            url = "https://opensearch.example:9200"
+            "#,
+        )
+        .unwrap();
+        let env = HashMap::from([
+            ("TUCANT_LISTEN_ADDRESS", "0.0.0.0:80"),
+            ("TUCANT_CORS_ORIGINS", "https://tucant.example, "),
+            ("TUCANT_TYPESCRIPT_OUTPUT", ""),
+            ("TUCANT_OPENSEARCH_ACCEPT_INVALID_CERTIFICATES", "false"),
+            ("TUCANT_SEARCH_BACKEND", "opensearch"),
+            ("TUCANT_OTLP_ENDPOINT", ""),
+            ("TUCANT_PREVIOUS_SESSION_KEY_FILES", "old.key,older.key"),
+            ("TUCANT_CREDENTIAL_KEY_FILE", "credentials.key"),
+            ("TUCANT_READYZ_CHECKS_TUCAN", "true"),
+        ]);
+        config
+            .apply_overrides(|name| env.get(name).map(ToString::to_string))
+            .unwrap();
+
+        assert_eq!(config.listen_address.to_string(), "0.0.0.0:80");
+        assert_eq!(config.cors_origins, vec!["https://tucant.example"]);
+        assert_eq!(config.typescript_output, None);
+        assert_eq!(config.session_key_file, PathBuf::from("sessions.key"));
+        assert_eq!(
+            config.previous_session_key_files,
+            vec![PathBuf::from("old.key"), PathBuf::from("older.key")]
+        );
+        assert_eq!(config.session_lifetime_hours, 168);
+        assert_eq!(
+            config.credential_key_file,
+            Some(PathBuf::from("credentials.key"))
+        );
+        assert_eq!(config.opensearch.url, "https://opensearch.example:9200");
+        assert!(!config.opensearch.accept_invalid_certificates);
+        assert_eq!(config.search_backend, SearchBackendKind::Opensearch);
+        assert_eq!(config.otlp_endpoint, None);
+        assert!(config.readyz_checks_tucan);
+        assert_eq!(
+            config.frontend("/module/abc"),
+            "https://tucant.example/module/abc"
+        );
+        assert_eq!(
+            config.public("/calendar/abc.ics"),
+            "https://api.tucant.example/calendar/abc.ics"
+        );
+        assert!(config.validate().is_ok());
+
+        let mut public = config.clone();
+        public
+            .apply_overrides(|name| (name == "TUCANT_PUBLIC_URL").then(|| "ftp://tucant".into()))
+            .unwrap();
+        assert!(public.validate().is_err());
+
+        let mut expiring = config.clone();
+        expiring
+            .apply_overrides(|name| (name == "TUCANT_SESSION_LIFETIME_HOURS").then(|| "0".into()))
+            .unwrap();
+        assert!(expiring.validate().is_err());
+
+        let mut sso = config.clone();
+        sso.apply_overrides(|name| (name == "TUCANT_SSO_START_URL").then(|| "/sso".into()))
+            .unwrap();
+        assert!(sso.validate().is_err());
+
+        let sso_env = HashMap::from([
+            (
+                "TUCANT_SSO_START_URL",
+                "https://www.tucan.tu-darmstadt.de/sso",
+            ),
+            ("TUCANT_SSO_IDP_HOST", "idp.example"),
+        ]);
+        let mut sso = config.clone();
+        sso.apply_overrides(|name| sso_env.get(name).map(ToString::to_string))
+            .unwrap();
+        assert_eq!(sso.sso_idp_host, "idp.example");
+        assert!(sso.validate().is_ok());
+        sso.apply_overrides(|name| {
+            (name == "TUCANT_SSO_IDP_HOST").then(|| "https://idp.example/".into())
+        })
+        .unwrap();
+        assert!(sso.validate().is_err());
+
+        let invalid = HashMap::from([("TUCANT_LISTEN_ADDRESS", "localhost")]);
+        assert!(config
+            .apply_overrides(|name| invalid.get(name).map(ToString::to_string))
+            .is_err());
+    }
+}
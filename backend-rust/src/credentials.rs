@@ -0,0 +1,395 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! TUCaN credentials that users chose to store so [`Tucan::relogin`](crate::tucan::Tucan::relogin)
+//! can get a new session for background jobs after theirs timed out.
+//!
+//! Every entry is encrypted with its own random data key, which is stored encrypted with the
+//! server key in [`Config::credential_key_file`](crate::config::Config::credential_key_file).
+//! Both are bound to the matriculation number so entries can't be swapped between users. Storing,
+//! using and revoking them is recorded in `credential_audit_log`.
+
+use std::io::Write;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Context;
+use chrono::{NaiveDateTime, Utc};
+use diesel::upsert::excluded;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{CredentialAuditEntry, CredentialStatus};
+use crate::schema::{credential_audit_log, stored_credentials};
+use crate::telemetry::Traced;
+use crate::tucan::DbPool;
+
+/// Length of the AES-GCM nonce in front of every ciphertext.
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Never prints the password, so credentials can't end up in logs.
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialEvent {
+    Stored,
+    /// A background job logged in with them.
+    Used,
+    /// TUCaN rejected them, they were deleted as the password was probably changed.
+    LoginFailed,
+    /// They logged in as someone else, they were deleted.
+    WrongUser,
+    Revoked,
+}
+
+impl CredentialEvent {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stored => "stored",
+            Self::Used => "used",
+            Self::LoginFailed => "login_failed",
+            Self::WrongUser => "wrong_user",
+            Self::Revoked => "revoked",
+        }
+    }
+}
+
+/// The encrypted form of [`Credentials`] as stored in `stored_credentials`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sealed {
+    pub data_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// The server key, it only ever encrypts data keys.
+#[derive(Clone)]
+pub struct CredentialKey {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for CredentialKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialKey").finish_non_exhaustive()
+    }
+}
+
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("failed to encrypt"))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn decrypt(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(sealed.len() > NONCE_LENGTH, "ciphertext is too short");
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("failed to decrypt, the key or the data is wrong"))
+}
+
+impl CredentialKey {
+    #[must_use]
+    pub fn generate() -> Self {
+        Self {
+            cipher: Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)),
+        }
+    }
+
+    /// Reads the key, it is generated if the file doesn't exist yet. Fails if other users may
+    /// read the file as the key decrypts all stored credentials.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut options = std::fs::OpenOptions::new();
+        options.create_new(true).write(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        if let Ok(mut file) = options.open(path) {
+            file.write_all(&Aes256Gcm::generate_key(&mut OsRng))?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path)
+                .with_context(|| format!("failed to read {path:?}"))?
+                .permissions()
+                .mode();
+            anyhow::ensure!(
+                mode & 0o077 == 0,
+                "{path:?} may be read by other users, restrict it with chmod 600"
+            );
+        }
+
+        let key = std::fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        Ok(Self {
+            cipher: Aes256Gcm::new_from_slice(&key)
+                .map_err(|_| anyhow::anyhow!("{path:?} is not a 32 byte key"))?,
+        })
+    }
+
+    pub fn seal(
+        &self,
+        matriculation_number: i32,
+        credentials: &Credentials,
+    ) -> anyhow::Result<Sealed> {
+        let aad = matriculation_number.to_be_bytes();
+        let data_key = Aes256Gcm::generate_key(&mut OsRng);
+        Ok(Sealed {
+            ciphertext: encrypt(
+                &Aes256Gcm::new(&data_key),
+                &serde_json::to_vec(credentials)?,
+                &aad,
+            )?,
+            data_key: encrypt(&self.cipher, &data_key, &aad)?,
+        })
+    }
+
+    pub fn open(&self, matriculation_number: i32, sealed: &Sealed) -> anyhow::Result<Credentials> {
+        let aad = matriculation_number.to_be_bytes();
+        let data_key = decrypt(&self.cipher, &sealed.data_key, &aad)?;
+        let data_cipher = Aes256Gcm::new_from_slice(&data_key)
+            .map_err(|_| anyhow::anyhow!("data key has the wrong length"))?;
+        Ok(serde_json::from_slice(&decrypt(
+            &data_cipher,
+            &sealed.ciphertext,
+            &aad,
+        )?)?)
+    }
+}
+
+async fn record(
+    connection: &mut AsyncPgConnection,
+    matriculation_number: i32,
+    event: CredentialEvent,
+) -> Result<(), diesel::result::Error> {
+    diesel::insert_into(credential_audit_log::table)
+        .values((
+            credential_audit_log::matriculation_number.eq(matriculation_number),
+            credential_audit_log::event.eq(event.as_str()),
+        ))
+        .execute(connection)
+        .traced()
+        .await?;
+    Ok(())
+}
+
+/// Stores the credentials of a user who consented to it, replacing earlier ones.
+pub async fn store(
+    pool: &DbPool,
+    key: &CredentialKey,
+    matriculation_number: i32,
+    credentials: &Credentials,
+) -> anyhow::Result<()> {
+    let sealed = key.seal(matriculation_number, credentials)?;
+    let mut connection = pool.get().await?;
+    connection
+        .build_transaction()
+        .run(|mut connection| {
+            Box::pin(async move {
+                diesel::insert_into(stored_credentials::table)
+                    .values((
+                        stored_credentials::matriculation_number.eq(matriculation_number),
+                        stored_credentials::data_key.eq(&sealed.data_key),
+                        stored_credentials::ciphertext.eq(&sealed.ciphertext),
+                        stored_credentials::consented.eq(Utc::now().naive_utc()),
+                    ))
+                    .on_conflict(stored_credentials::matriculation_number)
+                    .do_update()
+                    .set((
+                        stored_credentials::data_key.eq(excluded(stored_credentials::data_key)),
+                        stored_credentials::ciphertext.eq(excluded(stored_credentials::ciphertext)),
+                        stored_credentials::consented.eq(excluded(stored_credentials::consented)),
+                        stored_credentials::last_used.eq(None::<NaiveDateTime>),
+                    ))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+                record(
+                    &mut connection,
+                    matriculation_number,
+                    CredentialEvent::Stored,
+                )
+                .await
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Deletes the stored credentials, `event` says why. Returns whether there were any.
+pub async fn revoke(
+    pool: &DbPool,
+    matriculation_number: i32,
+    event: CredentialEvent,
+) -> anyhow::Result<bool> {
+    let mut connection = pool.get().await?;
+    Ok(connection
+        .build_transaction()
+        .run(|mut connection| {
+            Box::pin(async move {
+                let deleted = diesel::delete(stored_credentials::table)
+                    .filter(stored_credentials::matriculation_number.eq(matriculation_number))
+                    .execute(&mut connection)
+                    .traced()
+                    .await?;
+                if deleted > 0 {
+                    record(&mut connection, matriculation_number, event).await?;
+                }
+                Ok::<_, diesel::result::Error>(deleted > 0)
+            })
+        })
+        .await?)
+}
+
+/// Decrypts the stored credentials, only for [`Tucan::relogin`] which calls [`used`] once it
+/// logged in with them.
+///
+/// [`Tucan::relogin`]: crate::tucan::Tucan::relogin
+pub(crate) async fn open(
+    pool: &DbPool,
+    key: &CredentialKey,
+    matriculation_number: i32,
+) -> anyhow::Result<Option<Credentials>> {
+    let mut connection = pool.get().await?;
+    let stored = stored_credentials::table
+        .filter(stored_credentials::matriculation_number.eq(matriculation_number))
+        .select((stored_credentials::data_key, stored_credentials::ciphertext))
+        .get_result::<(Vec<u8>, Vec<u8>)>(&mut connection)
+        .traced()
+        .await
+        .optional()?;
+
+    stored
+        .map(|(data_key, ciphertext)| {
+            key.open(
+                matriculation_number,
+                &Sealed {
+                    data_key,
+                    ciphertext,
+                },
+            )
+        })
+        .transpose()
+}
+
+/// Records that a background job logged in with the stored credentials.
+pub(crate) async fn used(pool: &DbPool, matriculation_number: i32) -> anyhow::Result<()> {
+    let mut connection = pool.get().await?;
+    connection
+        .build_transaction()
+        .run(|mut connection| {
+            Box::pin(async move {
+                diesel::update(
+                    stored_credentials::table
+                        .filter(stored_credentials::matriculation_number.eq(matriculation_number)),
+                )
+                .set(stored_credentials::last_used.eq(Utc::now().naive_utc()))
+                .execute(&mut connection)
+                .traced()
+                .await?;
+                record(&mut connection, matriculation_number, CredentialEvent::Used).await
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Whether credentials are stored and what happened with them, never the credentials themselves.
+pub async fn status(pool: &DbPool, matriculation_number: i32) -> anyhow::Result<CredentialStatus> {
+    let mut connection = pool.get().await?;
+    let stored = stored_credentials::table
+        .filter(stored_credentials::matriculation_number.eq(matriculation_number))
+        .select((stored_credentials::consented, stored_credentials::last_used))
+        .get_result::<(NaiveDateTime, Option<NaiveDateTime>)>(&mut connection)
+        .traced()
+        .await
+        .optional()?;
+    let audit_log = credential_audit_log::table
+        .filter(credential_audit_log::matriculation_number.eq(matriculation_number))
+        .order_by(credential_audit_log::id.desc())
+        .select((credential_audit_log::event, credential_audit_log::created))
+        .load::<CredentialAuditEntry>(&mut connection)
+        .traced()
+        .await?;
+    Ok(CredentialStatus {
+        consented: stored.map(|(consented, _)| consented),
+        last_used: stored.and_then(|(_, last_used)| last_used),
+        audit_log,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CredentialKey, Credentials};
+
+    #[test]
+    fn test_seal() {
+        let key = CredentialKey::generate();
+        let credentials = Credentials {
+            username: "ab12cdef".to_string(),
+            password: "hunter2".to_string(),
+        };
+
+        let sealed = key.seal(4242, &credentials).unwrap();
+        assert_eq!(key.open(4242, &sealed).unwrap(), credentials);
+        assert!(!String::from_utf8_lossy(&sealed.ciphertext).contains("hunter2"));
+        // bound to the user and the server key
+        assert!(key.open(4243, &sealed).is_err());
+        assert!(CredentialKey::generate().open(4242, &sealed).is_err());
+
+        let mut tampered = sealed;
+        tampered.ciphertext[20] ^= 1;
+        assert!(key.open(4242, &tampered).is_err());
+
+        assert!(!format!("{credentials:?}").contains("hunter2"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("tucant-{}.key", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        CredentialKey::load(&path).unwrap();
+        let permissions = std::fs::metadata(&path).unwrap().permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o600);
+        // the existing key is read
+        CredentialKey::load(&path).unwrap();
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(CredentialKey::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
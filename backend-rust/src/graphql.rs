@@ -0,0 +1,577 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! A read-only GraphQL API over everything that was already crawled.
+//!
+//! Unlike the other routes this never fetches from TUCaN, so it is cheap enough to load whole
+//! study plans in one request. Relations are loaded through [`DataLoader`]s so a list of
+//! modules with their courses and events takes one query per level instead of one per entity.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, NullableExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::ids::{CourseId, ExamId, MenuPath, ModuleId};
+use crate::models::{
+    Course, CourseEvent, CourseGroup, CourseGroupEvent, Exam, Language, Localize, Module,
+    ModuleMenu, TucanSession, COURSES_UNFINISHED, MODULES_UNFINISHED,
+};
+use crate::schema::{
+    course_events, course_exams, course_groups_events, course_groups_unfinished,
+    courses_unfinished, exams_unfinished, module_courses, module_exams, module_menu_module,
+    module_menu_unfinished, modules_unfinished, user_course_groups, user_courses, user_exams,
+    user_modules,
+};
+use crate::telemetry::Traced;
+use crate::tucan::DbPool;
+
+async_graphql::scalar!(
+    ModuleId,
+    "ModuleId",
+    "The id of a module as url safe base64."
+);
+async_graphql::scalar!(
+    CourseId,
+    "CourseId",
+    "The id of a course or course group as url safe base64."
+);
+async_graphql::scalar!(ExamId, "ExamId", "The id of an exam as url safe base64.");
+async_graphql::scalar!(
+    MenuPath,
+    "MenuPath",
+    "The id of a module menu as url safe base64."
+);
+
+pub type TucantSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Deeper queries than this are most likely not written by a human.
+const MAX_DEPTH: usize = 10;
+const MAX_COMPLEXITY: usize = 1000;
+
+/// The request data is the [`Language`] and the `Option<TucanSession>` of the request.
+#[must_use]
+pub fn schema(pool: DbPool) -> TucantSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(
+            CatalogLoader { pool: pool.clone() },
+            tokio::spawn,
+        ))
+        .data(pool)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Database errors are logged and only a generic message is returned like for the other routes.
+fn internal(err: impl Into<anyhow::Error>) -> async_graphql::Error {
+    tracing::error!("{:?}", err.into());
+    async_graphql::Error::new("internal error")
+}
+
+fn language(ctx: &Context<'_>) -> Language {
+    ctx.data_opt::<Language>().copied().unwrap_or_default()
+}
+
+async fn connection(
+    ctx: &Context<'_>,
+) -> async_graphql::Result<deadpool::managed::Object<AsyncDieselConnectionManager<AsyncPgConnection>>>
+{
+    ctx.data_unchecked::<DbPool>().get().await.map_err(internal)
+}
+
+async fn load<K>(
+    ctx: &Context<'_>,
+    key: K,
+) -> async_graphql::Result<<CatalogLoader as Loader<K>>::Value>
+where
+    K: Send + Sync + Hash + Eq + Clone + 'static,
+    CatalogLoader: Loader<K, Error = Arc<anyhow::Error>>,
+    <CatalogLoader as Loader<K>>::Value: Default,
+{
+    Ok(ctx
+        .data_unchecked::<DataLoader<CatalogLoader>>()
+        .load_one(key)
+        .await
+        .map_err(|err| internal(anyhow::anyhow!("{err:?}")))?
+        .unwrap_or_default())
+}
+
+fn group<K: Hash + Eq, V>(rows: impl IntoIterator<Item = (K, V)>) -> HashMap<K, Vec<V>> {
+    let mut result: HashMap<K, Vec<V>> = HashMap::new();
+    for (key, value) in rows {
+        result.entry(key).or_default().push(value);
+    }
+    result
+}
+
+pub struct CatalogLoader {
+    pool: DbPool,
+}
+
+/// Implements [`Loader`] for a key that wraps the id the rows are grouped by.
+macro_rules! relation_loader {
+    ($(#[$meta:meta])* $key:ident($id:ty) -> $value:ty, |$connection:ident, $ids:ident| $query:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $key(pub $id);
+
+        #[async_graphql::async_trait::async_trait]
+        impl Loader<$key> for CatalogLoader {
+            type Value = Vec<$value>;
+            type Error = Arc<anyhow::Error>;
+
+            async fn load(&self, keys: &[$key]) -> Result<HashMap<$key, Self::Value>, Self::Error> {
+                let mut $connection = self.pool.get().await.map_err(|err| Arc::new(anyhow::Error::from(err)))?;
+                let $ids: Vec<$id> = keys.iter().map(|key| key.0.clone()).collect();
+                let rows: Vec<($id, $value)> = $query.await.map_err(|err| Arc::new(anyhow::Error::from(err)))?;
+                Ok(group(rows.into_iter().map(|(id, value)| ($key(id), value))))
+            }
+        }
+    };
+}
+
+relation_loader!(
+    CoursesOfModule(ModuleId) -> Course,
+    |connection, ids| module_courses::table
+        .inner_join(courses_unfinished::table)
+        .filter(module_courses::module.eq_any(ids))
+        .select((module_courses::module, COURSES_UNFINISHED))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    ExamsOfModule(ModuleId) -> Exam,
+    |connection, ids| module_exams::table
+        .inner_join(exams_unfinished::table)
+        .filter(module_exams::module_id.eq_any(ids))
+        .select((module_exams::module_id, exams_unfinished::all_columns))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    ModulesOfCourse(CourseId) -> Module,
+    |connection, ids| module_courses::table
+        .inner_join(modules_unfinished::table)
+        .filter(module_courses::course.eq_any(ids))
+        .select((module_courses::course, MODULES_UNFINISHED))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    GroupsOfCourse(CourseId) -> CourseGroup,
+    |connection, ids| course_groups_unfinished::table
+        .filter(course_groups_unfinished::course.eq_any(ids))
+        .select((course_groups_unfinished::course, course_groups_unfinished::all_columns))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    EventsOfCourse(CourseId) -> CourseEvent,
+    |connection, ids| course_events::table
+        .filter(course_events::course.eq_any(ids))
+        .select((course_events::course, course_events::all_columns))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    /// Keyed by the id of the course group.
+    EventsOfCourseGroup(CourseId) -> CourseGroupEvent,
+    |connection, ids| course_groups_events::table
+        .filter(course_groups_events::course.eq_any(ids))
+        .select((course_groups_events::course, course_groups_events::all_columns))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    ExamsOfCourse(CourseId) -> Exam,
+    |connection, ids| course_exams::table
+        .inner_join(exams_unfinished::table)
+        .filter(course_exams::course_id.eq_any(ids))
+        .select((course_exams::course_id, exams_unfinished::all_columns))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    ModulesOfMenu(MenuPath) -> Module,
+    |connection, ids| module_menu_module::table
+        .inner_join(modules_unfinished::table)
+        .filter(module_menu_module::module_menu_id.eq_any(ids))
+        .select((module_menu_module::module_menu_id, MODULES_UNFINISHED))
+        .load(&mut connection)
+        .traced()
+);
+
+relation_loader!(
+    ChildrenOfMenu(MenuPath) -> ModuleMenu,
+    |connection, ids| module_menu_unfinished::table
+        .filter(module_menu_unfinished::parent.eq_any(ids))
+        .select((
+            module_menu_unfinished::parent.assume_not_null(),
+            module_menu_unfinished::all_columns,
+        ))
+        .load(&mut connection)
+        .traced()
+);
+
+pub struct ModuleObject(Module);
+
+#[Object(name = "Module")]
+impl ModuleObject {
+    async fn id(&self) -> &ModuleId {
+        &self.0.tucan_id
+    }
+
+    /// The number like `20-00-0004` that is shown in TUCaN.
+    async fn module_id(&self) -> &str {
+        &self.0.module_id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn credits(&self) -> Option<i32> {
+        self.0.credits
+    }
+
+    async fn last_checked(&self) -> NaiveDateTime {
+        self.0.tucan_last_checked
+    }
+
+    async fn courses(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<CourseObject>> {
+        let courses = load(ctx, CoursesOfModule(self.0.tucan_id.clone())).await?;
+        Ok(courses
+            .localize(language(ctx))
+            .into_iter()
+            .map(CourseObject)
+            .collect())
+    }
+
+    async fn exams(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Exam>> {
+        load(ctx, ExamsOfModule(self.0.tucan_id.clone())).await
+    }
+}
+
+pub struct CourseObject(Course);
+
+#[Object(name = "Course")]
+impl CourseObject {
+    async fn id(&self) -> &CourseId {
+        &self.0.tucan_id
+    }
+
+    /// The number like `20-00-0004-iv` that is shown in TUCaN.
+    async fn course_id(&self) -> &str {
+        &self.0.course_id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn sws(&self) -> i16 {
+        self.0.sws
+    }
+
+    async fn last_checked(&self) -> NaiveDateTime {
+        self.0.tucan_last_checked
+    }
+
+    async fn modules(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ModuleObject>> {
+        let modules = load(ctx, ModulesOfCourse(self.0.tucan_id.clone())).await?;
+        Ok(modules
+            .localize(language(ctx))
+            .into_iter()
+            .map(ModuleObject)
+            .collect())
+    }
+
+    async fn groups(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<CourseGroupObject>> {
+        let groups = load(ctx, GroupsOfCourse(self.0.tucan_id.clone())).await?;
+        Ok(groups.into_iter().map(CourseGroupObject).collect())
+    }
+
+    async fn events(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<CourseEvent>> {
+        load(ctx, EventsOfCourse(self.0.tucan_id.clone())).await
+    }
+
+    async fn exams(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Exam>> {
+        load(ctx, ExamsOfCourse(self.0.tucan_id.clone())).await
+    }
+}
+
+pub struct CourseGroupObject(CourseGroup);
+
+#[Object(name = "CourseGroup")]
+impl CourseGroupObject {
+    async fn id(&self) -> &CourseId {
+        &self.0.tucan_id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn course(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<CourseObject>> {
+        let mut connection = connection(ctx).await?;
+        let course = courses_unfinished::table
+            .filter(courses_unfinished::tucan_id.eq(&self.0.course))
+            .select(COURSES_UNFINISHED)
+            .get_result::<Course>(&mut connection)
+            .traced()
+            .await
+            .optional()
+            .map_err(internal)?;
+        Ok(course.map(|course| CourseObject(course.localize(language(ctx)))))
+    }
+
+    async fn events(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<CourseGroupEvent>> {
+        load(ctx, EventsOfCourseGroup(self.0.tucan_id.clone())).await
+    }
+}
+
+pub struct ModuleMenuObject(ModuleMenu);
+
+#[Object(name = "ModuleMenu")]
+impl ModuleMenuObject {
+    async fn id(&self) -> &MenuPath {
+        &self.0.tucan_id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn parent(&self) -> Option<&MenuPath> {
+        self.0.parent.as_ref()
+    }
+
+    async fn children(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Self>> {
+        let children = load(ctx, ChildrenOfMenu(self.0.tucan_id.clone())).await?;
+        Ok(children.into_iter().map(Self).collect())
+    }
+
+    async fn modules(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ModuleObject>> {
+        let modules = load(ctx, ModulesOfMenu(self.0.tucan_id.clone())).await?;
+        Ok(modules
+            .localize(language(ctx))
+            .into_iter()
+            .map(ModuleObject)
+            .collect())
+    }
+}
+
+/// What the logged in user is registered for, as of the last time it was crawled.
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Me {
+    matriculation_number: i32,
+}
+
+#[async_graphql::ComplexObject]
+impl Me {
+    async fn modules(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ModuleObject>> {
+        let mut connection = connection(ctx).await?;
+        let modules = user_modules::table
+            .filter(user_modules::user_id.eq(self.matriculation_number))
+            .inner_join(modules_unfinished::table)
+            .select(MODULES_UNFINISHED)
+            .load::<Module>(&mut connection)
+            .traced()
+            .await
+            .map_err(internal)?;
+        Ok(modules
+            .localize(language(ctx))
+            .into_iter()
+            .map(ModuleObject)
+            .collect())
+    }
+
+    async fn courses(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<CourseObject>> {
+        let mut connection = connection(ctx).await?;
+        let courses = user_courses::table
+            .filter(user_courses::user_id.eq(self.matriculation_number))
+            .inner_join(courses_unfinished::table)
+            .select(COURSES_UNFINISHED)
+            .load::<Course>(&mut connection)
+            .traced()
+            .await
+            .map_err(internal)?;
+        Ok(courses
+            .localize(language(ctx))
+            .into_iter()
+            .map(CourseObject)
+            .collect())
+    }
+
+    async fn course_groups(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<CourseGroupObject>> {
+        let mut connection = connection(ctx).await?;
+        let course_groups = user_course_groups::table
+            .filter(user_course_groups::user_id.eq(self.matriculation_number))
+            .inner_join(course_groups_unfinished::table)
+            .select(course_groups_unfinished::all_columns)
+            .load::<CourseGroup>(&mut connection)
+            .traced()
+            .await
+            .map_err(internal)?;
+        Ok(course_groups.into_iter().map(CourseGroupObject).collect())
+    }
+
+    async fn exams(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Exam>> {
+        let mut connection = connection(ctx).await?;
+        user_exams::table
+            .filter(user_exams::matriculation_number.eq(self.matriculation_number))
+            .inner_join(exams_unfinished::table)
+            .select(exams_unfinished::all_columns)
+            .load::<Exam>(&mut connection)
+            .traced()
+            .await
+            .map_err(internal)
+    }
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn module(
+        &self,
+        ctx: &Context<'_>,
+        id: ModuleId,
+    ) -> async_graphql::Result<Option<ModuleObject>> {
+        let mut connection = connection(ctx).await?;
+        let module = modules_unfinished::table
+            .filter(modules_unfinished::tucan_id.eq(&id))
+            .select(MODULES_UNFINISHED)
+            .get_result::<Module>(&mut connection)
+            .traced()
+            .await
+            .optional()
+            .map_err(internal)?;
+        Ok(module.map(|module| ModuleObject(module.localize(language(ctx)))))
+    }
+
+    async fn course(
+        &self,
+        ctx: &Context<'_>,
+        id: CourseId,
+    ) -> async_graphql::Result<Option<CourseObject>> {
+        let mut connection = connection(ctx).await?;
+        let course = courses_unfinished::table
+            .filter(courses_unfinished::tucan_id.eq(&id))
+            .select(COURSES_UNFINISHED)
+            .get_result::<Course>(&mut connection)
+            .traced()
+            .await
+            .optional()
+            .map_err(internal)?;
+        Ok(course.map(|course| CourseObject(course.localize(language(ctx)))))
+    }
+
+    async fn course_group(
+        &self,
+        ctx: &Context<'_>,
+        id: CourseId,
+    ) -> async_graphql::Result<Option<CourseGroupObject>> {
+        let mut connection = connection(ctx).await?;
+        let course_group = course_groups_unfinished::table
+            .filter(course_groups_unfinished::tucan_id.eq(&id))
+            .get_result::<CourseGroup>(&mut connection)
+            .traced()
+            .await
+            .optional()
+            .map_err(internal)?;
+        Ok(course_group.map(CourseGroupObject))
+    }
+
+    async fn exam(&self, ctx: &Context<'_>, id: ExamId) -> async_graphql::Result<Option<Exam>> {
+        let mut connection = connection(ctx).await?;
+        exams_unfinished::table
+            .filter(exams_unfinished::tucan_id.eq(&id))
+            .get_result::<Exam>(&mut connection)
+            .traced()
+            .await
+            .optional()
+            .map_err(internal)
+    }
+
+    /// The menu with the given id or the root menus if none is given.
+    async fn menus(
+        &self,
+        ctx: &Context<'_>,
+        id: Option<MenuPath>,
+    ) -> async_graphql::Result<Vec<ModuleMenuObject>> {
+        let mut connection = connection(ctx).await?;
+        let query = module_menu_unfinished::table.into_boxed();
+        let query = match id {
+            Some(id) => query.filter(module_menu_unfinished::tucan_id.eq(id)),
+            None => query.filter(module_menu_unfinished::parent.is_null()),
+        };
+        let menus = query
+            .load::<ModuleMenu>(&mut connection)
+            .traced()
+            .await
+            .map_err(internal)?;
+        Ok(menus.into_iter().map(ModuleMenuObject).collect())
+    }
+
+    /// Fails if the request is not logged in.
+    async fn me(&self, ctx: &Context<'_>) -> async_graphql::Result<Me> {
+        let session = ctx
+            .data_opt::<Option<TucanSession>>()
+            .and_then(Option::as_ref)
+            .ok_or_else(|| async_graphql::Error::new("unauthorized"))?;
+        Ok(Me {
+            matriculation_number: session.matriculation_number,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{group, Query};
+    use async_graphql::{EmptyMutation, EmptySubscription, Schema};
+
+    #[test]
+    fn test_group() {
+        let grouped = group([(1, "a"), (2, "b"), (1, "c")]);
+        assert_eq!(grouped[&1], vec!["a", "c"]);
+        assert_eq!(grouped[&2], vec!["b"]);
+    }
+
+    #[test]
+    fn test_sdl() {
+        let sdl = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .finish()
+            .sdl();
+        assert!(sdl.contains("scalar ModuleId"));
+        assert!(sdl.contains("courses: [Course!]!"));
+        assert!(sdl.contains("me: Me!"));
+    }
+}
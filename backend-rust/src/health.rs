@@ -0,0 +1,267 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Whether the dependencies are usable, answered by `/readyz`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use diesel::migration::MigrationSource;
+use diesel::pg::Pg;
+use diesel::sql_types::Text;
+use diesel::QueryableByName;
+use diesel_async::RunQueryDsl;
+use itertools::Itertools;
+use log::warn;
+use serde::Serialize;
+
+use crate::config::{Config, SearchBackendKind};
+use crate::telemetry::Traced;
+use crate::tucan::{DbPool, Tucan};
+use crate::MIGRATIONS;
+
+/// A dependency that doesn't answer in this time counts as unavailable.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Why a check failed. Only the category is returned as `/readyz` is public, the details are
+/// logged.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckError {
+    /// No answer within [`CHECK_TIMEOUT`].
+    Timeout,
+    /// Not reachable or answered with an error.
+    Unavailable,
+    /// The database misses migrations of this version.
+    PendingMigrations,
+    /// The database has migrations this version doesn't know, so it is probably outdated.
+    UnknownMigrations,
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Timeout => "timed out",
+            Self::Unavailable => "unavailable",
+            Self::PendingMigrations => "pending migrations",
+            Self::UnknownMigrations => "unknown migrations",
+        })
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct CheckResult {
+    pub ok: bool,
+    pub duration_ms: u64,
+    /// Why the check failed, only set if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<CheckError>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Readiness {
+    /// Whether every check succeeded.
+    pub ready: bool,
+    pub checks: BTreeMap<&'static str, CheckResult>,
+}
+
+impl Readiness {
+    #[must_use]
+    pub fn new(checks: BTreeMap<&'static str, CheckResult>) -> Self {
+        Self {
+            ready: checks.values().all(|check| check.ok),
+            checks,
+        }
+    }
+}
+
+/// Runs `future` with the [`CHECK_TIMEOUT`] and measures how long it took. Errors are
+/// [`CheckError::Unavailable`] unless they are a [`CheckError`] themselves.
+pub async fn check(name: &str, future: impl Future<Output = anyhow::Result<()>>) -> CheckResult {
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, future)
+        .await
+        .unwrap_or_else(|_| Err(CheckError::Timeout.into()));
+    CheckResult {
+        ok: result.is_ok(),
+        duration_ms: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+        error: result.err().map(|err| {
+            warn!("readiness check {name} failed: {err:#}");
+            err.downcast_ref::<CheckError>()
+                .copied()
+                .unwrap_or(CheckError::Unavailable)
+        }),
+    }
+}
+
+async fn ping_database(pool: &DbPool) -> anyhow::Result<()> {
+    let mut connection = pool.get().await?;
+    diesel::sql_query("SELECT 1")
+        .execute(&mut connection)
+        .traced()
+        .await?;
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct AppliedMigration {
+    #[diesel(sql_type = Text)]
+    version: String,
+}
+
+/// Fails if `applied` misses one of the `known` migrations or the other way around.
+fn compare_migrations(known: &BTreeSet<String>, applied: &BTreeSet<String>) -> anyhow::Result<()> {
+    let pending = known.difference(applied).join(", ");
+    if !pending.is_empty() {
+        return Err(anyhow::Error::new(CheckError::PendingMigrations)
+            .context(format!("not applied: {pending}")));
+    }
+    let unknown = applied.difference(known).join(", ");
+    if !unknown.is_empty() {
+        return Err(anyhow::Error::new(CheckError::UnknownMigrations)
+            .context(format!("not part of this version: {unknown}")));
+    }
+    Ok(())
+}
+
+/// Fails if another instance applied migrations this one doesn't know about or the other way
+/// around, which would break queries.
+async fn pending_migrations(pool: &DbPool) -> anyhow::Result<()> {
+    let known = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|err| anyhow::anyhow!("{err}"))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+    let mut connection = pool.get().await?;
+    let applied = diesel::sql_query("SELECT version FROM __diesel_schema_migrations")
+        .load::<AppliedMigration>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    compare_migrations(&known, &applied)
+}
+
+async fn ping_tucan(tucan: &Tucan) -> anyhow::Result<()> {
+    tucan
+        .client
+        .get("https://www.tucan.tu-darmstadt.de/")
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Checks the database, its migrations, OpenSearch if it is the search backend and TUCaN if
+/// [`Config::readyz_checks_tucan`] is set.
+pub async fn readiness(tucan: &Tucan, config: &Config) -> Readiness {
+    let (database, migrations, opensearch, reachable) = futures::join!(
+        check("database", ping_database(&tucan.pool)),
+        check("migrations", pending_migrations(&tucan.pool)),
+        async {
+            if config.search_backend == SearchBackendKind::Opensearch {
+                Some(
+                    check("opensearch", async {
+                        tucan
+                            .search
+                            .ping()
+                            .await
+                            .map_err(|err| anyhow::anyhow!("{err}"))
+                    })
+                    .await,
+                )
+            } else {
+                None
+            }
+        },
+        async {
+            if config.readyz_checks_tucan {
+                Some(check("tucan", ping_tucan(tucan)).await)
+            } else {
+                None
+            }
+        },
+    );
+
+    let mut checks = BTreeMap::from([("database", database), ("migrations", migrations)]);
+    if let Some(opensearch) = opensearch {
+        checks.insert("opensearch", opensearch);
+    }
+    if let Some(reachable) = reachable {
+        checks.insert("tucan", reachable);
+    }
+    Readiness::new(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use diesel::migration::MigrationSource;
+    use diesel::pg::Pg;
+
+    use super::{check, compare_migrations, CheckError, CheckResult, Readiness};
+    use crate::MIGRATIONS;
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let ok = check("database", async { Ok(()) }).await;
+        assert!(ok.ok);
+        assert_eq!(ok.error, None);
+
+        let failed = check("database", async {
+            Err(anyhow::anyhow!("connection refused at 10.0.0.1"))
+        })
+        .await;
+        assert_eq!(failed.error, Some(CheckError::Unavailable));
+
+        let readiness = Readiness::new(BTreeMap::from([
+            ("database", ok),
+            (
+                "opensearch",
+                CheckResult {
+                    ok: false,
+                    duration_ms: 3,
+                    error: Some(CheckError::Timeout),
+                },
+            ),
+        ]));
+        assert!(!readiness.ready);
+        assert_eq!(
+            serde_json::to_value(&readiness).unwrap()["checks"]["opensearch"],
+            serde_json::json!({"ok": false, "duration_ms": 3, "error": "timeout"})
+        );
+
+        assert!(Readiness::new(BTreeMap::new()).ready);
+    }
+
+    #[tokio::test]
+    async fn test_migrations() {
+        let known: BTreeSet<String> = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+            .unwrap()
+            .iter()
+            .map(|migration| migration.name().version().to_string())
+            .collect();
+        // the versions as diesel stores them in __diesel_schema_migrations
+        assert!(known.contains("20220910005633"));
+        assert!(compare_migrations(&known, &known).is_ok());
+
+        let mut outdated = known.clone();
+        outdated.remove("20220910005633");
+        let pending = check("migrations", async {
+            compare_migrations(&known, &outdated)
+        })
+        .await;
+        assert_eq!(pending.error, Some(CheckError::PendingMigrations));
+
+        let mut newer = known.clone();
+        newer.insert("99990101000000".to_string());
+        let unknown = check("migrations", async { compare_migrations(&known, &newer) }).await;
+        assert_eq!(unknown.error, Some(CheckError::UnknownMigrations));
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Just enough of [RFC 5545](https://www.rfc-editor.org/rfc/rfc5545) to get
+//! courses and exams into calendar apps.
+
+use std::fmt::{Display, Formatter};
+
+use chrono::NaiveDateTime;
+
+/// Content lines must not be longer than this many octets, excluding the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// The time zone of all times from TUCaN.
+const TIMEZONE: &str = "Europe/Berlin";
+
+/// The rules of [`TIMEZONE`] since 1996, calendar apps need them to convert the local times.
+const VTIMEZONE: [&str; 17] = [
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/Berlin",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:+0100",
+    "TZOFFSETTO:+0200",
+    "TZNAME:CEST",
+    "DTSTART:19700329T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:+0200",
+    "TZOFFSETTO:+0100",
+    "TZNAME:CET",
+    "DTSTART:19701025T030000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Has to stay the same across exports so calendar apps update the event instead of duplicating it.
+    pub uid: String,
+    /// Local time in Europe/Berlin as shown by TUCaN, like all event times in the database.
+    pub start: NaiveDateTime,
+    /// `None` for points in time like registration deadlines.
+    pub end: Option<NaiveDateTime>,
+    pub summary: String,
+    pub location: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    pub name: String,
+    /// When the calendar was generated in UTC, this is the `DTSTAMP` of all events.
+    pub timestamp: NaiveDateTime,
+    pub events: Vec<Event>,
+}
+
+/// Escapes the characters that have a meaning in TEXT values.
+#[must_use]
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            ';' => result.push_str("\\;"),
+            ',' => result.push_str("\\,"),
+            '\n' => result.push_str("\\n"),
+            '\r' => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn format_utc(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A `DTSTART` or `DTEND` property in [`TIMEZONE`].
+fn format_local(name: &str, time: NaiveDateTime) -> String {
+    format!("{name};TZID={TIMEZONE}:{}", time.format("%Y%m%dT%H%M%S"))
+}
+
+/// Writes a content line, folding it after [`MAX_LINE_LENGTH`] octets without splitting characters.
+fn write_line(f: &mut Formatter<'_>, line: &str) -> std::fmt::Result {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            // the space that starts the continuation line counts towards its length
+            f.write_str("\r\n ")?;
+            length = 1;
+        }
+        write!(f, "{c}")?;
+        length += c.len_utf8();
+    }
+    f.write_str("\r\n")
+}
+
+impl Event {
+    fn write(&self, f: &mut Formatter<'_>, timestamp: &str) -> std::fmt::Result {
+        write_line(f, "BEGIN:VEVENT")?;
+        write_line(f, &format!("UID:{}", escape(&self.uid)))?;
+        write_line(f, &format!("DTSTAMP:{timestamp}"))?;
+        write_line(f, &format_local("DTSTART", self.start))?;
+        if let Some(end) = self.end {
+            write_line(f, &format_local("DTEND", end))?;
+        }
+        write_line(f, &format!("SUMMARY:{}", escape(&self.summary)))?;
+        if let Some(location) = &self.location {
+            write_line(f, &format!("LOCATION:{}", escape(location)))?;
+        }
+        if let Some(description) = &self.description {
+            write_line(f, &format!("DESCRIPTION:{}", escape(description)))?;
+        }
+        write_line(f, "END:VEVENT")
+    }
+}
+
+impl Display for Calendar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_line(f, "BEGIN:VCALENDAR")?;
+        write_line(f, "VERSION:2.0")?;
+        write_line(f, "PRODID:-//tucant//tucant//DE")?;
+        write_line(f, "CALSCALE:GREGORIAN")?;
+        write_line(f, &format!("X-WR-CALNAME:{}", escape(&self.name)))?;
+        // subscribed calendars are only refetched this often
+        write_line(f, "REFRESH-INTERVAL;VALUE=DURATION:PT6H")?;
+        write_line(f, "X-PUBLISHED-TTL:PT6H")?;
+        for line in VTIMEZONE {
+            write_line(f, line)?;
+        }
+        let timestamp = format_utc(self.timestamp);
+        for event in &self.events {
+            event.write(f, &timestamp)?;
+        }
+        write_line(f, "END:VCALENDAR")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{escape, Calendar, Event};
+
+    fn time(hour: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 16)
+            .unwrap()
+            .and_hms_opt(hour, 15, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("Raum S2|02, C205; Gebäude\\\r\nzwei"),
+            "Raum S2|02\\, C205\\; Gebäude\\\\\\nzwei"
+        );
+    }
+
+    #[test]
+    fn test_folding() {
+        let calendar = Calendar {
+            name: "ä".repeat(100),
+            timestamp: time(8),
+            events: Vec::new(),
+        };
+        let ics = calendar.to_string();
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "{line:?} is too long");
+        }
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("\r\nX-WR-CALNAME:{}\r\n", "ä".repeat(100))));
+    }
+
+    #[test]
+    fn test_calendar() {
+        let calendar = Calendar {
+            name: "TUCaN".to_string(),
+            timestamp: time(8),
+            events: vec![
+                Event {
+                    uid: "course-1@tucant".to_string(),
+                    start: time(9),
+                    end: Some(time(10)),
+                    summary: "Funktionale und objektorientierte Programmierkonzepte".to_string(),
+                    location: Some("S1|01/A1".to_string()),
+                    description: Some("Prof. Dr. Karsten Weihe".to_string()),
+                },
+                Event {
+                    uid: "exam-2-registration-end@tucant".to_string(),
+                    start: time(23),
+                    end: None,
+                    summary: "Anmeldeschluss: Klausur".to_string(),
+                    location: None,
+                    description: None,
+                },
+            ],
+        };
+        assert_eq!(
+            calendar.to_string(),
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//tucant//tucant//DE",
+                "CALSCALE:GREGORIAN",
+                "X-WR-CALNAME:TUCaN",
+                "REFRESH-INTERVAL;VALUE=DURATION:PT6H",
+                "X-PUBLISHED-TTL:PT6H",
+                "BEGIN:VTIMEZONE",
+                "TZID:Europe/Berlin",
+                "BEGIN:DAYLIGHT",
+                "TZOFFSETFROM:+0100",
+                "TZOFFSETTO:+0200",
+                "TZNAME:CEST",
+                "DTSTART:19700329T020000",
+                "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+                "END:DAYLIGHT",
+                "BEGIN:STANDARD",
+                "TZOFFSETFROM:+0200",
+                "TZOFFSETTO:+0100",
+                "TZNAME:CET",
+                "DTSTART:19701025T030000",
+                "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+                "END:STANDARD",
+                "END:VTIMEZONE",
+                "BEGIN:VEVENT",
+                "UID:course-1@tucant",
+                "DTSTAMP:20230116T081500Z",
+                "DTSTART;TZID=Europe/Berlin:20230116T091500",
+                "DTEND;TZID=Europe/Berlin:20230116T101500",
+                "SUMMARY:Funktionale und objektorientierte Programmierkonzepte",
+                "LOCATION:S1|01/A1",
+                "DESCRIPTION:Prof. Dr. Karsten Weihe",
+                "END:VEVENT",
+                "BEGIN:VEVENT",
+                "UID:exam-2-registration-end@tucant",
+                "DTSTAMP:20230116T081500Z",
+                "DTSTART;TZID=Europe/Berlin:20230116T231500",
+                "SUMMARY:Anmeldeschluss: Klausur",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+}
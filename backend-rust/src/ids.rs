@@ -0,0 +1,312 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Typed identifiers for the entities we scrape from TUCaN.
+//!
+//! TUCaN identifies everything by one or more numbers in the url arguments.
+//! We store these numbers concatenated as big endian bytes so they can be used
+//! as `bytea` primary keys, and send them to the frontend as url safe base64.
+
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "server")]
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{Pg, PgValue},
+    serialize::{self, Output, ToSql},
+    sql_types::Bytea,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const NUMBER: usize = std::mem::size_of::<u64>();
+
+fn numbers<const N: usize>(bytes: &[u8]) -> [u64; N] {
+    let mut chunks = bytes.chunks_exact(NUMBER);
+    [(); N].map(|()| u64::from_be_bytes(chunks.next().unwrap().try_into().unwrap()))
+}
+
+const fn base64_engine() -> base64::engine::fast_portable::FastPortable {
+    base64::engine::fast_portable::FastPortable::from(
+        &base64::alphabet::URL_SAFE,
+        base64::engine::fast_portable::NO_PAD,
+    )
+}
+
+/// Why a string could not be parsed as an id.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdError {
+    InvalidBase64(base64::DecodeError),
+    InvalidLength { kind: &'static str, length: usize },
+}
+
+impl Display for IdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidBase64(err) => write!(f, "invalid base64 in id: {err}"),
+            Self::InvalidLength { kind, length } => {
+                write!(f, "{length} bytes are not a valid {kind}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidBase64(err) => Some(err),
+            Self::InvalidLength { .. } => None,
+        }
+    }
+}
+
+macro_rules! tucan_id {
+    ($(#[$meta:meta])* $name:ident, $valid:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+        #[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Bytea))]
+        pub struct $name(Vec<u8>);
+
+        impl $name {
+            /// Wraps bytes that were read from the database or created by [`Self::as_bytes`],
+            /// the accessors of the id rely on their length being checked here.
+            pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, IdError> {
+                let valid: fn(&[u8]) -> bool = $valid;
+                if valid(&bytes) {
+                    Ok(Self(bytes))
+                } else {
+                    Err(IdError::InvalidLength {
+                        kind: stringify!($name),
+                        length: bytes.len(),
+                    })
+                }
+            }
+
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.0
+            }
+
+            #[must_use]
+            pub fn into_bytes(self) -> Vec<u8> {
+                self.0
+            }
+
+            pub fn from_base64(value: &str) -> Result<Self, IdError> {
+                Self::from_bytes(
+                    base64::decode_engine(value, &base64_engine()).map_err(IdError::InvalidBase64)?,
+                )
+            }
+
+            #[must_use]
+            pub fn to_base64(&self) -> String {
+                base64::encode_engine(&self.0, &base64_engine())
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.to_base64())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = IdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::from_base64(s)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.to_base64())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let string = String::deserialize(deserializer)?;
+                Self::from_base64(&string).map_err(serde::de::Error::custom)
+            }
+        }
+
+        #[cfg(feature = "server")]
+        impl ToSql<Bytea, Pg> for $name {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                <[u8] as ToSql<Bytea, Pg>>::to_sql(&self.0, out)
+            }
+        }
+
+        #[cfg(feature = "server")]
+        impl FromSql<Bytea, Pg> for $name {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                Ok(Self::from_bytes(<Vec<u8> as FromSql<Bytea, Pg>>::from_sql(bytes)?)?)
+            }
+        }
+
+        #[cfg(feature = "server")]
+        impl tucant_derive_lib::Typescriptable for $name {
+            fn name() -> String {
+                stringify!($name).to_string()
+            }
+
+            fn code() -> std::collections::BTreeSet<String> {
+                std::collections::BTreeSet::from([format!(
+                    "export type {0} = string & {{ __brand: \"{0}\" }}",
+                    stringify!($name)
+                )])
+            }
+
+            fn schema() -> serde_json::Value {
+                tucant_derive_lib::schema_ref(stringify!($name))
+            }
+
+            fn schemas() -> std::collections::BTreeMap<String, serde_json::Value> {
+                std::collections::BTreeMap::from([(
+                    stringify!($name).to_string(),
+                    serde_json::json!({ "type": "string", "contentEncoding": "base64url" }),
+                )])
+            }
+        }
+    };
+}
+
+tucan_id!(
+    /// The id of a module, the argument of `MODULEDETAILS` urls.
+    ModuleId,
+    |bytes| bytes.len() == NUMBER || bytes == ModuleId::TUCANSCHEISS
+);
+
+tucan_id!(
+    /// The id of a course or course group, the two arguments of `COURSEDETAILS` urls.
+    /// The first number identifies the course, the second one the group inside it.
+    CourseId,
+    |bytes| bytes.len() == 2 * NUMBER
+);
+
+tucan_id!(
+    /// The id of an exam, the argument of `EXAMDETAILS` urls.
+    ExamId,
+    |bytes| bytes.len() == NUMBER
+);
+
+tucan_id!(
+    /// The path of a module menu, the three numbers of `REGISTRATION` urls.
+    MenuPath,
+    |bytes| bytes.len() == 3 * NUMBER
+);
+
+impl ModuleId {
+    /// Courses in the registration that don't belong to any module are put into this one.
+    /// See <https://github.com/mohe2015/tucant/issues/104>.
+    const TUCANSCHEISS: &[u8] = &[77, 64, 128, 53, 32, 135, 16, 132, 146];
+
+    #[must_use]
+    pub fn new(number: u64) -> Self {
+        Self(number.to_be_bytes().to_vec())
+    }
+
+    #[must_use]
+    pub fn tucanscheiss() -> Self {
+        Self(Self::TUCANSCHEISS.to_vec())
+    }
+
+    #[must_use]
+    pub fn is_tucanscheiss(&self) -> bool {
+        self.0 == Self::TUCANSCHEISS
+    }
+
+    /// The number TUCaN uses for this module, `None` for [`Self::tucanscheiss`].
+    #[must_use]
+    pub fn number(&self) -> Option<u64> {
+        (!self.is_tucanscheiss()).then(|| numbers::<1>(&self.0)[0])
+    }
+}
+
+impl CourseId {
+    #[must_use]
+    pub fn new(course: u64, group: u64) -> Self {
+        Self([course.to_be_bytes(), group.to_be_bytes()].concat())
+    }
+
+    /// This *should* be unique per course.
+    #[must_use]
+    pub fn course(&self) -> u64 {
+        numbers::<2>(&self.0)[0]
+    }
+
+    /// This *should* be different per sub-group in a course and the course itself,
+    /// but you can't find out which of these is the top-level course page without fetching.
+    #[must_use]
+    pub fn group(&self) -> u64 {
+        numbers::<2>(&self.0)[1]
+    }
+}
+
+impl ExamId {
+    #[must_use]
+    pub fn new(number: u64) -> Self {
+        Self(number.to_be_bytes().to_vec())
+    }
+
+    #[must_use]
+    pub fn number(&self) -> u64 {
+        numbers::<1>(&self.0)[0]
+    }
+}
+
+impl MenuPath {
+    #[must_use]
+    pub fn new(parts: [u64; 3]) -> Self {
+        Self(parts.map(u64::to_be_bytes).concat())
+    }
+
+    #[must_use]
+    pub fn parts(&self) -> [u64; 3] {
+        numbers::<3>(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CourseId, IdError, MenuPath, ModuleId};
+
+    #[test]
+    fn test_base64() {
+        let id = CourseId::new(379_144_023_730_730, 379_144_023_752_731);
+        assert_eq!(CourseId::from_base64(&id.to_base64()), Ok(id.clone()));
+        assert_eq!(id.course(), 379_144_023_730_730);
+        assert_eq!(id.group(), 379_144_023_752_731);
+
+        let path = MenuPath::new([376_333_755_785_484, 356_173_456_785_530, 0]);
+        assert_eq!(path.parts(), [376_333_755_785_484, 356_173_456_785_530, 0]);
+    }
+
+    #[test]
+    fn test_tucanscheiss() {
+        let id = ModuleId::from_base64("TUCANSCHEISS").unwrap();
+        assert!(id.is_tucanscheiss());
+        assert_eq!(id, ModuleId::tucanscheiss());
+        assert_eq!(id.number(), None);
+        assert_eq!(ModuleId::new(42).number(), Some(42));
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let module = ModuleId::new(42).to_base64();
+        assert_eq!(
+            CourseId::from_base64(&module),
+            Err(IdError::InvalidLength {
+                kind: "CourseId",
+                length: 8
+            })
+        );
+        assert!(matches!(
+            ModuleId::from_base64("not base64!"),
+            Err(IdError::InvalidBase64(_))
+        ));
+    }
+}
@@ -9,16 +9,47 @@
     clippy::multiple_crate_versions
 )]
 
+#[cfg(feature = "server")]
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
 
 use axum::response::{IntoResponse, Response};
+use axum::Json;
 use reqwest::StatusCode;
+use serde::Serialize;
+#[cfg(feature = "server")]
+use tucant_derive::Typescriptable;
 extern crate self as tucant;
 
+#[cfg(feature = "server")]
+pub mod config;
+#[cfg(feature = "server")]
+pub mod credentials;
+#[cfg(feature = "server")]
+pub mod csrf;
+#[cfg(feature = "server")]
+pub mod graphql;
+#[cfg(feature = "server")]
+pub mod health;
+pub mod ical;
+pub mod ids;
 pub mod models;
 #[cfg(feature = "server")]
 pub mod schema;
 #[cfg(feature = "server")]
+pub mod search;
+#[cfg(feature = "server")]
+pub mod search_backend;
+pub mod search_syntax;
+#[cfg(feature = "server")]
+pub mod session;
+#[cfg(feature = "server")]
+pub mod snapshot;
+#[cfg(feature = "server")]
+pub mod sso;
+#[cfg(feature = "server")]
+pub mod telemetry;
+#[cfg(feature = "server")]
 pub mod tucan;
 #[cfg(feature = "server")]
 pub mod tucan_user;
@@ -27,26 +58,249 @@ pub mod typescript;
 #[cfg(feature = "server")]
 pub mod url;
 
+/// The migrations of this version, the server applies them on startup.
+#[cfg(feature = "server")]
+pub const MIGRATIONS: diesel_migrations::EmbeddedMigrations =
+    diesel_migrations::embed_migrations!();
+
+/// Errors from talking to TUCaN that callers may want to handle, wrapped in an [`anyhow::Error`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TucanError {
+    /// TUCaN ended the session, the user has to log in again.
+    SessionExpired,
+    InvalidCredentials,
+    /// A page contains a link we can't parse or that leads somewhere else than expected, so
+    /// TUCaN probably changed.
+    UnexpectedLink,
+}
+
+impl Display for TucanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SessionExpired => f.write_str("the TUCaN session expired"),
+            Self::InvalidCredentials => f.write_str("invalid username or password"),
+            Self::UnexpectedLink => f.write_str("TUCaN returned a page with an unexpected link"),
+        }
+    }
+}
+
+impl std::error::Error for TucanError {}
+
+/// The error of all API routes. Only the kind of error and the details of
+/// [`MyError::BadRequest`] and [`MyError::InvalidQuery`] are sent to the client, everything else is logged.
 #[derive(Debug)]
-pub struct MyError {
-    err: anyhow::Error,
+pub enum MyError {
+    /// No valid session cookie was sent.
+    Unauthorized,
+    SessionExpired,
+    NotFound,
+    BadRequest(String),
+    /// A search query that doesn't follow the syntax in [`search_syntax`].
+    InvalidQuery(search_syntax::QueryError),
+    /// TUCaN, the database or OpenSearch could not be reached or returned garbage.
+    UpstreamUnavailable(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+/// What the client gets to see of a [`MyError`]. The `type` is a stable error code the frontend can match on.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "server", derive(Typescriptable))]
+#[serde(tag = "type", content = "value")]
+pub enum ApiError {
+    Unauthorized,
+    SessionExpired,
+    NotFound,
+    BadRequest(String),
+    InvalidQuery(search_syntax::QueryError),
+    UpstreamUnavailable,
+    Internal,
 }
 
 impl Display for MyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.err.fmt(f)
+        match self {
+            Self::Unauthorized => f.write_str("unauthorized"),
+            Self::SessionExpired => f.write_str("session expired"),
+            Self::NotFound => f.write_str("not found"),
+            Self::BadRequest(message) => write!(f, "bad request: {message}"),
+            Self::InvalidQuery(err) => write!(f, "invalid query: {err}"),
+            Self::UpstreamUnavailable(err) => write!(f, "upstream unavailable: {err}"),
+            Self::Internal(err) => err.fmt(f),
+        }
     }
 }
 
+/// The error of type `T` in `err`, also if it was wrapped with [`anyhow::Context`] or is the
+/// source of another error.
+fn find_cause<T: std::error::Error + Send + Sync + 'static>(err: &anyhow::Error) -> Option<&T> {
+    // downcasting finds context and the errors it wraps, the chain finds sources
+    err.downcast_ref::<T>()
+        .or_else(|| err.chain().find_map(|cause| cause.downcast_ref::<T>()))
+}
+
 impl<E: Into<anyhow::Error>> From<E> for MyError {
     fn from(err: E) -> Self {
-        Self { err: err.into() }
+        let err: anyhow::Error = err.into();
+        if let Some(tucan_error) = find_cause::<TucanError>(&err).copied() {
+            return match tucan_error {
+                TucanError::SessionExpired => Self::SessionExpired,
+                TucanError::InvalidCredentials => Self::Unauthorized,
+                TucanError::UnexpectedLink => Self::UpstreamUnavailable(err),
+            };
+        }
+        match find_cause::<diesel::result::Error>(&err) {
+            Some(diesel::result::Error::NotFound) => return Self::NotFound,
+            // like ids in the database that don't parse, which is not the fault of the client
+            Some(_) => return Self::Internal(err),
+            None => {}
+        }
+        if let Some(query_error) = find_cause::<search_syntax::QueryError>(&err) {
+            return Self::InvalidQuery(query_error.clone());
+        }
+        if find_cause::<crate::ids::IdError>(&err).is_some()
+            || find_cause::<crate::url::UrlError>(&err).is_some()
+        {
+            return Self::BadRequest(err.to_string());
+        }
+        if find_cause::<reqwest::Error>(&err).is_some()
+            || find_cause::<opensearch::Error>(&err).is_some()
+            || find_cause::<diesel::ConnectionError>(&err).is_some()
+            || find_cause::<deadpool::managed::PoolError<diesel_async::pooled_connection::PoolError>>(
+                &err,
+            )
+            .is_some()
+        {
+            return Self::UpstreamUnavailable(err);
+        }
+        Self::Internal(err)
+    }
+}
+
+impl MyError {
+    #[must_use]
+    pub const fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unauthorized | Self::SessionExpired => StatusCode::UNAUTHORIZED,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::BadRequest(_) | Self::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            Self::UpstreamUnavailable(_) => StatusCode::BAD_GATEWAY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The `type` of the [`ApiError`], used as metric label.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Unauthorized => "Unauthorized",
+            Self::SessionExpired => "SessionExpired",
+            Self::NotFound => "NotFound",
+            Self::BadRequest(_) => "BadRequest",
+            Self::InvalidQuery(_) => "InvalidQuery",
+            Self::UpstreamUnavailable(_) => "UpstreamUnavailable",
+            Self::Internal(_) => "Internal",
+        }
+    }
+
+    #[must_use]
+    pub fn to_api_error(&self) -> ApiError {
+        match self {
+            Self::Unauthorized => ApiError::Unauthorized,
+            Self::SessionExpired => ApiError::SessionExpired,
+            Self::NotFound => ApiError::NotFound,
+            Self::BadRequest(message) => ApiError::BadRequest(message.clone()),
+            Self::InvalidQuery(err) => ApiError::InvalidQuery(err.clone()),
+            Self::UpstreamUnavailable(_) => ApiError::UpstreamUnavailable,
+            Self::Internal(_) => ApiError::Internal,
+        }
     }
 }
 
 impl IntoResponse for MyError {
     fn into_response(self) -> Response {
-        println!("{:?}", self.err);
-        (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", self.err)).into_response()
+        match &self {
+            Self::UpstreamUnavailable(err) | Self::Internal(err) => {
+                tracing::error!("{err:?}");
+            }
+            _ => tracing::debug!("{self}"),
+        }
+        #[cfg(feature = "server")]
+        telemetry::API_ERRORS
+            .with_label_values(&[self.kind()])
+            .inc();
+        (self.status_code(), Json(self.to_api_error())).into_response()
+    }
+}
+
+#[cfg(feature = "server")]
+impl tucant_derive_lib::Typescriptable for MyError {
+    fn name() -> String {
+        <ApiError as tucant_derive_lib::Typescriptable>::name()
+    }
+
+    fn code() -> BTreeSet<String> {
+        <ApiError as tucant_derive_lib::Typescriptable>::code()
+    }
+
+    fn schema() -> serde_json::Value {
+        <ApiError as tucant_derive_lib::Typescriptable>::schema()
+    }
+
+    fn schemas() -> BTreeMap<String, serde_json::Value> {
+        <ApiError as tucant_derive_lib::Typescriptable>::schemas()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ApiError, MyError, TucanError};
+
+    #[test]
+    fn test_error_classification() {
+        let error = MyError::from(anyhow::Error::from(TucanError::SessionExpired));
+        assert_eq!(error.to_api_error(), ApiError::SessionExpired);
+        assert_eq!(error.status_code().as_u16(), 401);
+
+        let error = MyError::from(
+            anyhow::anyhow!("the link leads to logout").context(TucanError::UnexpectedLink),
+        );
+        assert_eq!(error.to_api_error(), ApiError::UpstreamUnavailable);
+
+        let error = MyError::from(diesel::result::Error::NotFound);
+        assert_eq!(error.status_code().as_u16(), 404);
+
+        // wrapped in context
+        let error = MyError::from(
+            anyhow::Error::from(TucanError::SessionExpired).context("failed to fetch the module"),
+        );
+        assert_eq!(error.to_api_error(), ApiError::SessionExpired);
+        let error = MyError::from(
+            anyhow::Error::from(diesel::result::Error::NotFound).context("no such module"),
+        );
+        assert_eq!(error.to_api_error(), ApiError::NotFound);
+        let error = MyError::from(anyhow::Error::from(
+            diesel::result::Error::DeserializationError(
+                crate::ids::ModuleId::from_base64("!").unwrap_err().into(),
+            ),
+        ));
+        assert_eq!(error.kind(), "Internal");
+
+        let error = MyError::from(crate::ids::ModuleId::from_base64("!").unwrap_err());
+        assert!(matches!(error.to_api_error(), ApiError::BadRequest(_)));
+        assert_eq!(error.kind(), "BadRequest");
+
+        let error = MyError::from(crate::search_syntax::ParsedQuery::parse("sws:x").unwrap_err());
+        assert_eq!(error.status_code().as_u16(), 400);
+        assert_eq!(
+            serde_json::to_string(&error.to_api_error()).unwrap(),
+            r#"{"type":"InvalidQuery","value":{"message":"expected a whole number","position":4}}"#
+        );
+
+        // internal details are not sent to the client
+        let error = MyError::from(anyhow::anyhow!("connection string with password"));
+        assert_eq!(
+            serde_json::to_string(&error.to_api_error()).unwrap(),
+            r#"{"type":"Internal"}"#
+        );
     }
 }
@@ -2,13 +2,11 @@
 
 use std::collections::VecDeque;
 
-use axum::extract::FromRef;
+use std::convert::Infallible;
+
 use axum::extract::FromRequestParts;
+use axum::http::header::ACCEPT_LANGUAGE;
 use axum::http::request::Parts;
-use axum::response::IntoResponse;
-use axum::response::Response;
-use axum_extra::extract::cookie::Key;
-use axum_extra::extract::PrivateCookieJar;
 // SPDX-FileCopyrightText: The tucant Contributors
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
@@ -28,12 +26,13 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(feature = "server")]
 use tucant_derive::Typescriptable;
 
+use crate::ids::{CourseId, ExamId, MenuPath, ModuleId};
 #[cfg(feature = "server")]
 use crate::schema::{
-    course_events, course_exams, course_groups_events, course_groups_unfinished,
+    calendar_tokens, course_events, course_exams, course_groups_events, course_groups_unfinished,
     courses_unfinished, exams_unfinished, module_courses, module_exams, module_menu_module,
-    module_menu_unfinished, modules_unfinished, sessions, user_course_groups, user_courses,
-    user_exams, user_modules, users_unfinished,
+    module_menu_unfinished, modules_unfinished, user_course_groups, user_courses, user_exams,
+    user_modules, users_unfinished,
 };
 
 pub fn as_base64<T, S>(buffer: &T, serializer: S) -> Result<S::Ok, S::Error>
@@ -67,41 +66,6 @@ where
     })
 }
 
-pub fn as_option_base64<T, S>(buffer: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    T: AsRef<[u8]>,
-    S: Serializer,
-{
-    if let Some(ref buffer) = *buffer {
-        as_base64(buffer, serializer)
-    } else {
-        serializer.serialize_none()
-    }
-}
-
-pub fn from_option_base64<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    use serde::de::Error;
-    let string: Option<String> = Option::deserialize(deserializer)?;
-
-    string.map_or_else(
-        || Ok(None),
-        |string| {
-            base64::decode_engine(
-                string,
-                &base64::engine::fast_portable::FastPortable::from(
-                    &base64::alphabet::URL_SAFE,
-                    base64::engine::fast_portable::NO_PAD,
-                ),
-            )
-            .map(Option::Some)
-            .map_err(|err| Error::custom(err.to_string()))
-        },
-    )
-}
-
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
 #[cfg_attr(
     feature = "server",
@@ -111,15 +75,15 @@ where
 #[cfg_attr(feature = "server", diesel(table_name = modules_unfinished))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct Module {
-    #[cfg_attr(feature = "server", ts_type(String))]
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub tucan_id: Vec<u8>,
+    pub tucan_id: ModuleId,
     pub tucan_last_checked: NaiveDateTime,
     pub title: String,
     pub module_id: String,
     pub credits: Option<i32>,
     pub content: String,
     pub done: bool,
+    pub title_en: String,
+    pub content_en: String,
 }
 
 #[cfg_attr(feature = "server", derive(QueryableByName, Typescriptable))]
@@ -127,11 +91,9 @@ pub struct Module {
 pub struct ModuleMenuPathPart {
     #[cfg_attr(feature = "server", diesel(sql_type = Nullable<Bytea>))]
     #[serde(skip)]
-    pub parent: Option<Vec<u8>>,
+    pub parent: Option<MenuPath>,
     #[cfg_attr(feature = "server", diesel(sql_type = Bytea))]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub tucan_id: Vec<u8>,
+    pub tucan_id: MenuPath,
     #[cfg_attr(feature = "server", diesel(sql_type = Text))]
     pub name: String,
     #[cfg_attr(feature = "server", diesel(sql_type = Bool))]
@@ -177,21 +139,14 @@ pub struct ModuleResponse {
 #[cfg_attr(feature = "server", diesel(table_name = module_menu_unfinished))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = false))]
 pub struct ModuleMenu {
-    #[cfg_attr(feature = "server", ts_type(String))]
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub tucan_id: Vec<u8>,
+    pub tucan_id: MenuPath,
     pub tucan_last_checked: NaiveDateTime,
     pub name: String,
     pub done: bool,
-    #[cfg_attr(feature = "server", ts_type(String))]
-    #[serde(
-        serialize_with = "as_option_base64",
-        deserialize_with = "from_option_base64"
-    )]
-    pub parent: Option<Vec<u8>>,
+    pub parent: Option<MenuPath>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[cfg_attr(
     feature = "server",
     derive(Associations, Identifiable, Queryable, Insertable,)
@@ -201,10 +156,8 @@ pub struct ModuleMenu {
 #[cfg_attr(feature = "server", diesel(belongs_to(ModuleMenu)))]
 #[cfg_attr(feature = "server", diesel(belongs_to(Module)))]
 pub struct ModuleMenuEntryModule {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub module_menu_id: Vec<u8>,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub module_id: Vec<u8>,
+    pub module_menu_id: MenuPath,
+    pub module_id: ModuleId,
 }
 
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
@@ -224,15 +177,15 @@ pub struct ModuleMenuEntryModule {
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 #[diesel(belongs_to(ModuleCourse, foreign_key = tucan_id))]
 pub struct Course {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub tucan_id: Vec<u8>,
+    pub tucan_id: CourseId,
     pub tucan_last_checked: NaiveDateTime,
     pub title: String,
     pub course_id: String,
     pub sws: i16,
     pub content: String,
     pub done: bool,
+    pub title_en: String,
+    pub content_en: String,
 }
 
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
@@ -252,12 +205,8 @@ pub struct Course {
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 #[cfg_attr(feature = "server", diesel(belongs_to(Course, foreign_key = course)))]
 pub struct CourseGroup {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub tucan_id: Vec<u8>,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub course: Vec<u8>,
+    pub tucan_id: CourseId,
+    pub course: CourseId,
     pub title: String,
     pub done: bool,
 }
@@ -272,38 +221,39 @@ pub struct CourseGroup {
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 #[cfg_attr(feature = "server", diesel(belongs_to(Module, foreign_key = module)))]
 pub struct ModuleCourse {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub module: Vec<u8>,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    pub course: Vec<u8>,
+    pub module: ModuleId,
+    pub course: CourseId,
 }
 
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
-#[cfg_attr(feature = "server", derive(Identifiable, Queryable, Insertable))]
+#[cfg_attr(
+    feature = "server",
+    derive(Identifiable, Queryable, Insertable, Typescriptable)
+)]
 #[cfg_attr(feature = "server", diesel(primary_key(matriculation_number)))]
 #[cfg_attr(feature = "server", diesel(table_name = users_unfinished))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct User {
-    matriculation_number: i32,
-    title: String,
-    academic_title: String,
-    post_name: String,
-    first_name: String,
-    middle_name: String,
-    last_name: String,
-    pre_name: String,
-    redirect_messages_to_university_email: bool,
-    subject: String,
-    email: String,
-    department: i32,
-    post_title: String,
-    street: String,
-    address_addition: String,
-    country: String,
-    plz: i32,
-    city: String,
-    phone_number: String,
-    done: bool,
+    pub matriculation_number: i32,
+    pub title: String,
+    pub academic_title: String,
+    pub post_name: String,
+    pub first_name: String,
+    pub middle_name: String,
+    pub last_name: String,
+    pub pre_name: String,
+    pub redirect_messages_to_university_email: bool,
+    pub subject: String,
+    pub email: String,
+    pub department: i32,
+    pub post_title: String,
+    pub street: String,
+    pub address_addition: String,
+    pub country: String,
+    pub plz: i32,
+    pub city: String,
+    pub phone_number: String,
+    pub done: bool,
 }
 
 // TODO FIXME maybe we can convert this to a user enum with undone and done
@@ -327,46 +277,221 @@ impl UndoneUser {
     }
 }
 
+/// A session at TUCaN, stored in `sessions` and never sent to the client, see [`crate::session`].
+#[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "server", derive(Queryable))]
+pub struct TucanSession {
+    pub matriculation_number: i32,
+    pub session_nr: i64,
+    pub session_id: String,
+}
+
+/// The secret in the url of a user's calendar subscription.
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone)]
 #[cfg_attr(
     feature = "server",
     derive(Identifiable, Queryable, Insertable, Typescriptable)
 )]
-#[cfg_attr(
-    feature = "server",
-    diesel(primary_key(matriculation_number, session_nr, session_id))
-)]
-#[cfg_attr(feature = "server", diesel(table_name = sessions))]
+#[cfg_attr(feature = "server", diesel(primary_key(token)))]
+#[cfg_attr(feature = "server", diesel(table_name = calendar_tokens))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
-pub struct TucanSession {
+pub struct CalendarToken {
+    pub token: String,
     pub matriculation_number: i32,
-    pub session_nr: i64,
-    pub session_id: String,
+    pub created: NaiveDateTime,
+}
+
+/// A login as listed to the user, the id in the cookie is not included.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "server", derive(Typescriptable))]
+pub struct SessionInfo {
+    /// Identifies the session for revoking it.
+    pub handle: i64,
+    pub created: NaiveDateTime,
+    pub last_used: NaiveDateTime,
+    pub expires: NaiveDateTime,
+    pub user_agent: Option<String>,
+    /// Whether this is the session of the request.
+    pub current: bool,
+}
+
+/// Something that happened with the stored TUCaN credentials of a user, see
+/// [`crate::credentials`].
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "server", derive(Queryable, Typescriptable))]
+pub struct CredentialAuditEntry {
+    /// `stored`, `used`, `login_failed`, `wrong_user` or `revoked`.
+    pub event: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "server", derive(Typescriptable))]
+pub struct CredentialStatus {
+    /// When the credentials were stored, unset if there are none.
+    pub consented: Option<NaiveDateTime>,
+    pub last_used: Option<NaiveDateTime>,
+    /// The most recent entry first.
+    pub audit_log: Vec<CredentialAuditEntry>,
+}
+
+/// The languages TUCaN serves its pages in.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub enum Language {
+    #[default]
+    German,
+    English,
+}
+
+impl Language {
+    /// The number TUCaN uses for this language in `CHANGELANGUAGE` urls.
+    #[must_use]
+    pub const fn tucan_id(self) -> u64 {
+        match self {
+            Self::German => 1,
+            Self::English => 2,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_tucan_id(id: u64) -> Option<Self> {
+        match id {
+            1 => Some(Self::German),
+            2 => Some(Self::English),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code
+            .trim()
+            .split(['-', '_'])
+            .next()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "de" => Some(Self::German),
+            "en" => Some(Self::English),
+            _ => None,
+        }
+    }
+
+    /// Picks the preferred supported language of an `Accept-Language` header value.
+    #[must_use]
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let language = Self::from_code(parts.next()?)?;
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                Some((language, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .fold(None, |best: Option<(Self, f32)>, current| match best {
+                Some(best) if best.1 >= current.1 => Some(best),
+                _ => Some(current),
+            })
+            .map(|(language, _)| language)
+    }
 }
 
+/// Selects the response language from the `lang` query parameter or the `Accept-Language` header.
 #[axum::async_trait]
-impl<S> FromRequestParts<S> for TucanSession
+impl<S> FromRequestParts<S> for Language
 where
-    Key: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = Response;
-
-    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let cookie_jar = PrivateCookieJar::<Key>::from_request_parts(parts, state)
-            .await
-            .map_err(axum::response::IntoResponse::into_response)?;
-
-        let session: Self = serde_json::from_str(
-            cookie_jar
-                .get("session")
-                .ok_or_else(|| {
-                    (axum::http::StatusCode::UNAUTHORIZED, "session not found").into_response()
-                })?
-                .value(),
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let from_query = parts.uri.query().and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "lang")
+                .and_then(|(_, value)| Self::from_code(value))
+        });
+
+        let from_header = || {
+            parts
+                .headers
+                .get(ACCEPT_LANGUAGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(Self::from_accept_language)
+        };
+
+        Ok(from_query.or_else(from_header).unwrap_or_default())
+    }
+}
+
+/// Replaces the German texts of an entity with the requested language if they were scraped.
+pub trait Localize {
+    #[must_use]
+    fn localize(self, language: Language) -> Self;
+}
+
+/// Keeps the German text if there is no translation, each field on its own.
+fn replace_if_translated(text: &mut String, translated: &str) {
+    if !translated.is_empty() {
+        *text = translated.to_string();
+    }
+}
+
+impl Localize for Module {
+    fn localize(mut self, language: Language) -> Self {
+        if language == Language::English {
+            replace_if_translated(&mut self.title, &self.title_en);
+            replace_if_translated(&mut self.content, &self.content_en);
+        }
+        self
+    }
+}
+
+impl Localize for Course {
+    fn localize(mut self, language: Language) -> Self {
+        if language == Language::English {
+            replace_if_translated(&mut self.title, &self.title_en);
+            replace_if_translated(&mut self.content, &self.content_en);
+        }
+        self
+    }
+}
+
+macro_rules! impl_untranslated {
+    ($($ty:ty),*) => {
+        $(impl Localize for $ty {
+            fn localize(self, _language: Language) -> Self {
+                self
+            }
+        })*
+    };
+}
+
+impl_untranslated!(CourseGroup, CourseEvent, CourseGroupEvent, Exam);
+
+impl<T: Localize> Localize for Vec<T> {
+    fn localize(self, language: Language) -> Self {
+        self.into_iter().map(|v| v.localize(language)).collect()
+    }
+}
+
+impl<A: Localize, B: Localize> Localize for (A, B) {
+    fn localize(self, language: Language) -> Self {
+        (self.0.localize(language), self.1.localize(language))
+    }
+}
+
+impl<A: Localize, B: Localize, C: Localize> Localize for (A, B, C) {
+    fn localize(self, language: Language) -> Self {
+        (
+            self.0.localize(language),
+            self.1.localize(language),
+            self.2.localize(language),
         )
-        .map_err(|err| Into::<tucant::MyError>::into(err).into_response())?;
-        Ok(session)
     }
 }
 
@@ -382,7 +507,7 @@ where
 #[cfg_attr(feature = "server", diesel(belongs_to(UndoneUser, foreign_key = user_id)))]
 pub struct UserModule {
     pub user_id: i32,
-    pub module_id: Vec<u8>,
+    pub module_id: ModuleId,
 }
 
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone, Typescriptable)]
@@ -397,9 +522,7 @@ pub struct UserModule {
 #[cfg_attr(feature = "server", diesel(belongs_to(UndoneUser, foreign_key = user_id)))]
 pub struct UserCourse {
     pub user_id: i32,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub course_id: Vec<u8>,
+    pub course_id: CourseId,
 }
 
 #[derive(Serialize, Debug, Deserialize, PartialEq, Eq, Clone, Typescriptable)]
@@ -414,23 +537,26 @@ pub struct UserCourse {
 #[cfg_attr(feature = "server", diesel(belongs_to(UndoneUser, foreign_key = user_id)))]
 pub struct UserCourseGroup {
     pub user_id: i32,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub course_group_id: Vec<u8>,
+    pub course_group_id: CourseId,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(
     feature = "server",
-    derive(Identifiable, Queryable, Insertable, AsChangeset, Typescriptable)
+    derive(
+        Identifiable,
+        Queryable,
+        Insertable,
+        AsChangeset,
+        Typescriptable,
+        async_graphql::SimpleObject
+    )
 )]
 #[cfg_attr(feature = "server", diesel(primary_key(tucan_id)))]
 #[cfg_attr(feature = "server", diesel(table_name = exams_unfinished))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct Exam {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub tucan_id: Vec<u8>,
+    pub tucan_id: ExamId,
     pub exam_type: String,
     pub semester: String,
     pub exam_time_start: Option<NaiveDateTime>,
@@ -453,12 +579,8 @@ pub struct Exam {
 #[cfg_attr(feature = "server", diesel(table_name = course_exams))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct CourseExam {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub course_id: Vec<u8>,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub exam: Vec<u8>,
+    pub course_id: CourseId,
+    pub exam: ExamId,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -470,12 +592,8 @@ pub struct CourseExam {
 #[cfg_attr(feature = "server", diesel(table_name = module_exams))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct ModuleExam {
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub module_id: Vec<u8>,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub exam: Vec<u8>,
+    pub module_id: ModuleId,
+    pub exam: ExamId,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -488,15 +606,20 @@ pub struct ModuleExam {
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct UserExam {
     pub matriculation_number: i32,
-    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
-    #[cfg_attr(feature = "server", ts_type(String))]
-    pub exam: Vec<u8>,
+    pub exam: ExamId,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(
     feature = "server",
-    derive(Identifiable, Queryable, Insertable, Typescriptable, AsChangeset)
+    derive(
+        Identifiable,
+        Queryable,
+        Insertable,
+        Typescriptable,
+        AsChangeset,
+        async_graphql::SimpleObject
+    )
 )]
 #[cfg_attr(
     feature = "server",
@@ -505,17 +628,24 @@ pub struct UserExam {
 #[cfg_attr(feature = "server", diesel(table_name = course_events))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct CourseEvent {
-    pub course: Vec<u8>,
+    pub course: CourseId,
     pub timestamp_start: NaiveDateTime,
     pub timestamp_end: NaiveDateTime,
     pub room: String,
     pub teachers: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(
     feature = "server",
-    derive(Identifiable, Queryable, Insertable, Typescriptable, AsChangeset)
+    derive(
+        Identifiable,
+        Queryable,
+        Insertable,
+        Typescriptable,
+        AsChangeset,
+        async_graphql::SimpleObject
+    )
 )]
 #[cfg_attr(
     feature = "server",
@@ -524,7 +654,7 @@ pub struct CourseEvent {
 #[cfg_attr(feature = "server", diesel(table_name = course_groups_events))]
 #[cfg_attr(feature = "server", diesel(treat_none_as_null = true))]
 pub struct CourseGroupEvent {
-    pub course: Vec<u8>,
+    pub course: CourseId,
     pub timestamp_start: NaiveDateTime,
     pub timestamp_end: NaiveDateTime,
     pub room: String,
@@ -539,6 +669,8 @@ pub const MODULES_UNFINISHED: (
     modules_unfinished::columns::credits,
     modules_unfinished::columns::content,
     modules_unfinished::columns::done,
+    modules_unfinished::columns::title_en,
+    modules_unfinished::columns::content_en,
 ) = (
     modules_unfinished::tucan_id,
     modules_unfinished::tucan_last_checked,
@@ -547,6 +679,8 @@ pub const MODULES_UNFINISHED: (
     modules_unfinished::credits,
     modules_unfinished::content,
     modules_unfinished::done,
+    modules_unfinished::title_en,
+    modules_unfinished::content_en,
 );
 
 pub const COURSES_UNFINISHED: (
@@ -557,6 +691,8 @@ pub const COURSES_UNFINISHED: (
     courses_unfinished::columns::sws,
     courses_unfinished::columns::content,
     courses_unfinished::columns::done,
+    courses_unfinished::columns::title_en,
+    courses_unfinished::columns::content_en,
 ) = (
     courses_unfinished::tucan_id,
     courses_unfinished::tucan_last_checked,
@@ -565,4 +701,137 @@ pub const COURSES_UNFINISHED: (
     courses_unfinished::sws,
     courses_unfinished::content,
     courses_unfinished::done,
+    courses_unfinished::title_en,
+    courses_unfinished::content_en,
+);
+
+/// The columns of [`User`], the table also stores when the user's modules, courses and exams were fetched.
+pub const USERS_UNFINISHED: (
+    users_unfinished::columns::matriculation_number,
+    users_unfinished::columns::title,
+    users_unfinished::columns::academic_title,
+    users_unfinished::columns::post_name,
+    users_unfinished::columns::first_name,
+    users_unfinished::columns::middle_name,
+    users_unfinished::columns::last_name,
+    users_unfinished::columns::pre_name,
+    users_unfinished::columns::redirect_messages_to_university_email,
+    users_unfinished::columns::subject,
+    users_unfinished::columns::email,
+    users_unfinished::columns::department,
+    users_unfinished::columns::post_title,
+    users_unfinished::columns::street,
+    users_unfinished::columns::address_addition,
+    users_unfinished::columns::country,
+    users_unfinished::columns::plz,
+    users_unfinished::columns::city,
+    users_unfinished::columns::phone_number,
+    users_unfinished::columns::done,
+) = (
+    users_unfinished::matriculation_number,
+    users_unfinished::title,
+    users_unfinished::academic_title,
+    users_unfinished::post_name,
+    users_unfinished::first_name,
+    users_unfinished::middle_name,
+    users_unfinished::last_name,
+    users_unfinished::pre_name,
+    users_unfinished::redirect_messages_to_university_email,
+    users_unfinished::subject,
+    users_unfinished::email,
+    users_unfinished::department,
+    users_unfinished::post_title,
+    users_unfinished::street,
+    users_unfinished::address_addition,
+    users_unfinished::country,
+    users_unfinished::plz,
+    users_unfinished::city,
+    users_unfinished::phone_number,
+    users_unfinished::done,
 );
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::FromRequestParts;
+    use axum::http::header::ACCEPT_LANGUAGE;
+    use axum::http::Request;
+    use chrono::Utc;
+
+    use super::{Language, Localize, Module};
+    use crate::ids::ModuleId;
+
+    async fn language(uri: &str, accept_language: Option<&str>) -> Language {
+        let mut request = Request::get(uri);
+        if let Some(accept_language) = accept_language {
+            request = request.header(ACCEPT_LANGUAGE, accept_language);
+        }
+        let (mut parts, ()) = request.body(()).unwrap().into_parts();
+        Language::from_request_parts(&mut parts, &()).await.unwrap()
+    }
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(
+            Language::from_accept_language("en"),
+            Some(Language::English)
+        );
+        assert_eq!(
+            Language::from_accept_language("en-US,en;q=0.9,de;q=0.8"),
+            Some(Language::English)
+        );
+        assert_eq!(
+            Language::from_accept_language("en;q=0.5, de-DE"),
+            Some(Language::German)
+        );
+        assert_eq!(
+            Language::from_accept_language("fr-FR, en_GB;q=0.3"),
+            Some(Language::English)
+        );
+        assert_eq!(Language::from_accept_language("en;q=0, fr"), None);
+        assert_eq!(Language::from_accept_language("en;q=abc"), None);
+        assert_eq!(Language::from_accept_language(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_lang_query_parameter() {
+        assert_eq!(language("/module", None).await, Language::German);
+        assert_eq!(language("/module?lang=en", None).await, Language::English);
+        assert_eq!(
+            language("/module?id=1&lang=en-GB", Some("de")).await,
+            Language::English
+        );
+        assert_eq!(
+            language("/module?lang=de", Some("en")).await,
+            Language::German
+        );
+        assert_eq!(language("/module", Some("en")).await, Language::English);
+        // unsupported values fall back to the header
+        assert_eq!(
+            language("/module?lang=fr", Some("en")).await,
+            Language::English
+        );
+    }
+
+    #[test]
+    fn test_localize_falls_back_per_field() {
+        let module = Module {
+            tucan_id: ModuleId::tucanscheiss(),
+            tucan_last_checked: Utc::now().naive_utc(),
+            title: "Titel".to_string(),
+            module_id: "01-23-4567".to_string(),
+            credits: Some(5),
+            content: "Beschreibung".to_string(),
+            done: true,
+            title_en: "Title".to_string(),
+            content_en: String::new(),
+        };
+
+        let english = module.clone().localize(Language::English);
+        assert_eq!(english.title, "Title");
+        assert_eq!(english.content, "Beschreibung");
+
+        let german = module.localize(Language::German);
+        assert_eq!(german.title, "Titel");
+        assert_eq!(german.content, "Beschreibung");
+    }
+}
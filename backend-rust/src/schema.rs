@@ -1,6 +1,17 @@
 #![allow(clippy::wildcard_imports)]
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::*;
+
+    calendar_tokens (token) {
+        token -> Text,
+        matriculation_number -> Int4,
+        created -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::*;
@@ -62,6 +73,20 @@ diesel::table! {
         content -> Text,
         done -> Bool,
         tsv -> Tsvector,
+        title_en -> Text,
+        content_en -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::*;
+
+    credential_audit_log (id) {
+        id -> Int8,
+        matriculation_number -> Int4,
+        event -> Text,
+        created -> Timestamptz,
     }
 }
 
@@ -85,6 +110,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::*;
+
+    lecturers (name) {
+        name -> Text,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::*;
@@ -141,6 +175,19 @@ diesel::table! {
         content -> Text,
         done -> Bool,
         tsv -> Tsvector,
+        title_en -> Text,
+        content_en -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::*;
+
+    search_index_changes (kind, tucan_id) {
+        kind -> Text,
+        tucan_id -> Bytea,
+        changed_at -> Timestamptz,
     }
 }
 
@@ -148,10 +195,29 @@ diesel::table! {
     use diesel::sql_types::*;
     use diesel_full_text_search::*;
 
-    sessions (matriculation_number, session_nr, session_id) {
+    sessions (id) {
         matriculation_number -> Int4,
         session_nr -> Int8,
         session_id -> Text,
+        id -> Text,
+        handle -> Int8,
+        created -> Timestamptz,
+        last_used -> Timestamptz,
+        expires -> Timestamptz,
+        user_agent -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::*;
+
+    stored_credentials (matriculation_number) {
+        matriculation_number -> Int4,
+        data_key -> Bytea,
+        ciphertext -> Bytea,
+        consented -> Timestamptz,
+        last_used -> Nullable<Timestamptz>,
     }
 }
 
@@ -226,11 +292,13 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(calendar_tokens -> users_unfinished (matriculation_number));
 diesel::joinable!(course_events -> courses_unfinished (course));
 diesel::joinable!(course_exams -> courses_unfinished (course_id));
 diesel::joinable!(course_exams -> exams_unfinished (exam));
 diesel::joinable!(course_groups_events -> course_groups_unfinished (course));
 diesel::joinable!(course_groups_unfinished -> courses_unfinished (course));
+diesel::joinable!(credential_audit_log -> users_unfinished (matriculation_number));
 diesel::joinable!(module_courses -> courses_unfinished (course));
 diesel::joinable!(module_courses -> modules_unfinished (module));
 diesel::joinable!(module_exams -> exams_unfinished (exam));
@@ -238,6 +306,7 @@ diesel::joinable!(module_exams -> modules_unfinished (module_id));
 diesel::joinable!(module_menu_module -> module_menu_unfinished (module_menu_id));
 diesel::joinable!(module_menu_module -> modules_unfinished (module_id));
 diesel::joinable!(sessions -> users_unfinished (matriculation_number));
+diesel::joinable!(stored_credentials -> users_unfinished (matriculation_number));
 diesel::joinable!(user_course_groups -> course_groups_unfinished (course_group_id));
 diesel::joinable!(user_course_groups -> users_unfinished (user_id));
 diesel::joinable!(user_courses -> courses_unfinished (course_id));
@@ -248,18 +317,23 @@ diesel::joinable!(user_modules -> modules_unfinished (module_id));
 diesel::joinable!(user_modules -> users_unfinished (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    calendar_tokens,
     course_events,
     course_exams,
     course_groups_events,
     course_groups_unfinished,
     courses_unfinished,
+    credential_audit_log,
     exams_unfinished,
+    lecturers,
     module_courses,
     module_exams,
     module_menu_module,
     module_menu_unfinished,
     modules_unfinished,
+    search_index_changes,
     sessions,
+    stored_credentials,
     user_course_groups,
     user_courses,
     user_exams,
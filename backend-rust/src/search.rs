@@ -0,0 +1,1698 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Request and response types shared by all search routes so the Postgres and the OpenSearch
+//! implementation page, filter and count the same way.
+
+use diesel::dsl::{count_distinct, count_star, sql};
+use diesel::expression::SqlLiteral;
+use diesel::pg::Pg;
+use diesel::sql_query;
+use diesel::sql_types::{Array, BigInt, Bool, Bytea, Double, Nullable, Text};
+use diesel::{
+    ExpressionMethods, NullableExpressionMethods, QueryDsl, Queryable, QueryableByName,
+    TextExpressionMethods,
+};
+use diesel_async::RunQueryDsl;
+use diesel_full_text_search::TsVectorExtensions;
+use diesel_full_text_search::{
+    configuration::TsConfigurationByName, ts_headline_with_search_config, ts_rank_cd_normalized,
+    websearch_to_tsquery_with_search_config,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tucant_derive::Typescriptable;
+
+use crate::ids::{CourseId, ExamId, IdError, MenuPath, ModuleId};
+use crate::models::Language;
+use crate::schema::{
+    course_exams, courses_unfinished, exams_unfinished, module_exams, modules_unfinished,
+};
+use crate::search_syntax::{Comparison, FieldFilter, ParsedQuery, Term};
+use crate::telemetry::Traced;
+use crate::tucan::DbPool;
+use crate::MyError;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// How many values of a facet are returned at most.
+const FACET_SIZE: u32 = 50;
+
+/// Selects the ids of all modules in a menu or one of its submenus, the menu id has to be bound
+/// between the two parts.
+const MODULES_IN_MENU: (&str, &str) = (
+    "WITH RECURSIVE subtree AS (
+        SELECT t.tucan_id FROM module_menu_unfinished t WHERE t.tucan_id = ",
+    "
+      UNION
+        SELECT t.tucan_id FROM module_menu_unfinished t JOIN subtree s ON t.parent = s.tucan_id
+    )
+    SELECT mmm.module_id FROM module_menu_module mmm JOIN subtree s ON mmm.module_menu_id = s.tucan_id",
+);
+
+/// Matches the modules with an examiner or a teacher of an event of one of their courses like
+/// the `ILIKE` pattern, which has to be bound between the parts.
+const MODULES_OF_LECTURER: [&str; 3] = [
+    "modules_unfinished.tucan_id IN (
+        SELECT me.module_id FROM module_exams me JOIN exams_unfinished e ON e.tucan_id = me.exam
+        WHERE e.examinator ILIKE ",
+    "
+      UNION
+        SELECT mc.module FROM module_courses mc JOIN course_events ce ON ce.course = mc.course
+        WHERE ce.teachers ILIKE ",
+    ")",
+];
+
+/// Matches the courses with an examiner or a teacher of one of their events like the `ILIKE`
+/// pattern, which has to be bound between the parts.
+const COURSES_OF_LECTURER: [&str; 3] = [
+    "courses_unfinished.tucan_id IN (
+        SELECT ce.course_id FROM course_exams ce JOIN exams_unfinished e ON e.tucan_id = ce.exam
+        WHERE e.examinator ILIKE ",
+    "
+      UNION
+        SELECT ce.course FROM course_events ce WHERE ce.teachers ILIKE ",
+    ")",
+];
+
+#[derive(Deserialize, Debug, Default, PartialEq, Eq, Clone, Typescriptable)]
+pub struct SearchQuery {
+    /// In the syntax of [`crate::search_syntax`].
+    pub query: String,
+    /// Starts at 0.
+    #[serde(default)]
+    pub page: u32,
+    /// Defaults to [`DEFAULT_PAGE_SIZE`] and is capped at [`MAX_PAGE_SIZE`].
+    #[serde(default)]
+    pub page_size: Option<u32>,
+    #[serde(default)]
+    pub filters: SearchFilters,
+}
+
+/// Filters that don't apply to what is searched (like `sws` for modules) are ignored.
+#[derive(Deserialize, Debug, Default, PartialEq, Eq, Clone, Typescriptable)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub credits_min: Option<i32>,
+    pub credits_max: Option<i32>,
+    pub sws_min: Option<i16>,
+    pub sws_max: Option<i16>,
+    /// Only matches with an exam in this semester, spelled like TUCaN does, e.g. `WiSe 2022/23`.
+    pub semester: Option<String>,
+    /// `en` only returns matches that have an english translation.
+    pub language: Option<String>,
+    /// Only modules in this menu or one of its submenus, or courses of these modules.
+    pub menu: Option<MenuPath>,
+}
+
+fn clamp_page_size(page_size: Option<u32>) -> u32 {
+    page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE)
+}
+
+impl SearchQuery {
+    #[must_use]
+    pub fn page_size(&self) -> u32 {
+        clamp_page_size(self.page_size)
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page) * i64::from(self.page_size())
+    }
+
+    pub fn language(&self) -> Result<Option<Language>, MyError> {
+        self.filters
+            .language
+            .as_deref()
+            .map(|code| {
+                Language::from_code(code)
+                    .ok_or_else(|| MyError::BadRequest(format!("unsupported language {code}")))
+            })
+            .transpose()
+    }
+
+    /// Parses `query` and adds `filters` in front of its filters, except the menu.
+    pub fn parse(&self) -> Result<ParsedQuery, MyError> {
+        let mut parsed = ParsedQuery::parse(&self.query)?;
+        let filters = &self.filters;
+        let mut all_filters = [
+            filters
+                .credits_min
+                .map(|credits| FieldFilter::Credits(Comparison::Ge, credits)),
+            filters
+                .credits_max
+                .map(|credits| FieldFilter::Credits(Comparison::Le, credits)),
+            filters
+                .sws_min
+                .map(|sws| FieldFilter::Sws(Comparison::Ge, sws)),
+            filters
+                .sws_max
+                .map(|sws| FieldFilter::Sws(Comparison::Le, sws)),
+            filters.semester.clone().map(FieldFilter::Semester),
+            self.language()?.map(FieldFilter::Language),
+        ]
+        .into_iter()
+        .flatten()
+        .collect_vec();
+        all_filters.append(&mut parsed.filters);
+        parsed.filters = all_filters;
+        Ok(parsed)
+    }
+}
+
+#[derive(Queryable, Serialize, Debug, PartialEq, Typescriptable)]
+pub struct SearchResult<Id: tucant_derive_lib::Typescriptable> {
+    pub tucan_id: Id,
+    pub title: String,
+    pub excerpt: String,
+    pub rank: f64,
+}
+
+#[derive(QueryableByName, Serialize, Debug, PartialEq, Eq, Typescriptable)]
+pub struct FacetCount {
+    #[diesel(sql_type = Text)]
+    pub value: String,
+    #[diesel(sql_type = BigInt)]
+    pub count: i64,
+}
+
+/// The number of matches per value, ignoring pagination.
+#[derive(Serialize, Debug, Default, PartialEq, Eq, Typescriptable)]
+pub struct SearchFacets {
+    pub credits: Vec<FacetCount>,
+    pub sws: Vec<FacetCount>,
+    pub semesters: Vec<FacetCount>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Typescriptable)]
+pub struct SearchResponse<Id: tucant_derive_lib::Typescriptable> {
+    pub results: Vec<SearchResult<Id>>,
+    /// The number of matches on all pages.
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+    pub facets: SearchFacets,
+}
+
+fn facet<T: ToString>(rows: Vec<(Option<T>, i64)>) -> Vec<FacetCount> {
+    rows.into_iter()
+        .filter_map(|(value, count)| {
+            value.map(|value| FacetCount {
+                value: value.to_string(),
+                count,
+            })
+        })
+        .collect()
+}
+
+/// The title in `language` like OpenSearch returns it, German if there is no translation.
+fn localized_title(table: &str, language: Language) -> SqlLiteral<Text> {
+    sql(&match language {
+        Language::German => format!("{table}.title"),
+        Language::English => format!("COALESCE(NULLIF({table}.title_en, ''), {table}.title)"),
+    })
+}
+
+type ModulesQuery<'a> = modules_unfinished::BoxedQuery<'a, Pg>;
+
+/// Only the filters select if there is nothing to search in the text, a query without any
+/// terms and filters matches nothing.
+fn filtered_modules<'a>(search: &'a SearchQuery, parsed: &'a ParsedQuery) -> ModulesQuery<'a> {
+    let mut query = modules_unfinished::table.into_boxed();
+    if !parsed.terms.is_empty() || parsed.is_empty() {
+        let tsquery = websearch_to_tsquery_with_search_config(
+            TsConfigurationByName("tucan"),
+            parsed.websearch(),
+        );
+        query = query.filter(modules_unfinished::tsv.matches(tsquery));
+    }
+    for filter in &parsed.filters {
+        match filter {
+            FieldFilter::Credits(comparison, credits) => {
+                let credits = *credits;
+                query = match comparison {
+                    Comparison::Eq => query.filter(modules_unfinished::credits.eq(credits)),
+                    Comparison::Lt => query.filter(modules_unfinished::credits.lt(credits)),
+                    Comparison::Le => query.filter(modules_unfinished::credits.le(credits)),
+                    Comparison::Gt => query.filter(modules_unfinished::credits.gt(credits)),
+                    Comparison::Ge => query.filter(modules_unfinished::credits.ge(credits)),
+                };
+            }
+            FieldFilter::Sws(..) | FieldFilter::Language(Language::German) => {}
+            FieldFilter::Lecturer(name) => {
+                let pattern = like_contains(name);
+                query = query.filter(
+                    sql::<Bool>(MODULES_OF_LECTURER[0])
+                        .bind::<Text, _>(pattern.clone())
+                        .sql(MODULES_OF_LECTURER[1])
+                        .bind::<Text, _>(pattern)
+                        .sql(MODULES_OF_LECTURER[2]),
+                );
+            }
+            FieldFilter::Semester(semester) => {
+                query = query.filter(
+                    modules_unfinished::tucan_id.eq_any(
+                        module_exams::table
+                            .inner_join(exams_unfinished::table)
+                            .filter(exams_unfinished::semester.eq(semester))
+                            .select(module_exams::module_id),
+                    ),
+                );
+            }
+            FieldFilter::Language(Language::English) => {
+                query = query.filter(modules_unfinished::title_en.ne(""));
+            }
+        }
+    }
+    if let Some(menu) = &search.filters.menu {
+        query = query.filter(
+            sql::<Bool>(&format!(
+                "modules_unfinished.tucan_id IN ({}",
+                MODULES_IN_MENU.0
+            ))
+            .bind::<Bytea, _>(menu)
+            .sql(&format!("{})", MODULES_IN_MENU.1)),
+        );
+    }
+    query
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn search_modules_postgres(
+    pool: &DbPool,
+    search: &SearchQuery,
+    language: Language,
+) -> Result<SearchResponse<ModuleId>, MyError> {
+    let parsed = search.parse()?;
+    let text = parsed.websearch();
+    let mut connection = pool.get().await?;
+
+    let config = TsConfigurationByName("tucan");
+    let tsquery = websearch_to_tsquery_with_search_config(config, &text);
+    let rank = ts_rank_cd_normalized(modules_unfinished::tsv, tsquery, 1);
+    let results = filtered_modules(search, &parsed)
+        .order_by(rank.desc())
+        .then_order_by(modules_unfinished::tucan_id)
+        .limit(i64::from(search.page_size()))
+        .offset(search.offset())
+        .select((
+            modules_unfinished::tucan_id,
+            ts_headline_with_search_config(
+                config,
+                localized_title("modules_unfinished", language),
+                tsquery,
+            ),
+            ts_headline_with_search_config(
+                config,
+                modules_unfinished::module_id
+                    .concat(" ")
+                    .concat(modules_unfinished::title)
+                    .concat(" ")
+                    .concat(modules_unfinished::content),
+                tsquery,
+            ),
+            sql::<Double>("CAST(").bind(rank).sql(" as FLOAT8)"),
+        ))
+        .load::<SearchResult<ModuleId>>(&mut connection)
+        .traced()
+        .await?;
+
+    let total = filtered_modules(search, &parsed)
+        .count()
+        .get_result::<i64>(&mut connection)
+        .traced()
+        .await?;
+
+    let credits = modules_unfinished::table
+        .filter(
+            modules_unfinished::tucan_id
+                .eq_any(filtered_modules(search, &parsed).select(modules_unfinished::tucan_id)),
+        )
+        .group_by(modules_unfinished::credits)
+        .select((modules_unfinished::credits, count_star()))
+        .order_by(modules_unfinished::credits)
+        .limit(FACET_SIZE.into())
+        .load::<(Option<i32>, i64)>(&mut connection)
+        .traced()
+        .await?;
+
+    let semesters = module_exams::table
+        .inner_join(exams_unfinished::table)
+        .filter(
+            module_exams::module_id
+                .eq_any(filtered_modules(search, &parsed).select(modules_unfinished::tucan_id)),
+        )
+        .group_by(exams_unfinished::semester)
+        .select((
+            exams_unfinished::semester.nullable(),
+            count_distinct(module_exams::module_id),
+        ))
+        .order_by(exams_unfinished::semester)
+        .limit(FACET_SIZE.into())
+        .load::<(Option<String>, i64)>(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(SearchResponse {
+        results,
+        total,
+        page: search.page,
+        page_size: search.page_size(),
+        facets: SearchFacets {
+            credits: facet(credits),
+            sws: Vec::new(),
+            semesters: facet(semesters),
+        },
+    })
+}
+
+type CoursesQuery<'a> = courses_unfinished::BoxedQuery<'a, Pg>;
+
+/// Like [`filtered_modules`].
+fn filtered_courses<'a>(search: &'a SearchQuery, parsed: &'a ParsedQuery) -> CoursesQuery<'a> {
+    let mut query = courses_unfinished::table.into_boxed();
+    if !parsed.terms.is_empty() || parsed.is_empty() {
+        let tsquery = websearch_to_tsquery_with_search_config(
+            TsConfigurationByName("tucan"),
+            parsed.websearch(),
+        );
+        query = query.filter(courses_unfinished::tsv.matches(tsquery));
+    }
+    for filter in &parsed.filters {
+        match filter {
+            FieldFilter::Sws(comparison, sws) => {
+                let sws = *sws;
+                query = match comparison {
+                    Comparison::Eq => query.filter(courses_unfinished::sws.eq(sws)),
+                    Comparison::Lt => query.filter(courses_unfinished::sws.lt(sws)),
+                    Comparison::Le => query.filter(courses_unfinished::sws.le(sws)),
+                    Comparison::Gt => query.filter(courses_unfinished::sws.gt(sws)),
+                    Comparison::Ge => query.filter(courses_unfinished::sws.ge(sws)),
+                };
+            }
+            FieldFilter::Credits(..) | FieldFilter::Language(Language::German) => {}
+            FieldFilter::Lecturer(name) => {
+                let pattern = like_contains(name);
+                query = query.filter(
+                    sql::<Bool>(COURSES_OF_LECTURER[0])
+                        .bind::<Text, _>(pattern.clone())
+                        .sql(COURSES_OF_LECTURER[1])
+                        .bind::<Text, _>(pattern)
+                        .sql(COURSES_OF_LECTURER[2]),
+                );
+            }
+            FieldFilter::Semester(semester) => {
+                query = query.filter(
+                    courses_unfinished::tucan_id.eq_any(
+                        course_exams::table
+                            .inner_join(exams_unfinished::table)
+                            .filter(exams_unfinished::semester.eq(semester))
+                            .select(course_exams::course_id),
+                    ),
+                );
+            }
+            FieldFilter::Language(Language::English) => {
+                query = query.filter(courses_unfinished::title_en.ne(""));
+            }
+        }
+    }
+    if let Some(menu) = &search.filters.menu {
+        query = query.filter(
+            sql::<Bool>(&format!(
+                "courses_unfinished.tucan_id IN (SELECT mc.course FROM module_courses mc WHERE mc.module IN ({}",
+                MODULES_IN_MENU.0
+            ))
+            .bind::<Bytea, _>(menu)
+            .sql(&format!("{}))", MODULES_IN_MENU.1)),
+        );
+    }
+    query
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn search_courses_postgres(
+    pool: &DbPool,
+    search: &SearchQuery,
+    language: Language,
+) -> Result<SearchResponse<CourseId>, MyError> {
+    let parsed = search.parse()?;
+    let text = parsed.websearch();
+    let mut connection = pool.get().await?;
+
+    let config = TsConfigurationByName("tucan");
+    let tsquery = websearch_to_tsquery_with_search_config(config, &text);
+    let rank = ts_rank_cd_normalized(courses_unfinished::tsv, tsquery, 1);
+    let results = filtered_courses(search, &parsed)
+        .order_by(rank.desc())
+        .then_order_by(courses_unfinished::tucan_id)
+        .limit(i64::from(search.page_size()))
+        .offset(search.offset())
+        .select((
+            courses_unfinished::tucan_id,
+            ts_headline_with_search_config(
+                config,
+                localized_title("courses_unfinished", language),
+                tsquery,
+            ),
+            ts_headline_with_search_config(
+                config,
+                courses_unfinished::course_id
+                    .concat(" ")
+                    .concat(courses_unfinished::title)
+                    .concat(" ")
+                    .concat(courses_unfinished::content),
+                tsquery,
+            ),
+            sql::<Double>("CAST(").bind(rank).sql(" as FLOAT8)"),
+        ))
+        .load::<SearchResult<CourseId>>(&mut connection)
+        .traced()
+        .await?;
+
+    let total = filtered_courses(search, &parsed)
+        .count()
+        .get_result::<i64>(&mut connection)
+        .traced()
+        .await?;
+
+    let sws = courses_unfinished::table
+        .filter(
+            courses_unfinished::tucan_id
+                .eq_any(filtered_courses(search, &parsed).select(courses_unfinished::tucan_id)),
+        )
+        .group_by(courses_unfinished::sws)
+        .select((courses_unfinished::sws.nullable(), count_star()))
+        .order_by(courses_unfinished::sws)
+        .limit(FACET_SIZE.into())
+        .load::<(Option<i16>, i64)>(&mut connection)
+        .traced()
+        .await?;
+
+    let semesters = course_exams::table
+        .inner_join(exams_unfinished::table)
+        .filter(
+            course_exams::course_id
+                .eq_any(filtered_courses(search, &parsed).select(courses_unfinished::tucan_id)),
+        )
+        .group_by(exams_unfinished::semester)
+        .select((
+            exams_unfinished::semester.nullable(),
+            count_distinct(course_exams::course_id),
+        ))
+        .order_by(exams_unfinished::semester)
+        .limit(FACET_SIZE.into())
+        .load::<(Option<String>, i64)>(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(SearchResponse {
+        results,
+        total,
+        page: search.page,
+        page_size: search.page_size(),
+        facets: SearchFacets {
+            credits: Vec::new(),
+            sws: facet(sws),
+            semesters: facet(semesters),
+        },
+    })
+}
+
+/// The kinds of entities the unified search returns.
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord, Typescriptable,
+)]
+#[serde(tag = "type", content = "value")]
+pub enum SearchKind {
+    Module,
+    Course,
+    CourseGroup,
+    Exam,
+    /// Names of lecturers of events and examiners, they have no page of their own.
+    Lecturer,
+}
+
+impl SearchKind {
+    pub const ALL: [Self; 5] = [
+        Self::Module,
+        Self::Course,
+        Self::CourseGroup,
+        Self::Exam,
+        Self::Lecturer,
+    ];
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone, Typescriptable)]
+pub struct KindWeight {
+    pub kind: SearchKind,
+    /// Multiplies the rank of results of this kind.
+    pub weight: f64,
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq, Clone, Typescriptable)]
+pub struct UnifiedSearchQuery {
+    pub query: String,
+    /// Starts at 0.
+    #[serde(default)]
+    pub page: u32,
+    /// Defaults to [`DEFAULT_PAGE_SIZE`] and is capped at [`MAX_PAGE_SIZE`].
+    #[serde(default)]
+    pub page_size: Option<u32>,
+    /// Only these kinds are searched. All kinds with weight 1 if empty.
+    #[serde(default)]
+    pub kinds: Vec<KindWeight>,
+}
+
+impl UnifiedSearchQuery {
+    #[must_use]
+    pub fn page_size(&self) -> u32 {
+        clamp_page_size(self.page_size)
+    }
+
+    /// The weight of every kind in the order of [`SearchKind::ALL`], 0 for kinds that are not searched.
+    pub fn weights(&self) -> Result<[f64; SearchKind::ALL.len()], MyError> {
+        if self.kinds.is_empty() {
+            return Ok([1.0; SearchKind::ALL.len()]);
+        }
+        let mut weights = [0.0; SearchKind::ALL.len()];
+        for KindWeight { kind, weight } in &self.kinds {
+            if !weight.is_finite() || *weight < 0.0 {
+                return Err(MyError::BadRequest(format!(
+                    "invalid weight {weight} for {kind:?}"
+                )));
+            }
+            weights[*kind as usize] = *weight;
+        }
+        Ok(weights)
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Typescriptable)]
+pub struct SearchHit<Id: tucant_derive_lib::Typescriptable> {
+    pub id: Id,
+    pub title: String,
+    pub excerpt: String,
+    pub rank: f64,
+}
+
+#[derive(Serialize, Debug, PartialEq, Typescriptable)]
+#[serde(tag = "type", content = "value")]
+pub enum UnifiedSearchHit {
+    Module(SearchHit<ModuleId>),
+    Course(SearchHit<CourseId>),
+    CourseGroup(SearchHit<CourseId>),
+    Exam(SearchHit<ExamId>),
+    /// The id is the name.
+    Lecturer(SearchHit<String>),
+}
+
+#[derive(Serialize, Debug, PartialEq, Typescriptable)]
+pub struct UnifiedSearchResponse {
+    pub results: Vec<UnifiedSearchHit>,
+    /// The number of matches on all pages.
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+    /// The number of matches per [`SearchKind`].
+    pub kinds: Vec<FacetCount>,
+}
+
+/// The matches of the unified search in `hits`. `$1` is the query and `$2` to `$6` are the
+/// weights in the order of [`SearchKind::ALL`], kinds with weight 0 are not searched.
+const UNIFIED_HITS: &str = r#"
+    WITH q AS (SELECT websearch_to_tsquery('tucan', $1) AS query),
+    exam_documents AS (
+        SELECT e.tucan_id, coalesce(
+            (SELECT m.title FROM module_exams me JOIN modules_unfinished m ON m.tucan_id = me.module_id WHERE me.exam = e.tucan_id LIMIT 1),
+            (SELECT c.title FROM course_exams ce JOIN courses_unfinished c ON c.tucan_id = ce.course_id WHERE ce.exam = e.tucan_id LIMIT 1),
+            e.exam_type
+        ) AS title, e.exam_type, e.semester, e.examinator
+        FROM exams_unfinished e
+    ),
+    hits AS (
+        SELECT 'Module' AS kind, m.tucan_id AS id, NULL AS name, m.title,
+            ts_headline('tucan', m.module_id || ' ' || m.title || ' ' || m.content, q.query) AS excerpt,
+            ts_rank_cd(m.tsv, q.query, 1) * $2 AS rank
+        FROM modules_unfinished m, q WHERE $2 > 0 AND m.tsv @@ q.query
+      UNION ALL
+        SELECT 'Course', c.tucan_id, NULL, c.title,
+            ts_headline('tucan', c.course_id || ' ' || c.title || ' ' || c.content, q.query),
+            ts_rank_cd(c.tsv, q.query, 1) * $3
+        FROM courses_unfinished c, q WHERE $3 > 0 AND c.tsv @@ q.query
+      UNION ALL
+        SELECT 'CourseGroup', g.tucan_id, NULL, g.title,
+            ts_headline('tucan', c.title || ' ' || g.title, q.query),
+            ts_rank_cd(to_tsvector('tucan', c.title || ' ' || g.title), q.query, 1) * $4
+        FROM course_groups_unfinished g JOIN courses_unfinished c ON c.tucan_id = g.course, q
+        WHERE $4 > 0 AND to_tsvector('tucan', c.title || ' ' || g.title) @@ q.query
+      UNION ALL
+        SELECT 'Exam', e.tucan_id, NULL, e.title,
+            ts_headline('tucan', concat_ws(' ', e.title, e.exam_type, e.semester, e.examinator), q.query),
+            ts_rank_cd(to_tsvector('tucan', concat_ws(' ', e.title, e.exam_type, e.semester, e.examinator)), q.query, 1) * $5
+        FROM exam_documents e, q
+        WHERE $5 > 0 AND to_tsvector('tucan', concat_ws(' ', e.title, e.exam_type, e.semester, e.examinator)) @@ q.query
+      UNION ALL
+        SELECT 'Lecturer', NULL, l.name, l.name,
+            ts_headline('tucan', l.name, q.query),
+            ts_rank_cd(to_tsvector('tucan', l.name), q.query, 1) * $6
+        FROM lecturers l, q WHERE $6 > 0 AND to_tsvector('tucan', l.name) @@ q.query
+    )
+"#;
+
+#[derive(QueryableByName, Debug)]
+struct UnifiedHitRow {
+    #[diesel(sql_type = Text)]
+    kind: String,
+    #[diesel(sql_type = Nullable<Bytea>)]
+    id: Option<Vec<u8>>,
+    #[diesel(sql_type = Nullable<Text>)]
+    name: Option<String>,
+    #[diesel(sql_type = Text)]
+    title: String,
+    #[diesel(sql_type = Text)]
+    excerpt: String,
+    #[diesel(sql_type = Double)]
+    rank: f64,
+}
+
+impl TryFrom<UnifiedHitRow> for UnifiedSearchHit {
+    type Error = MyError;
+
+    fn try_from(row: UnifiedHitRow) -> Result<Self, Self::Error> {
+        fn hit<Id: tucant_derive_lib::Typescriptable>(row: UnifiedHitRow, id: Id) -> SearchHit<Id> {
+            SearchHit {
+                id,
+                title: row.title,
+                excerpt: row.excerpt,
+                rank: row.rank,
+            }
+        }
+
+        let id = || {
+            row.id
+                .clone()
+                .ok_or_else(|| MyError::Internal(anyhow::anyhow!("{} without id", row.kind)))
+        };
+        let invalid = |err: IdError| MyError::Internal(err.into());
+        Ok(match row.kind.as_str() {
+            "Module" => {
+                let id = ModuleId::from_bytes(id()?).map_err(invalid)?;
+                Self::Module(hit(row, id))
+            }
+            "Course" => {
+                let id = CourseId::from_bytes(id()?).map_err(invalid)?;
+                Self::Course(hit(row, id))
+            }
+            "CourseGroup" => {
+                let id = CourseId::from_bytes(id()?).map_err(invalid)?;
+                Self::CourseGroup(hit(row, id))
+            }
+            "Exam" => {
+                let id = ExamId::from_bytes(id()?).map_err(invalid)?;
+                Self::Exam(hit(row, id))
+            }
+            "Lecturer" => {
+                let name = row.name.clone().unwrap_or_default();
+                Self::Lecturer(hit(row, name))
+            }
+            kind => return Err(MyError::Internal(anyhow::anyhow!("unknown kind {kind}"))),
+        })
+    }
+}
+
+/// Searches all kinds of entities at once, their ranks are comparable because they are all ranked
+/// with the `tucan` text search configuration.
+#[tracing::instrument(skip_all)]
+pub async fn search_unified_postgres(
+    pool: &DbPool,
+    search: &UnifiedSearchQuery,
+) -> Result<UnifiedSearchResponse, MyError> {
+    let weights = search.weights()?;
+    let mut connection = pool.get().await?;
+
+    let results = sql_query(format!(
+        "{UNIFIED_HITS} SELECT kind, id, name, title, excerpt, CAST(rank AS FLOAT8) AS rank FROM hits
+        ORDER BY rank DESC, kind, id, name LIMIT $7 OFFSET $8"
+    ))
+    .bind::<Text, _>(&search.query)
+    .bind::<Double, _>(weights[0])
+    .bind::<Double, _>(weights[1])
+    .bind::<Double, _>(weights[2])
+    .bind::<Double, _>(weights[3])
+    .bind::<Double, _>(weights[4])
+    .bind::<BigInt, _>(i64::from(search.page_size()))
+    .bind::<BigInt, _>(i64::from(search.page) * i64::from(search.page_size()))
+    .load::<UnifiedHitRow>(&mut connection)
+    .traced()
+    .await?
+    .into_iter()
+    .map(UnifiedSearchHit::try_from)
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let kinds = sql_query(format!(
+        "{UNIFIED_HITS} SELECT kind AS value, count(*) AS count FROM hits GROUP BY kind ORDER BY kind"
+    ))
+    .bind::<Text, _>(&search.query)
+    .bind::<Double, _>(weights[0])
+    .bind::<Double, _>(weights[1])
+    .bind::<Double, _>(weights[2])
+    .bind::<Double, _>(weights[3])
+    .bind::<Double, _>(weights[4])
+    .load::<FacetCount>(&mut connection)
+    .traced()
+    .await?;
+
+    Ok(UnifiedSearchResponse {
+        results,
+        total: kinds.iter().map(|kind| kind.count).sum(),
+        page: search.page,
+        page_size: search.page_size(),
+        kinds,
+    })
+}
+
+/// How many suggestions are returned.
+const SUGGESTION_LIMIT: i64 = 10;
+
+/// Modules whose id starts with or whose title contains something similar to `$1`, and lecturers
+/// with a similar name. `$2` is `$1` as a `LIKE` prefix pattern.
+const SUGGESTIONS: &str = r#"
+    SELECT text, kind, module, CAST(score AS FLOAT8) AS score FROM (
+        SELECT m.module_id AS text, 'Module' AS kind, m.tucan_id AS module,
+            CASE WHEN m.module_id ILIKE $2 THEN 1 ELSE similarity($1, m.module_id) END AS score
+        FROM modules_unfinished m WHERE m.module_id ILIKE $2 OR $1 % m.module_id
+      UNION ALL
+        SELECT m.title, 'Module', m.tucan_id, word_similarity($1, m.title)
+        FROM modules_unfinished m WHERE $1 <% m.title
+      UNION ALL
+        SELECT m.title_en, 'Module', m.tucan_id, word_similarity($1, m.title_en)
+        FROM modules_unfinished m WHERE $1 <% m.title_en
+      UNION ALL
+        SELECT l.name, 'Lecturer', NULL, word_similarity($1, l.name)
+        FROM lecturers l WHERE $1 <% l.name
+    ) suggestions
+    ORDER BY score DESC, length(text), text
+    LIMIT $3
+"#;
+
+#[derive(Serialize, Debug, PartialEq, Typescriptable)]
+pub struct Suggestion {
+    /// What the search input is completed to.
+    pub text: String,
+    pub kind: SearchKind,
+    /// The module whose id or title matched.
+    pub module: Option<ModuleId>,
+    pub score: f64,
+}
+
+#[derive(QueryableByName, Debug)]
+struct SuggestionRow {
+    #[diesel(sql_type = Text)]
+    text: String,
+    #[diesel(sql_type = Text)]
+    kind: String,
+    #[diesel(sql_type = Nullable<Bytea>)]
+    module: Option<Vec<u8>>,
+    #[diesel(sql_type = Double)]
+    score: f64,
+}
+
+impl TryFrom<SuggestionRow> for Suggestion {
+    type Error = MyError;
+
+    fn try_from(row: SuggestionRow) -> Result<Self, Self::Error> {
+        let kind = match row.kind.as_str() {
+            "Module" => SearchKind::Module,
+            "Lecturer" => SearchKind::Lecturer,
+            kind => return Err(MyError::Internal(anyhow::anyhow!("unknown kind {kind}"))),
+        };
+        Ok(Self {
+            text: row.text,
+            kind,
+            module: row
+                .module
+                .map(ModuleId::from_bytes)
+                .transpose()
+                .map_err(|err| MyError::Internal(err.into()))?,
+            score: row.score,
+        })
+    }
+}
+
+/// Escapes the wildcards of `LIKE` and matches everything starting with `input`.
+fn like_prefix(input: &str) -> String {
+    let mut pattern = String::with_capacity(input.len() + 1);
+    for char in input.chars() {
+        if matches!(char, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(char);
+    }
+    pattern.push('%');
+    pattern
+}
+
+fn like_contains(input: &str) -> String {
+    format!("%{}", like_prefix(input))
+}
+
+/// Completes partial words and tolerates typos using trigrams, unlike the full text search.
+#[tracing::instrument(skip_all)]
+pub async fn suggest_postgres(pool: &DbPool, input: &str) -> Result<Vec<Suggestion>, MyError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut connection = pool.get().await?;
+
+    sql_query(SUGGESTIONS)
+        .bind::<Text, _>(input)
+        .bind::<Text, _>(like_prefix(input))
+        .bind::<BigInt, _>(SUGGESTION_LIMIT)
+        .load::<SuggestionRow>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .map(Suggestion::try_from)
+        .collect()
+}
+
+/// How many lexemes of a module are searched for in the others to find similar ones.
+const SIMILAR_TERMS: i64 = 25;
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Typescriptable)]
+pub struct SimilarModulesQuery {
+    pub module: ModuleId,
+    /// Defaults to [`DEFAULT_PAGE_SIZE`] and is capped at [`MAX_PAGE_SIZE`].
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Only modules in the menu the user can register in or one of its submenus.
+    #[serde(default)]
+    pub registrable_only: bool,
+    /// Leaves out the modules the user is already registered for.
+    #[serde(default)]
+    pub exclude_registered: bool,
+}
+
+/// A [`SimilarModulesQuery`] with the menu and modules of the user resolved.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SimilarModules {
+    pub module: ModuleId,
+    pub limit: u32,
+    pub menu: Option<MenuPath>,
+    pub excluded: Vec<ModuleId>,
+}
+
+impl SimilarModules {
+    #[must_use]
+    pub fn new(
+        query: &SimilarModulesQuery,
+        menu: Option<MenuPath>,
+        excluded: Vec<ModuleId>,
+    ) -> Self {
+        Self {
+            module: query.module.clone(),
+            limit: clamp_page_size(query.limit),
+            menu,
+            excluded,
+        }
+    }
+}
+
+/// Ranks the modules by the most important lexemes of the `tsv` of a module, those in its title
+/// first and then the most frequent ones. Lexemes without three letters in a row (like the parts
+/// of module ids) would make every module similar.
+fn similar_modules_sql() -> String {
+    format!(
+        "WITH terms AS (
+            SELECT word.lexeme
+            FROM modules_unfinished source, unnest(source.tsv) AS word
+            WHERE source.tucan_id = $1 AND word.lexeme ~ '[[:alpha:]]{{3}}'
+            ORDER BY 'A' = ANY(word.weights) DESC, cardinality(word.positions) DESC, word.lexeme
+            LIMIT $2
+        ), likeness AS (
+            SELECT CAST(string_agg(quote_literal(terms.lexeme), ' | ') AS tsquery) AS query
+            FROM terms
+        )
+        SELECT m.tucan_id AS id, m.title, ts_headline('tucan', m.content, l.query) AS excerpt,
+            CAST(ts_rank_cd(m.tsv, l.query, 1) AS FLOAT8) AS rank
+        FROM modules_unfinished m, likeness l
+        WHERE m.tucan_id <> $1 AND m.tsv @@ l.query AND m.tucan_id <> ALL($3)
+            AND ($4 IS NULL OR m.tucan_id IN ({}$4{}))
+        ORDER BY rank DESC, m.tucan_id
+        LIMIT $5",
+        MODULES_IN_MENU.0, MODULES_IN_MENU.1
+    )
+}
+
+#[derive(QueryableByName, Debug)]
+struct SimilarModuleRow {
+    #[diesel(sql_type = Bytea)]
+    id: ModuleId,
+    #[diesel(sql_type = Text)]
+    title: String,
+    #[diesel(sql_type = Text)]
+    excerpt: String,
+    #[diesel(sql_type = Double)]
+    rank: f64,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn similar_modules_postgres(
+    pool: &DbPool,
+    similar: &SimilarModules,
+) -> Result<Vec<SearchHit<ModuleId>>, MyError> {
+    let mut connection = pool.get().await?;
+
+    // a module without any lexemes has no similar modules but still has to exist
+    modules_unfinished::table
+        .filter(modules_unfinished::tucan_id.eq(&similar.module))
+        .select(modules_unfinished::tucan_id)
+        .get_result::<ModuleId>(&mut connection)
+        .traced()
+        .await?;
+
+    Ok(sql_query(similar_modules_sql())
+        .bind::<Bytea, _>(&similar.module)
+        .bind::<BigInt, _>(SIMILAR_TERMS)
+        .bind::<Array<Bytea>, _>(&similar.excluded)
+        .bind::<Nullable<Bytea>, _>(&similar.menu)
+        .bind::<BigInt, _>(i64::from(similar.limit))
+        .load::<SimilarModuleRow>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .map(|row| SearchHit {
+            id: row.id,
+            title: row.title,
+            excerpt: row.excerpt,
+            rank: row.rank,
+        })
+        .collect())
+}
+
+fn language_order(language: Language) -> [&'static str; 2] {
+    match language {
+        Language::German => ["de", "en"],
+        Language::English => ["en", "de"],
+    }
+}
+
+fn highlight(hit: &Value, field: &str, language: Language) -> Option<String> {
+    language_order(language).into_iter().find_map(|lang| {
+        hit["highlight"][format!("{field}.{lang}")]
+            .as_array()
+            .map(|fragments| fragments.iter().filter_map(Value::as_str).join("[...]"))
+    })
+}
+
+fn localized_source(hit: &Value, field: &str, language: Language) -> Option<String> {
+    language_order(language)
+        .into_iter()
+        .find_map(|lang| hit["_source"][field][lang].as_str().map(str::to_string))
+}
+
+fn opensearch_comparison(field: &str, comparison: Comparison, value: impl Into<Value>) -> Value {
+    match comparison.opensearch_range() {
+        None => json!({ "term": { field: value.into() } }),
+        Some(range) => json!({ "range": { field: { range: value.into() } } }),
+    }
+}
+
+fn opensearch_match(term: &Term) -> Value {
+    json!({
+        "multi_match": {
+            "query": term.text,
+            "type": if term.phrase { "phrase" } else { "best_fields" },
+            "fields": [
+              "title.de^3",
+              "title.en^3",
+              "content.de",
+              "content.en"
+            ],
+        }
+    })
+}
+
+/// The body of a search in an index with localized `title` and `content`, the `semesters`,
+/// `menus` and `lecturers` and the number field (`credits` or `sws`) named by `number`. Like
+/// in Postgres only the filters select if there is nothing to search in the text.
+fn opensearch_body(search: &SearchQuery, number: &str, aggs: Value) -> Result<Value, MyError> {
+    let parsed = search.parse()?;
+    let mut filter = Vec::new();
+    for field_filter in &parsed.filters {
+        match field_filter {
+            FieldFilter::Credits(comparison, credits) if number == "credits" => {
+                filter.push(opensearch_comparison("credits", *comparison, *credits));
+            }
+            FieldFilter::Sws(comparison, sws) if number == "sws" => {
+                filter.push(opensearch_comparison("sws", *comparison, *sws));
+            }
+            FieldFilter::Credits(..)
+            | FieldFilter::Sws(..)
+            | FieldFilter::Language(Language::German) => {}
+            FieldFilter::Lecturer(name) => {
+                filter.push(json!({ "match_phrase": { "lecturers": name } }));
+            }
+            FieldFilter::Semester(semester) => {
+                filter.push(json!({ "term": { "semesters": semester } }));
+            }
+            FieldFilter::Language(Language::English) => {
+                filter.push(json!({ "exists": { "field": "title.en" } }));
+            }
+        }
+    }
+    if let Some(menu) = &search.filters.menu {
+        filter.push(json!({ "term": { "menus": menu } }));
+    }
+
+    let (negated, terms): (Vec<_>, Vec<_>) = parsed.terms.iter().partition(|term| term.negated);
+    let (phrases, words): (Vec<_>, Vec<_>) = terms.into_iter().partition(|term| term.phrase);
+    let mut must = phrases.into_iter().map(opensearch_match).collect_vec();
+    if !words.is_empty() || parsed.is_empty() {
+        must.push(opensearch_match(&Term {
+            text: words.iter().map(|term| &term.text).join(" "),
+            phrase: false,
+            negated: false,
+        }));
+    }
+
+    Ok(json!({
+        "from": search.offset(),
+        "size": search.page_size(),
+        "track_total_hits": true,
+        "query": {
+            "bool": {
+                "must": must,
+                "must_not": negated.into_iter().map(opensearch_match).collect_vec(),
+                "filter": filter,
+            }
+        },
+        "aggs": aggs,
+        "highlight": {
+            "require_field_match": false,
+            "pre_tags": ["<b>"],
+            "post_tags": ["</b>"],
+            "fields": {
+                "title.de": {},
+                "title.en": {},
+                "content.de": {},
+                "content.en": {},
+            }
+        }
+    }))
+}
+
+/// The body of a search in the `tucant_modules` index.
+pub fn modules_opensearch_body(search: &SearchQuery) -> Result<Value, MyError> {
+    opensearch_body(
+        search,
+        "credits",
+        json!({
+            "credits": { "terms": { "field": "credits", "size": FACET_SIZE } },
+            "semesters": { "terms": { "field": "semesters", "size": FACET_SIZE } },
+        }),
+    )
+}
+
+/// The body of a search in the `tucant_courses` index.
+pub fn courses_opensearch_body(search: &SearchQuery) -> Result<Value, MyError> {
+    opensearch_body(
+        search,
+        "sws",
+        json!({
+            "sws": { "terms": { "field": "sws", "size": FACET_SIZE } },
+            "semesters": { "terms": { "field": "semesters", "size": FACET_SIZE } },
+        }),
+    )
+}
+
+fn unexpected() -> MyError {
+    MyError::UpstreamUnavailable(anyhow::anyhow!("unexpected opensearch response"))
+}
+
+fn opensearch_facet(response_body: &Value, name: &str) -> Result<Vec<FacetCount>, MyError> {
+    response_body["aggregations"][name]["buckets"]
+        .as_array()
+        .ok_or_else(unexpected)?
+        .iter()
+        .map(|bucket| {
+            Ok(FacetCount {
+                value: match &bucket["key"] {
+                    Value::String(key) => key.clone(),
+                    Value::Number(key) => key.to_string(),
+                    _ => return Err(unexpected()),
+                },
+                count: bucket["doc_count"].as_i64().ok_or_else(unexpected)?,
+            })
+        })
+        .collect()
+}
+
+/// The hits and the total number of matches, `parse_id` decodes the document ids.
+fn opensearch_results<Id: tucant_derive_lib::Typescriptable>(
+    response_body: &Value,
+    language: Language,
+    parse_id: impl Fn(&str) -> Result<Id, IdError>,
+) -> Result<(Vec<SearchResult<Id>>, i64), MyError> {
+    let results = response_body["hits"]["hits"]
+        .as_array()
+        .ok_or_else(unexpected)?
+        .iter()
+        .map(|hit| {
+            Ok(SearchResult {
+                tucan_id: parse_id(hit["_id"].as_str().ok_or_else(unexpected)?)
+                    .map_err(|err| MyError::UpstreamUnavailable(err.into()))?,
+                title: highlight(hit, "title", language)
+                    .or_else(|| localized_source(hit, "title", language))
+                    .unwrap_or_default(),
+                excerpt: highlight(hit, "content", language).unwrap_or_default(),
+                rank: hit["_score"].as_f64().ok_or_else(unexpected)?,
+            })
+        })
+        .collect::<Result<Vec<_>, MyError>>()?;
+    let total = response_body["hits"]["total"]["value"]
+        .as_i64()
+        .ok_or_else(unexpected)?;
+    Ok((results, total))
+}
+
+pub fn modules_opensearch_response(
+    search: &SearchQuery,
+    response_body: &Value,
+    language: Language,
+) -> Result<SearchResponse<ModuleId>, MyError> {
+    let (results, total) = opensearch_results(response_body, language, ModuleId::from_base64)?;
+
+    Ok(SearchResponse {
+        results,
+        total,
+        page: search.page,
+        page_size: search.page_size(),
+        facets: SearchFacets {
+            credits: opensearch_facet(response_body, "credits")?,
+            sws: Vec::new(),
+            semesters: opensearch_facet(response_body, "semesters")?,
+        },
+    })
+}
+
+pub fn courses_opensearch_response(
+    search: &SearchQuery,
+    response_body: &Value,
+    language: Language,
+) -> Result<SearchResponse<CourseId>, MyError> {
+    let (results, total) = opensearch_results(response_body, language, CourseId::from_base64)?;
+
+    Ok(SearchResponse {
+        results,
+        total,
+        page: search.page,
+        page_size: search.page_size(),
+        facets: SearchFacets {
+            credits: Vec::new(),
+            sws: opensearch_facet(response_body, "sws")?,
+            semesters: opensearch_facet(response_body, "semesters")?,
+        },
+    })
+}
+
+/// The body of a completion suggestion request in the `tucant_modules` index. Its `suggest`
+/// field contains the module id and every word suffix of the titles so inner words complete too.
+#[must_use]
+pub fn modules_suggest_opensearch_body(input: &str) -> Value {
+    json!({
+        "_source": ["title", "module_id"],
+        "suggest": {
+            "modules": {
+                "prefix": input.trim(),
+                "completion": {
+                    "field": "suggest",
+                    "size": SUGGESTION_LIMIT,
+                    "skip_duplicates": true,
+                    "fuzzy": { "fuzziness": "AUTO" },
+                }
+            }
+        }
+    })
+}
+
+pub fn modules_suggest_opensearch_response(
+    response_body: &Value,
+    language: Language,
+) -> Result<Vec<Suggestion>, MyError> {
+    let Some(suggestions) = response_body["suggest"]["modules"].as_array() else {
+        // no suggestions are returned for an empty prefix
+        return Ok(Vec::new());
+    };
+    suggestions
+        .iter()
+        .flat_map(|suggestion| suggestion["options"].as_array().into_iter().flatten())
+        .map(|option| {
+            let module_id = option["_source"]["module_id"].as_str();
+            let text = option["text"].as_str().ok_or_else(unexpected)?;
+            Ok(Suggestion {
+                text: if module_id == Some(text) {
+                    text.to_string()
+                } else {
+                    localized_source(option, "title", language).ok_or_else(unexpected)?
+                },
+                kind: SearchKind::Module,
+                module: Some(
+                    ModuleId::from_base64(option["_id"].as_str().ok_or_else(unexpected)?)
+                        .map_err(|err| MyError::UpstreamUnavailable(err.into()))?,
+                ),
+                score: option["_score"].as_f64().ok_or_else(unexpected)?,
+            })
+        })
+        .collect()
+}
+
+/// The body of a `more_like_this` search for modules similar to `similar.module` in the
+/// `tucant_modules` index, the module itself is never returned.
+#[must_use]
+pub fn similar_modules_opensearch_body(similar: &SimilarModules) -> Value {
+    let excluded = similar
+        .excluded
+        .iter()
+        .map(ModuleId::to_base64)
+        .collect_vec();
+    json!({
+        "size": similar.limit,
+        "_source": ["title"],
+        "query": {
+            "bool": {
+                "must": {
+                    "more_like_this": {
+                        "fields": ["title.de", "title.en", "content.de", "content.en"],
+                        "like": [{ "_id": similar.module.to_base64() }],
+                        "min_term_freq": 1,
+                        "max_query_terms": SIMILAR_TERMS,
+                    }
+                },
+                "must_not": {
+                    "ids": { "values": excluded }
+                },
+                "filter": similar
+                    .menu
+                    .iter()
+                    .map(|menu| json!({ "term": { "menus": menu } }))
+                    .collect_vec(),
+            }
+        },
+        "highlight": {
+            "require_field_match": false,
+            "pre_tags": ["<b>"],
+            "post_tags": ["</b>"],
+            "fields": {
+                "title.de": {},
+                "title.en": {},
+                "content.de": {},
+                "content.en": {},
+            }
+        }
+    })
+}
+
+pub fn similar_modules_opensearch_response(
+    response_body: &Value,
+    language: Language,
+) -> Result<Vec<SearchHit<ModuleId>>, MyError> {
+    let (results, _) = opensearch_results(response_body, language, ModuleId::from_base64)?;
+    Ok(results
+        .into_iter()
+        .map(|result| SearchHit {
+            id: result.tucan_id,
+            title: result.title,
+            excerpt: result.excerpt,
+            rank: result.rank,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        courses_opensearch_body, courses_opensearch_response, like_prefix, modules_opensearch_body,
+        modules_opensearch_response, modules_suggest_opensearch_response,
+        similar_modules_opensearch_body, similar_modules_opensearch_response, FacetCount,
+        KindWeight, SearchFilters, SearchHit, SearchKind, SearchQuery, SimilarModules,
+        SimilarModulesQuery, Suggestion, SuggestionRow, UnifiedHitRow, UnifiedSearchHit,
+        UnifiedSearchQuery, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+    };
+    use crate::ids::{CourseId, MenuPath, ModuleId};
+    use crate::models::Language;
+    use crate::MyError;
+
+    #[test]
+    fn test_pagination() {
+        let mut search: SearchQuery = serde_json::from_str(r#"{ "query": "test" }"#).unwrap();
+        assert_eq!(search.page_size(), DEFAULT_PAGE_SIZE);
+        assert_eq!(search.offset(), 0);
+
+        search.page = 3;
+        search.page_size = Some(10);
+        assert_eq!(search.offset(), 30);
+
+        search.page_size = Some(10_000);
+        assert_eq!(search.page_size(), MAX_PAGE_SIZE);
+        search.page_size = Some(0);
+        assert_eq!(search.page_size(), 1);
+    }
+
+    #[test]
+    fn test_language_filter() {
+        let mut search = SearchQuery::default();
+        assert_eq!(search.language().unwrap(), None);
+        search.filters.language = Some("en".to_string());
+        assert_eq!(search.language().unwrap(), Some(Language::English));
+        search.filters.language = Some("fr".to_string());
+        assert!(matches!(search.language(), Err(MyError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_opensearch_filters() {
+        let search = SearchQuery {
+            query: "analysis".to_string(),
+            page: 2,
+            page_size: Some(5),
+            filters: SearchFilters {
+                credits_min: Some(5),
+                semester: Some("WiSe 2022/23".to_string()),
+                sws_min: Some(4),
+                ..SearchFilters::default()
+            },
+        };
+        let body = modules_opensearch_body(&search).unwrap();
+        assert_eq!(body["from"], 10);
+        assert_eq!(body["size"], 5);
+        assert_eq!(
+            body["query"]["bool"]["filter"],
+            json!([
+                { "range": { "credits": { "gte": 5 } } },
+                { "term": { "semesters": "WiSe 2022/23" } },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_opensearch_query_language() {
+        let search = SearchQuery {
+            query: r#"credits:6 sws:2 lecturer:Weinmann "software engineering" -seminar entwurf"#
+                .to_string(),
+            ..SearchQuery::default()
+        };
+        let body = modules_opensearch_body(&search).unwrap();
+        assert_eq!(
+            body["query"]["bool"]["filter"],
+            json!([
+                { "term": { "credits": 6 } },
+                { "match_phrase": { "lecturers": "Weinmann" } },
+            ])
+        );
+        let must = &body["query"]["bool"]["must"];
+        assert_eq!(must[0]["multi_match"]["query"], "software engineering");
+        assert_eq!(must[0]["multi_match"]["type"], "phrase");
+        assert_eq!(must[1]["multi_match"]["query"], "entwurf");
+        assert_eq!(
+            body["query"]["bool"]["must_not"][0]["multi_match"]["query"],
+            "seminar"
+        );
+
+        // only the filters select
+        let search = SearchQuery {
+            query: "credits:>5".to_string(),
+            ..SearchQuery::default()
+        };
+        let body = modules_opensearch_body(&search).unwrap();
+        assert_eq!(body["query"]["bool"]["must"], json!([]));
+        assert_eq!(
+            body["query"]["bool"]["filter"],
+            json!([{ "range": { "credits": { "gt": 5 } } }])
+        );
+
+        let search = SearchQuery {
+            query: "credits:".to_string(),
+            ..SearchQuery::default()
+        };
+        assert!(matches!(
+            modules_opensearch_body(&search),
+            Err(MyError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_opensearch_response() {
+        let id = ModuleId::from_bytes(vec![0; 8]).unwrap();
+        let search = SearchQuery::default();
+        let response = modules_opensearch_response(
+            &search,
+            &json!({
+                "hits": {
+                    "total": { "value": 42, "relation": "eq" },
+                    "hits": [{
+                        "_id": id.to_base64(),
+                        "_score": 1.5,
+                        "_source": { "title": { "de": "Analysis", "en": "Calculus" } },
+                        "highlight": { "content.de": ["<b>a</b>", "<b>b</b>"] }
+                    }]
+                },
+                "aggregations": {
+                    "credits": { "buckets": [{ "key": 5, "doc_count": 30 }] },
+                    "semesters": { "buckets": [{ "key": "SoSe 2023", "doc_count": 12 }] }
+                }
+            }),
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(response.total, 42);
+        assert_eq!(response.results[0].tucan_id, id);
+        assert_eq!(response.results[0].title, "Calculus");
+        assert_eq!(response.results[0].excerpt, "<b>a</b>[...]<b>b</b>");
+        assert_eq!(
+            response.facets.credits,
+            vec![FacetCount {
+                value: "5".to_string(),
+                count: 30
+            }]
+        );
+        assert_eq!(response.facets.semesters[0].value, "SoSe 2023");
+    }
+
+    #[test]
+    fn test_unified_weights() {
+        let mut search = UnifiedSearchQuery::default();
+        assert_eq!(search.weights().unwrap(), [1.0; SearchKind::ALL.len()]);
+
+        search.kinds = vec![
+            KindWeight {
+                kind: SearchKind::Course,
+                weight: 2.0,
+            },
+            KindWeight {
+                kind: SearchKind::Lecturer,
+                weight: 0.5,
+            },
+        ];
+        assert_eq!(search.weights().unwrap(), [0.0, 2.0, 0.0, 0.0, 0.5]);
+
+        search.kinds[0].weight = -1.0;
+        assert!(matches!(search.weights(), Err(MyError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_unified_hit() {
+        let row = |kind: &str, id: Option<Vec<u8>>, name: Option<&str>| UnifiedHitRow {
+            kind: kind.to_string(),
+            id,
+            name: name.map(str::to_string),
+            title: "title".to_string(),
+            excerpt: "excerpt".to_string(),
+            rank: 0.5,
+        };
+
+        assert_eq!(
+            UnifiedSearchHit::try_from(row("Module", Some(vec![1; 8]), None)).unwrap(),
+            UnifiedSearchHit::Module(SearchHit {
+                id: ModuleId::from_bytes(vec![1; 8]).unwrap(),
+                title: "title".to_string(),
+                excerpt: "excerpt".to_string(),
+                rank: 0.5,
+            })
+        );
+        assert!(matches!(
+            UnifiedSearchHit::try_from(row("Lecturer", None, Some("Dr. Who"))).unwrap(),
+            UnifiedSearchHit::Lecturer(SearchHit { id, .. }) if id == "Dr. Who"
+        ));
+        assert!(matches!(
+            UnifiedSearchHit::try_from(row("Exam", None, None)),
+            Err(MyError::Internal(_))
+        ));
+        assert!(matches!(
+            UnifiedSearchHit::try_from(row("Exam", Some(vec![1; 3]), None)),
+            Err(MyError::Internal(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_kind_json() {
+        let kind: KindWeight =
+            serde_json::from_str(r#"{ "kind": { "type": "CourseGroup" }, "weight": 1.5 }"#)
+                .unwrap();
+        assert_eq!(kind.kind, SearchKind::CourseGroup);
+    }
+
+    #[test]
+    fn test_like_prefix() {
+        assert_eq!(like_prefix("20-00"), "20-00%");
+        assert_eq!(like_prefix(r"100%_\"), r"100\%\_\\%");
+    }
+
+    #[test]
+    fn test_suggestion_row() {
+        let suggestion = Suggestion::try_from(SuggestionRow {
+            text: "Dr. Who".to_string(),
+            kind: "Lecturer".to_string(),
+            module: None,
+            score: 0.8,
+        })
+        .unwrap();
+        assert_eq!(suggestion.kind, SearchKind::Lecturer);
+        assert_eq!(suggestion.module, None);
+    }
+
+    #[test]
+    fn test_opensearch_suggestions() {
+        let id = ModuleId::from_bytes(vec![2; 8]).unwrap();
+        let source = json!({
+            "module_id": "20-00-0005",
+            "title": { "de": "Algorithmen und Datenstrukturen", "en": null }
+        });
+        let suggestions = modules_suggest_opensearch_response(
+            &json!({
+                "suggest": {
+                    "modules": [{
+                        "text": "Datenstr",
+                        "options": [
+                            { "text": "Datenstrukturen", "_id": id.to_base64(), "_score": 1.0, "_source": source },
+                            { "text": "20-00-0005", "_id": id.to_base64(), "_score": 0.5, "_source": source },
+                        ]
+                    }]
+                }
+            }),
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(suggestions[0].text, "Algorithmen und Datenstrukturen");
+        assert_eq!(suggestions[0].module, Some(id));
+        assert_eq!(suggestions[1].text, "20-00-0005");
+
+        assert!(
+            modules_suggest_opensearch_response(&json!({ "hits": {} }), Language::German)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_opensearch_courses() {
+        let search = SearchQuery {
+            query: "analysis".to_string(),
+            filters: SearchFilters {
+                credits_min: Some(5),
+                sws_max: Some(4),
+                ..SearchFilters::default()
+            },
+            ..SearchQuery::default()
+        };
+        let body = courses_opensearch_body(&search).unwrap();
+        assert_eq!(
+            body["query"]["bool"]["filter"],
+            json!([{ "range": { "sws": { "lte": 4 } } }])
+        );
+        assert!(body["aggs"]["sws"].is_object());
+
+        let id = CourseId::new(42, 0);
+        let response = courses_opensearch_response(
+            &search,
+            &json!({
+                "hits": {
+                    "total": { "value": 1, "relation": "eq" },
+                    "hits": [{
+                        "_id": id.to_base64(),
+                        "_score": 2.0,
+                        "_source": { "title": { "de": "Analysis", "en": null } },
+                    }]
+                },
+                "aggregations": {
+                    "sws": { "buckets": [{ "key": 4, "doc_count": 1 }] },
+                    "semesters": { "buckets": [] }
+                }
+            }),
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(response.results[0].tucan_id, id);
+        assert_eq!(response.results[0].title, "Analysis");
+        assert_eq!(response.facets.sws[0].value, "4");
+        assert!(response.facets.credits.is_empty());
+    }
+
+    #[test]
+    fn test_similar_modules_opensearch() {
+        let module = ModuleId::from_bytes(vec![1; 8]).unwrap();
+        let other = ModuleId::from_bytes(vec![2; 8]).unwrap();
+        let query: SimilarModulesQuery = serde_json::from_value(json!({
+            "module": module.to_base64(),
+            "limit": 500,
+        }))
+        .unwrap();
+        assert!(!query.registrable_only && !query.exclude_registered);
+
+        let similar =
+            SimilarModules::new(&query, Some(MenuPath::new([1, 0, 0])), vec![other.clone()]);
+        assert_eq!(similar.limit, MAX_PAGE_SIZE);
+        let body = similar_modules_opensearch_body(&similar);
+        assert_eq!(
+            body["query"]["bool"]["must"]["more_like_this"]["like"],
+            json!([{ "_id": module.to_base64() }])
+        );
+        assert_eq!(
+            body["query"]["bool"]["must_not"]["ids"]["values"],
+            json!([other.to_base64()])
+        );
+        assert_eq!(body["query"]["bool"]["filter"].as_array().unwrap().len(), 1);
+
+        let hits = similar_modules_opensearch_response(
+            &json!({
+                "hits": {
+                    "total": { "value": 1, "relation": "eq" },
+                    "hits": [{
+                        "_id": other.to_base64(),
+                        "_score": 3.0,
+                        "_source": { "title": { "de": "Analysis", "en": null } },
+                        "highlight": { "content.de": ["<b>Folgen</b>"] }
+                    }]
+                }
+            }),
+            Language::German,
+        )
+        .unwrap();
+        assert_eq!(
+            hits,
+            vec![SearchHit {
+                id: other,
+                title: "Analysis".to_string(),
+                excerpt: "<b>Folgen</b>".to_string(),
+                rank: 3.0,
+            }]
+        );
+    }
+}
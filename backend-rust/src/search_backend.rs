@@ -0,0 +1,817 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! The engines that answer the search routes, selected by [`Config::search_backend`] so the
+//! routes don't have to care which one is deployed.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use itertools::Itertools;
+use opensearch::{
+    auth::Credentials,
+    cert::CertificateValidation,
+    cluster::ClusterHealthParts,
+    http::{
+        request::JsonBody,
+        transport::{SingleNodeConnectionPool, TransportBuilder},
+    },
+    params::Refresh,
+    BulkParts, DeleteParts, OpenSearch, SearchParts,
+};
+use reqwest::Url;
+use serde_json::{json, Value};
+
+use crate::config::{Config, OpensearchConfig, SearchBackendKind};
+use crate::ids::{CourseId, MenuPath, ModuleId};
+use crate::models::{Course, Language, Module, COURSES_UNFINISHED, MODULES_UNFINISHED};
+use crate::schema::{
+    course_events, course_exams, courses_unfinished, exams_unfinished, module_courses,
+    module_exams, module_menu_module, module_menu_unfinished, modules_unfinished,
+};
+use crate::search::{
+    courses_opensearch_body, courses_opensearch_response, modules_opensearch_body,
+    modules_opensearch_response, modules_suggest_opensearch_body,
+    modules_suggest_opensearch_response, search_courses_postgres, search_modules_postgres,
+    search_unified_postgres, similar_modules_opensearch_body, similar_modules_opensearch_response,
+    similar_modules_postgres, suggest_postgres, SearchHit, SearchQuery, SearchResponse,
+    SimilarModules, Suggestion, UnifiedSearchQuery, UnifiedSearchResponse,
+};
+use crate::telemetry::Traced;
+use crate::tucan::DbPool;
+use crate::MyError;
+
+/// The alias the modules are searched in, it points to the index of the last full rebuild.
+pub const MODULES_ALIAS: &str = "tucant_modules";
+pub const COURSES_ALIAS: &str = "tucant_courses";
+
+/// A module with everything that is searched or filtered by.
+#[derive(Debug, Clone)]
+pub struct ModuleDocument {
+    pub module: Module,
+    /// The semesters of the module's exams.
+    pub semesters: Vec<String>,
+    /// The menus the module is in and all their parents to filter by subtree.
+    pub menus: Vec<MenuPath>,
+    /// The examiners of the module's exams and who teaches the events of its courses.
+    pub lecturers: Vec<String>,
+}
+
+/// A course with everything that is searched or filtered by.
+#[derive(Debug, Clone)]
+pub struct CourseDocument {
+    pub course: Course,
+    /// The semesters of the course's exams.
+    pub semesters: Vec<String>,
+    /// The menus of the course's modules and all their parents.
+    pub menus: Vec<MenuPath>,
+    /// The examiners of the course's exams and who teaches its events.
+    pub lecturers: Vec<String>,
+}
+
+/// The inputs of the completion suggester: the module id and every word suffix of the titles,
+/// the suggester only matches prefixes so this also completes words in the middle of a title.
+fn suggest_inputs(module_id: &str, titles: &[&str]) -> Vec<String> {
+    let mut inputs = vec![module_id.to_string()];
+    for title in titles.iter().filter(|title| !title.is_empty()) {
+        let words = title.split_whitespace().collect_vec();
+        inputs.extend((0..words.len()).map(|i| words[i..].join(" ")));
+    }
+    inputs.into_iter().unique().collect()
+}
+
+/// The distinct names in examiner and teacher columns, the latter separate names by semicolons.
+fn lecturer_names<'a>(columns: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    columns
+        .into_iter()
+        .flat_map(|names| names.split(';'))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+fn localized(de: &str, en: &str) -> Value {
+    json!({
+        "de": de,
+        "en": (!en.is_empty()).then_some(en),
+    })
+}
+
+impl ModuleDocument {
+    /// The document in the `tucant_modules` index.
+    #[must_use]
+    pub fn to_opensearch(&self) -> Value {
+        let m = &self.module;
+        json!({
+            "id": m.tucan_id.to_base64(),
+            "last_checked": m.tucan_last_checked,
+            "title": localized(&m.title, &m.title_en),
+            "module_id": m.module_id,
+            "credits": m.credits,
+            "semesters": self.semesters,
+            "menus": self.menus,
+            "lecturers": self.lecturers,
+            "suggest": suggest_inputs(&m.module_id, &[&m.title, &m.title_en]),
+            "content": localized(&m.content, &m.content_en),
+        })
+    }
+}
+
+impl CourseDocument {
+    /// The document in the `tucant_courses` index.
+    #[must_use]
+    pub fn to_opensearch(&self) -> Value {
+        let c = &self.course;
+        json!({
+            "id": c.tucan_id.to_base64(),
+            "last_checked": c.tucan_last_checked,
+            "title": localized(&c.title, &c.title_en),
+            "course_id": c.course_id,
+            "sws": c.sws,
+            "semesters": self.semesters,
+            "menus": self.menus,
+            "lecturers": self.lecturers,
+            "content": localized(&c.content, &c.content_en),
+        })
+    }
+}
+
+/// The menus and all their parents, a module in a submenu is also found when filtering by a parent menu.
+fn with_ancestors<'a>(
+    menus: impl IntoIterator<Item = &'a MenuPath>,
+    parents: &HashMap<MenuPath, Option<MenuPath>>,
+) -> Vec<MenuPath> {
+    let mut result = HashSet::new();
+    for menu in menus {
+        let mut current = Some(menu);
+        while let Some(menu) = current {
+            if !result.insert(menu.clone()) {
+                break;
+            }
+            current = parents.get(menu).and_then(Option::as_ref);
+        }
+    }
+    result.into_iter().sorted().collect()
+}
+
+async fn menu_parents(
+    connection: &mut diesel_async::AsyncPgConnection,
+) -> Result<HashMap<MenuPath, Option<MenuPath>>, MyError> {
+    Ok(module_menu_unfinished::table
+        .select((
+            module_menu_unfinished::tucan_id,
+            module_menu_unfinished::parent,
+        ))
+        .load::<(MenuPath, Option<MenuPath>)>(connection)
+        .traced()
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// Loads the documents of these modules, or of all modules. Modules that don't exist are left out.
+#[tracing::instrument(skip_all)]
+pub async fn module_documents(
+    pool: &DbPool,
+    ids: Option<&[ModuleId]>,
+) -> Result<Vec<ModuleDocument>, MyError> {
+    let mut connection = pool.get().await?;
+
+    let mut modules = modules_unfinished::table
+        .select(MODULES_UNFINISHED)
+        .into_boxed();
+    let mut semesters = module_exams::table
+        .inner_join(exams_unfinished::table)
+        .select((module_exams::module_id, exams_unfinished::semester))
+        .distinct()
+        .into_boxed();
+    let mut menus = module_menu_module::table
+        .select((
+            module_menu_module::module_id,
+            module_menu_module::module_menu_id,
+        ))
+        .into_boxed();
+    let mut examiners = module_exams::table
+        .inner_join(exams_unfinished::table)
+        .select((module_exams::module_id, exams_unfinished::examinator))
+        .distinct()
+        .into_boxed();
+    let mut teachers = module_courses::table
+        .inner_join(courses_unfinished::table.inner_join(course_events::table))
+        .select((module_courses::module, course_events::teachers))
+        .distinct()
+        .into_boxed();
+    if let Some(ids) = ids {
+        modules = modules.filter(modules_unfinished::tucan_id.eq_any(ids));
+        semesters = semesters.filter(module_exams::module_id.eq_any(ids));
+        menus = menus.filter(module_menu_module::module_id.eq_any(ids));
+        examiners = examiners.filter(module_exams::module_id.eq_any(ids));
+        teachers = teachers.filter(module_courses::module.eq_any(ids));
+    }
+
+    let modules = modules.load::<Module>(&mut connection).traced().await?;
+    let semesters = semesters
+        .load::<(ModuleId, String)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .into_group_map();
+    let menus = menus
+        .load::<(ModuleId, MenuPath)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .into_group_map();
+    let lecturers = examiners
+        .load::<(ModuleId, Option<String>)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .filter_map(|(module, examiner)| Some((module, examiner?)))
+        .chain(
+            teachers
+                .load::<(ModuleId, String)>(&mut connection)
+                .traced()
+                .await?,
+        )
+        .into_group_map();
+    let parents = menu_parents(&mut connection).await?;
+
+    Ok(modules
+        .into_iter()
+        .map(|module| ModuleDocument {
+            semesters: semesters.get(&module.tucan_id).cloned().unwrap_or_default(),
+            menus: menus
+                .get(&module.tucan_id)
+                .map_or_else(Vec::new, |menus| with_ancestors(menus, &parents)),
+            lecturers: lecturer_names(lecturers.get(&module.tucan_id).into_iter().flatten()),
+            module,
+        })
+        .collect())
+}
+
+/// Loads the documents of these courses, or of all courses. Courses that don't exist are left out.
+#[tracing::instrument(skip_all)]
+pub async fn course_documents(
+    pool: &DbPool,
+    ids: Option<&[CourseId]>,
+) -> Result<Vec<CourseDocument>, MyError> {
+    let mut connection = pool.get().await?;
+
+    let mut courses = courses_unfinished::table
+        .select(COURSES_UNFINISHED)
+        .into_boxed();
+    let mut semesters = course_exams::table
+        .inner_join(exams_unfinished::table)
+        .select((course_exams::course_id, exams_unfinished::semester))
+        .distinct()
+        .into_boxed();
+    let mut modules = module_courses::table
+        .select((module_courses::course, module_courses::module))
+        .into_boxed();
+    let mut examiners = course_exams::table
+        .inner_join(exams_unfinished::table)
+        .select((course_exams::course_id, exams_unfinished::examinator))
+        .distinct()
+        .into_boxed();
+    let mut teachers = course_events::table
+        .select((course_events::course, course_events::teachers))
+        .distinct()
+        .into_boxed();
+    if let Some(ids) = ids {
+        courses = courses.filter(courses_unfinished::tucan_id.eq_any(ids));
+        semesters = semesters.filter(course_exams::course_id.eq_any(ids));
+        modules = modules.filter(module_courses::course.eq_any(ids));
+        examiners = examiners.filter(course_exams::course_id.eq_any(ids));
+        teachers = teachers.filter(course_events::course.eq_any(ids));
+    }
+
+    let courses = courses.load::<Course>(&mut connection).traced().await?;
+    let semesters = semesters
+        .load::<(CourseId, String)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .into_group_map();
+    let modules = modules
+        .load::<(CourseId, ModuleId)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .into_group_map();
+    let menus = module_menu_module::table
+        .filter(module_menu_module::module_id.eq_any(modules.values().flatten()))
+        .select((
+            module_menu_module::module_id,
+            module_menu_module::module_menu_id,
+        ))
+        .load::<(ModuleId, MenuPath)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .into_group_map();
+    let lecturers = examiners
+        .load::<(CourseId, Option<String>)>(&mut connection)
+        .traced()
+        .await?
+        .into_iter()
+        .filter_map(|(course, examiner)| Some((course, examiner?)))
+        .chain(
+            teachers
+                .load::<(CourseId, String)>(&mut connection)
+                .traced()
+                .await?,
+        )
+        .into_group_map();
+    let parents = menu_parents(&mut connection).await?;
+
+    Ok(courses
+        .into_iter()
+        .map(|course| CourseDocument {
+            semesters: semesters.get(&course.tucan_id).cloned().unwrap_or_default(),
+            menus: modules
+                .get(&course.tucan_id)
+                .map_or_else(Vec::new, |modules| {
+                    with_ancestors(
+                        modules
+                            .iter()
+                            .filter_map(|module| menus.get(module))
+                            .flatten(),
+                        &parents,
+                    )
+                }),
+            lecturers: lecturer_names(lecturers.get(&course.tucan_id).into_iter().flatten()),
+            course,
+        })
+        .collect())
+}
+
+#[axum::async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Adds the modules or replaces their previous documents.
+    async fn index_modules(&self, modules: &[ModuleDocument]) -> Result<(), MyError>;
+
+    /// Does nothing if the module was never indexed.
+    async fn delete_module(&self, module: &ModuleId) -> Result<(), MyError>;
+
+    /// Adds the courses or replaces their previous documents.
+    async fn index_courses(&self, courses: &[CourseDocument]) -> Result<(), MyError>;
+
+    /// Does nothing if the course was never indexed.
+    async fn delete_course(&self, course: &CourseId) -> Result<(), MyError>;
+
+    /// `language` is the language titles are returned in, not a filter.
+    async fn search_modules(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<ModuleId>, MyError>;
+
+    async fn search_courses(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<CourseId>, MyError>;
+
+    async fn search_unified(
+        &self,
+        search: &UnifiedSearchQuery,
+    ) -> Result<UnifiedSearchResponse, MyError>;
+
+    async fn suggest(&self, input: &str, language: Language) -> Result<Vec<Suggestion>, MyError>;
+
+    /// The modules most similar to `similar.module` first, without the module itself.
+    async fn similar_modules(
+        &self,
+        similar: &SimilarModules,
+        language: Language,
+    ) -> Result<Vec<SearchHit<ModuleId>>, MyError>;
+
+    /// Fails if searches can't be answered, the database is checked separately.
+    async fn ping(&self) -> Result<(), MyError>;
+}
+
+/// Creates the backend selected in the configuration.
+pub fn from_config(config: &Config, pool: DbPool) -> anyhow::Result<Arc<dyn SearchBackend>> {
+    Ok(match config.search_backend {
+        SearchBackendKind::Postgres => Arc::new(PostgresSearch::new(pool)),
+        SearchBackendKind::Opensearch => Arc::new(OpensearchSearch::new(&config.opensearch, pool)?),
+    })
+}
+
+/// The full text search of the database. Its index is the generated `tsv` column which is
+/// updated together with the rows, so there is nothing to index.
+#[derive(Clone)]
+pub struct PostgresSearch {
+    pool: DbPool,
+}
+
+impl PostgresSearch {
+    #[must_use]
+    pub const fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[axum::async_trait]
+impl SearchBackend for PostgresSearch {
+    async fn index_modules(&self, _modules: &[ModuleDocument]) -> Result<(), MyError> {
+        Ok(())
+    }
+
+    async fn delete_module(&self, _module: &ModuleId) -> Result<(), MyError> {
+        Ok(())
+    }
+
+    async fn index_courses(&self, _courses: &[CourseDocument]) -> Result<(), MyError> {
+        Ok(())
+    }
+
+    async fn delete_course(&self, _course: &CourseId) -> Result<(), MyError> {
+        Ok(())
+    }
+
+    async fn search_modules(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<ModuleId>, MyError> {
+        search_modules_postgres(&self.pool, search, language).await
+    }
+
+    async fn search_courses(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<CourseId>, MyError> {
+        search_courses_postgres(&self.pool, search, language).await
+    }
+
+    async fn search_unified(
+        &self,
+        search: &UnifiedSearchQuery,
+    ) -> Result<UnifiedSearchResponse, MyError> {
+        search_unified_postgres(&self.pool, search).await
+    }
+
+    async fn suggest(&self, input: &str, _language: Language) -> Result<Vec<Suggestion>, MyError> {
+        suggest_postgres(&self.pool, input).await
+    }
+
+    async fn similar_modules(
+        &self,
+        similar: &SimilarModules,
+        _language: Language,
+    ) -> Result<Vec<SearchHit<ModuleId>>, MyError> {
+        similar_modules_postgres(&self.pool, similar).await
+    }
+
+    async fn ping(&self) -> Result<(), MyError> {
+        Ok(())
+    }
+}
+
+/// Searches modules and courses in OpenSearch, the indices have to be filled by the
+/// `opensearch_indexer`. The unified search is still answered by Postgres.
+#[derive(Clone)]
+pub struct OpensearchSearch {
+    client: OpenSearch,
+    modules_index: String,
+    courses_index: String,
+    postgres: PostgresSearch,
+}
+
+impl OpensearchSearch {
+    pub fn new(config: &OpensearchConfig, pool: DbPool) -> anyhow::Result<Self> {
+        let url = Url::parse(&config.url)?;
+        let conn_pool = SingleNodeConnectionPool::new(url);
+        let transport = TransportBuilder::new(conn_pool)
+            .auth(Credentials::Basic(
+                config.username.clone(),
+                config.password.clone(),
+            ))
+            .cert_validation(if config.accept_invalid_certificates {
+                CertificateValidation::None
+            } else {
+                CertificateValidation::Default
+            })
+            .build()?;
+
+        Ok(Self {
+            client: OpenSearch::new(transport),
+            modules_index: MODULES_ALIAS.to_string(),
+            courses_index: COURSES_ALIAS.to_string(),
+            postgres: PostgresSearch::new(pool),
+        })
+    }
+
+    /// Writes to and searches these indices instead of the aliases.
+    #[must_use]
+    pub fn with_indices(mut self, modules: impl Into<String>, courses: impl Into<String>) -> Self {
+        self.modules_index = modules.into();
+        self.courses_index = courses.into();
+        self
+    }
+
+    #[must_use]
+    pub fn modules_index(&self) -> &str {
+        &self.modules_index
+    }
+
+    #[must_use]
+    pub fn courses_index(&self) -> &str {
+        &self.courses_index
+    }
+
+    /// For managing the indices.
+    #[must_use]
+    pub const fn client(&self) -> &OpenSearch {
+        &self.client
+    }
+
+    #[tracing::instrument(skip_all, fields(index = %index))]
+    async fn search(&self, index: &str, body: Value) -> Result<Value, MyError> {
+        let response = self
+            .client
+            .search(SearchParts::Index(&[index]))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status_code()?;
+        Ok(response.json::<Value>().await?)
+    }
+
+    #[tracing::instrument(skip_all, fields(index = %index))]
+    async fn bulk_index(
+        &self,
+        index: &str,
+        documents: impl Iterator<Item = (String, Value)> + Send,
+    ) -> Result<(), MyError> {
+        let body: Vec<JsonBody<_>> = documents
+            .flat_map(|(id, document)| [json!({"index": {"_id": id}}).into(), document.into()])
+            .collect();
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .bulk(BulkParts::Index(index))
+            .refresh(Refresh::WaitFor)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status_code()?;
+
+        // the request succeeds even if single documents were rejected
+        let response_body = response.json::<Value>().await?;
+        if response_body["errors"].as_bool() == Some(true) {
+            let failed = response_body["items"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find_map(|item| item["index"].get("error"));
+            return Err(MyError::UpstreamUnavailable(anyhow::anyhow!(
+                "indexing into {index} failed: {failed:?}"
+            )));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(index = %index))]
+    async fn delete(&self, index: &str, id: &str) -> Result<(), MyError> {
+        let response = self
+            .client
+            .delete(DeleteParts::IndexId(index, id))
+            .refresh(Refresh::WaitFor)
+            .send()
+            .await?;
+        if response.status_code().as_u16() == 404 {
+            return Ok(());
+        }
+        response.error_for_status_code()?;
+        Ok(())
+    }
+}
+
+#[axum::async_trait]
+impl SearchBackend for OpensearchSearch {
+    async fn index_modules(&self, modules: &[ModuleDocument]) -> Result<(), MyError> {
+        self.bulk_index(
+            &self.modules_index,
+            modules.iter().map(|document| {
+                (
+                    document.module.tucan_id.to_base64(),
+                    document.to_opensearch(),
+                )
+            }),
+        )
+        .await
+    }
+
+    async fn delete_module(&self, module: &ModuleId) -> Result<(), MyError> {
+        self.delete(&self.modules_index, &module.to_base64()).await
+    }
+
+    async fn index_courses(&self, courses: &[CourseDocument]) -> Result<(), MyError> {
+        self.bulk_index(
+            &self.courses_index,
+            courses.iter().map(|document| {
+                (
+                    document.course.tucan_id.to_base64(),
+                    document.to_opensearch(),
+                )
+            }),
+        )
+        .await
+    }
+
+    async fn delete_course(&self, course: &CourseId) -> Result<(), MyError> {
+        self.delete(&self.courses_index, &course.to_base64()).await
+    }
+
+    async fn search_modules(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<ModuleId>, MyError> {
+        let response_body = self
+            .search(&self.modules_index, modules_opensearch_body(search)?)
+            .await?;
+        modules_opensearch_response(search, &response_body, language)
+    }
+
+    async fn search_courses(
+        &self,
+        search: &SearchQuery,
+        language: Language,
+    ) -> Result<SearchResponse<CourseId>, MyError> {
+        let response_body = self
+            .search(&self.courses_index, courses_opensearch_body(search)?)
+            .await?;
+        courses_opensearch_response(search, &response_body, language)
+    }
+
+    async fn search_unified(
+        &self,
+        search: &UnifiedSearchQuery,
+    ) -> Result<UnifiedSearchResponse, MyError> {
+        self.postgres.search_unified(search).await
+    }
+
+    async fn suggest(&self, input: &str, language: Language) -> Result<Vec<Suggestion>, MyError> {
+        let response_body = self
+            .search(&self.modules_index, modules_suggest_opensearch_body(input))
+            .await?;
+        modules_suggest_opensearch_response(&response_body, language)
+    }
+
+    async fn similar_modules(
+        &self,
+        similar: &SimilarModules,
+        language: Language,
+    ) -> Result<Vec<SearchHit<ModuleId>>, MyError> {
+        let response_body = self
+            .search(
+                &self.modules_index,
+                similar_modules_opensearch_body(similar),
+            )
+            .await?;
+        similar_modules_opensearch_response(&response_body, language)
+    }
+
+    /// Fails if the indices are missing or not all of their primary shards are assigned.
+    async fn ping(&self) -> Result<(), MyError> {
+        let response = self
+            .client
+            .cluster()
+            .health(ClusterHealthParts::Index(&[
+                &self.modules_index,
+                &self.courses_index,
+            ]))
+            .send()
+            .await?
+            .error_for_status_code()?
+            .json::<Value>()
+            .await?;
+        if response["status"] == "red" {
+            return Err(MyError::UpstreamUnavailable(anyhow::anyhow!(
+                "the OpenSearch indices are red"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::{lecturer_names, suggest_inputs, with_ancestors, CourseDocument, ModuleDocument};
+    use crate::ids::{CourseId, MenuPath, ModuleId};
+    use crate::models::{Course, Module};
+
+    #[test]
+    fn test_suggest_inputs() {
+        assert_eq!(
+            suggest_inputs(
+                "20-00-0005",
+                &["Funktionale und objektorientierte", "", "20-00-0005"]
+            ),
+            vec![
+                "20-00-0005",
+                "Funktionale und objektorientierte",
+                "und objektorientierte",
+                "objektorientierte"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lecturer_names() {
+        let columns = [
+            "Prof. Dr. Karsten Weihe; Dr. Who ".to_string(),
+            "Dr. Who".to_string(),
+            " ; ".to_string(),
+        ];
+        assert_eq!(
+            lecturer_names(&columns),
+            vec!["Dr. Who", "Prof. Dr. Karsten Weihe"]
+        );
+    }
+
+    #[test]
+    fn test_with_ancestors() {
+        let root = MenuPath::new([1, 0, 0]);
+        let child = MenuPath::new([1, 2, 0]);
+        let leaf = MenuPath::new([1, 2, 3]);
+        let parents = HashMap::from([
+            (root.clone(), None),
+            (child.clone(), Some(root.clone())),
+            (leaf.clone(), Some(child.clone())),
+        ]);
+        assert_eq!(
+            with_ancestors([&leaf, &child], &parents),
+            vec![root, child, leaf]
+        );
+    }
+
+    #[test]
+    fn test_documents() {
+        let checked = NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let module = ModuleDocument {
+            module: Module {
+                tucan_id: ModuleId::from_bytes(vec![1; 8]).unwrap(),
+                tucan_last_checked: checked,
+                title: "Analysis".to_string(),
+                module_id: "04-00-0109".to_string(),
+                credits: Some(9),
+                content: "Folgen".to_string(),
+                done: true,
+                title_en: String::new(),
+                content_en: String::new(),
+            },
+            semesters: vec!["WiSe 2022/23".to_string()],
+            menus: Vec::new(),
+            lecturers: vec!["Dr. Who".to_string()],
+        }
+        .to_opensearch();
+        assert_eq!(module["title"]["en"], serde_json::Value::Null);
+        assert_eq!(module["credits"], 9);
+        assert_eq!(module["semesters"][0], "WiSe 2022/23");
+        assert_eq!(module["suggest"][1], "Analysis");
+        assert_eq!(module["lecturers"][0], "Dr. Who");
+
+        let course = CourseDocument {
+            course: Course {
+                tucan_id: CourseId::new(1, 0),
+                tucan_last_checked: checked,
+                title: "Analysis".to_string(),
+                course_id: "04-00-0109-vu".to_string(),
+                sws: 6,
+                content: String::new(),
+                done: true,
+                title_en: "Calculus".to_string(),
+                content_en: String::new(),
+            },
+            semesters: Vec::new(),
+            menus: vec![MenuPath::new([1, 2, 3])],
+            lecturers: Vec::new(),
+        }
+        .to_opensearch();
+        assert_eq!(course["title"]["en"], "Calculus");
+        assert_eq!(course["sws"], 6);
+        assert_eq!(course["menus"].as_array().unwrap().len(), 1);
+    }
+}
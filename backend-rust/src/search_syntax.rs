@@ -0,0 +1,375 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! The query language of the module and course search, e.g.
+//! `credits:>=6 lecturer:Weinmann sws:4 "software engineering" -seminar`.
+//!
+//! Words, `"quoted phrases"` and `-excluded` words or phrases are searched in the text,
+//! `field:value` pairs filter. Values with spaces can be quoted, numbers can be compared
+//! with `>=`, `<=`, `>`, `<` or `=`.
+
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde::Serialize;
+#[cfg(feature = "server")]
+use tucant_derive::Typescriptable;
+
+use crate::models::Language;
+
+/// Why a query couldn't be parsed, `position` counts the characters before the problem.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "server", derive(Typescriptable))]
+pub struct QueryError {
+    pub message: String,
+    pub position: u32,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position: u32::try_from(position).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Splits the comparison off a value like `>=6`, without one the value has to be equal.
+    fn split(value: &str) -> (Self, &str) {
+        [
+            (">=", Self::Ge),
+            ("<=", Self::Le),
+            (">", Self::Gt),
+            ("<", Self::Lt),
+            ("=", Self::Eq),
+        ]
+        .into_iter()
+        .find_map(|(prefix, comparison)| {
+            value
+                .strip_prefix(prefix)
+                .map(|number| (comparison, number))
+        })
+        .unwrap_or((Self::Eq, value))
+    }
+
+    /// The parameter of an OpenSearch `range` query, `None` for [`Comparison::Eq`].
+    #[must_use]
+    pub const fn opensearch_range(self) -> Option<&'static str> {
+        match self {
+            Self::Eq => None,
+            Self::Lt => Some("lt"),
+            Self::Le => Some("lte"),
+            Self::Gt => Some("gt"),
+            Self::Ge => Some("gte"),
+        }
+    }
+}
+
+/// A word or phrase that has to be in the text, or must not be if it is `negated`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Term {
+    pub text: String,
+    pub phrase: bool,
+    pub negated: bool,
+}
+
+/// Filters that don't apply to what is searched (like `sws` for modules) are ignored.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FieldFilter {
+    Credits(Comparison, i32),
+    Sws(Comparison, i16),
+    /// Part of the name of an examiner or of someone teaching an event.
+    Lecturer(String),
+    /// Only matches with an exam in this semester, spelled like TUCaN does, e.g. `WiSe 2022/23`.
+    Semester(String),
+    /// `en` only matches what has an english translation.
+    Language(Language),
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ParsedQuery {
+    pub terms: Vec<Term>,
+    pub filters: Vec<FieldFilter>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Returns whether there is anything left after the whitespace.
+    fn skip_whitespace(&mut self) -> bool {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.position += 1;
+        }
+        self.peek().is_some()
+    }
+
+    /// Reads up to the next whitespace or quote, and colon if `field` is set.
+    fn word(&mut self, field: bool) -> String {
+        let start = self.position;
+        while self.peek().map_or(false, |c| {
+            !c.is_whitespace() && c != '"' && !(field && c == ':')
+        }) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// Reads the text up to the closing quote, the opening one is at the current position.
+    fn quoted(&mut self) -> Result<String, QueryError> {
+        let start = self.position;
+        self.position += 1;
+        let Some(length) = self.chars[self.position..].iter().position(|c| *c == '"') else {
+            return Err(QueryError::new("unterminated quote", start));
+        };
+        let text = self.chars[self.position..self.position + length]
+            .iter()
+            .collect();
+        self.position += length + 1;
+        Ok(text)
+    }
+
+    fn value(&mut self) -> Result<String, QueryError> {
+        if self.peek() == Some('"') {
+            self.quoted()
+        } else {
+            Ok(self.word(false))
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(
+    value: &str,
+    position: usize,
+) -> Result<(Comparison, T), QueryError> {
+    let (comparison, number) = Comparison::split(value);
+    let number_position = position + value.chars().count() - number.chars().count();
+    number
+        .parse()
+        .map(|number| (comparison, number))
+        .map_err(|_| QueryError::new("expected a whole number", number_position))
+}
+
+const FIELDS: [&str; 7] = [
+    "credits", "cp", "sws", "lecturer", "semester", "language", "lang",
+];
+
+/// `field_position` and `position` are where the field and the value start.
+fn field_filter(
+    field: &str,
+    field_position: usize,
+    value: String,
+    position: usize,
+) -> Result<FieldFilter, QueryError> {
+    Ok(match field.to_lowercase().as_str() {
+        "credits" | "cp" => {
+            let (comparison, credits) = number(&value, position)?;
+            FieldFilter::Credits(comparison, credits)
+        }
+        "sws" => {
+            let (comparison, sws) = number(&value, position)?;
+            FieldFilter::Sws(comparison, sws)
+        }
+        "lecturer" => FieldFilter::Lecturer(value),
+        "semester" => FieldFilter::Semester(value),
+        "language" | "lang" => {
+            FieldFilter::Language(Language::from_code(&value).ok_or_else(|| {
+                QueryError::new(format!("unsupported language {value}"), position)
+            })?)
+        }
+        _ => {
+            return Err(QueryError::new(
+                format!(
+                    "unknown field {field}, expected one of {}",
+                    FIELDS.iter().join(", ")
+                ),
+                field_position,
+            ))
+        }
+    })
+}
+
+impl ParsedQuery {
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            position: 0,
+        };
+        let mut parsed = Self::default();
+
+        while parser.skip_whitespace() {
+            let negated = parser.eat('-');
+            let start = parser.position;
+            if parser.peek() == Some('"') {
+                let text = parser.quoted()?;
+                if !text.trim().is_empty() {
+                    parsed.terms.push(Term {
+                        text,
+                        phrase: true,
+                        negated,
+                    });
+                }
+                continue;
+            }
+
+            let word = parser.word(true);
+            if !parser.eat(':') {
+                if !word.is_empty() {
+                    parsed.terms.push(Term {
+                        text: word,
+                        phrase: false,
+                        negated,
+                    });
+                }
+                continue;
+            }
+
+            if negated {
+                return Err(QueryError::new("filters can't be negated", start - 1));
+            }
+            let value_position = parser.position;
+            let value = parser.value()?;
+            if value.trim().is_empty() {
+                return Err(QueryError::new(
+                    format!("expected a value for {word}"),
+                    value_position,
+                ));
+            }
+            parsed
+                .filters
+                .push(field_filter(&word, start, value, value_position)?);
+        }
+        Ok(parsed)
+    }
+
+    /// Whether there is neither text to search for nor a filter.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.filters.is_empty()
+    }
+
+    /// The terms in the syntax of Postgres' `websearch_to_tsquery`.
+    #[must_use]
+    pub fn websearch(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| {
+                format!(
+                    "{}{}",
+                    if term.negated { "-" } else { "" },
+                    if term.phrase {
+                        format!("\"{}\"", term.text)
+                    } else {
+                        term.text.clone()
+                    }
+                )
+            })
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, FieldFilter, ParsedQuery, QueryError, Term};
+    use crate::models::Language;
+
+    fn term(text: &str, phrase: bool, negated: bool) -> Term {
+        Term {
+            text: text.to_string(),
+            phrase,
+            negated,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = ParsedQuery::parse(
+            r#"credits:>=6 lecturer:Weinmann sws:4 "software engineering" -seminar"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.filters,
+            vec![
+                FieldFilter::Credits(Comparison::Ge, 6),
+                FieldFilter::Lecturer("Weinmann".to_string()),
+                FieldFilter::Sws(Comparison::Eq, 4),
+            ]
+        );
+        assert_eq!(
+            parsed.terms,
+            vec![
+                term("software engineering", true, false),
+                term("seminar", false, true),
+            ]
+        );
+        assert_eq!(parsed.websearch(), r#""software engineering" -seminar"#);
+
+        let parsed =
+            ParsedQuery::parse(r#"  semester:"WiSe 2022/23" LANG:en 20-00-0005 -"a b" "" - "#)
+                .unwrap();
+        assert_eq!(
+            parsed.filters,
+            vec![
+                FieldFilter::Semester("WiSe 2022/23".to_string()),
+                FieldFilter::Language(Language::English),
+            ]
+        );
+        assert_eq!(parsed.websearch(), r#"20-00-0005 -"a b""#);
+
+        assert!(ParsedQuery::parse(" ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |query: &str| ParsedQuery::parse(query).unwrap_err();
+        assert_eq!(
+            error("analysis credits:>=six"),
+            QueryError {
+                message: "expected a whole number".to_string(),
+                position: 19,
+            }
+        );
+        assert_eq!(error(r#"über "graphen"#).position, 5);
+        assert_eq!(error("a raum:S101").position, 2);
+        assert_eq!(error("a -sws:2").position, 2);
+        assert_eq!(error("sws:").position, 4);
+        assert_eq!(error("lang:fr").message, "unsupported language fr");
+        assert_eq!(
+            error("credits:<4.5").to_string(),
+            "expected a whole number at position 9"
+        );
+    }
+}
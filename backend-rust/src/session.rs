@@ -0,0 +1,437 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Logins to tucant. The session cookie only contains a random id, `sessions` stores its SHA-256
+//! hash next to the [`TucanSession`] so a leaked database doesn't contain usable cookies. Sessions
+//! expire if they aren't used for [`Config::session_lifetime_hours`].
+//!
+//! The cookie is encrypted with the key in [`Config::session_key_file`], cookies encrypted with
+//! one of the [`Config::previous_session_key_files`] are still accepted and [`reissue_cookie`]
+//! encrypts them with the current key, so the key can be rotated.
+
+use std::io::Write;
+
+use anyhow::Context;
+use axum::extract::{FromRef, FromRequestParts, State};
+use axum::http::header::SET_COOKIE;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::{Cookie, Key};
+use axum_extra::extract::PrivateCookieJar;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::RunQueryDsl;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::models::{SessionInfo, TucanSession};
+use crate::schema::sessions;
+use crate::telemetry::Traced;
+use crate::tucan::{DbPool, Tucan};
+use crate::MyError;
+
+pub const SESSION_COOKIE: &str = "session";
+
+#[derive(Clone)]
+pub struct SessionKeys {
+    /// New cookies are encrypted with this key.
+    pub current: Key,
+    pub previous: Vec<Key>,
+}
+
+fn read_key(path: &std::path::Path) -> anyhow::Result<Key> {
+    let key = std::fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+    Key::try_from(key.as_slice()).with_context(|| format!("{path:?} is not a valid key"))
+}
+
+impl SessionKeys {
+    /// Reads the keys, the current one is generated if its file doesn't exist yet.
+    pub fn load(config: &Config) -> anyhow::Result<Self> {
+        if let Ok(mut file) = std::fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&config.session_key_file)
+        {
+            file.write_all(Key::generate().master())?;
+        }
+
+        Ok(Self {
+            current: read_key(&config.session_key_file)?,
+            previous: config
+                .previous_session_key_files
+                .iter()
+                .map(|path| read_key(path))
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    /// The session id in the cookie, if it was encrypted with any of the keys.
+    #[must_use]
+    pub fn session_id(&self, headers: &HeaderMap) -> Option<String> {
+        self.decrypt(headers).map(|(id, _)| id)
+    }
+
+    /// The session id in the cookie and whether it was encrypted with the current key.
+    fn decrypt(&self, headers: &HeaderMap) -> Option<(String, bool)> {
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .enumerate()
+            .find_map(|(index, key)| {
+                PrivateCookieJar::from_headers(headers, key.clone())
+                    .get(SESSION_COOKIE)
+                    .map(|cookie| (cookie.value().to_string(), index == 0))
+            })
+    }
+}
+
+/// Sets the session cookie again, encrypted with the current key, if the request's was encrypted
+/// with one of the previous keys. Responses that set the session cookie themselves, like logging
+/// in or out, are left alone.
+pub async fn reissue_cookie<B>(
+    State(keys): State<SessionKeys>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let outdated = keys
+        .decrypt(request.headers())
+        .and_then(|(id, current)| (!current).then_some(id));
+    let response = next.run(request).await;
+    let Some(id) = outdated else {
+        return response;
+    };
+    let sets_cookie = response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.starts_with(&format!("{SESSION_COOKIE}=")));
+    if sets_cookie {
+        response
+    } else {
+        (
+            PrivateCookieJar::new(keys.current).add(cookie(id)),
+            response,
+        )
+            .into_response()
+    }
+}
+
+/// The cookie for a session created by [`create`], it has to be added to a [`PrivateCookieJar`].
+#[must_use]
+pub fn cookie(id: String) -> Cookie<'static> {
+    let mut cookie = Cookie::new(SESSION_COOKIE, id);
+    cookie.set_http_only(true);
+    cookie
+}
+
+/// What `sessions.id` stores for the id in the cookie.
+fn hash(id: &str) -> String {
+    format!("{:x}", Sha256::digest(id.as_bytes()))
+}
+
+fn lifetime(config: &Config) -> Duration {
+    Duration::hours(i64::from(config.session_lifetime_hours))
+}
+
+/// Stores a new session for `session` and returns its id for the cookie.
+pub async fn create(
+    pool: &DbPool,
+    config: &Config,
+    session: &TucanSession,
+    user_agent: Option<&str>,
+) -> Result<String, MyError> {
+    let mut connection = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    let id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    diesel::delete(sessions::table)
+        .filter(sessions::expires.le(now))
+        .execute(&mut connection)
+        .traced()
+        .await?;
+
+    diesel::insert_into(sessions::table)
+        .values((
+            sessions::id.eq(hash(&id)),
+            sessions::matriculation_number.eq(session.matriculation_number),
+            sessions::session_nr.eq(session.session_nr),
+            sessions::session_id.eq(&session.session_id),
+            sessions::created.eq(now),
+            sessions::last_used.eq(now),
+            sessions::expires.eq(now + lifetime(config)),
+            sessions::user_agent.eq(user_agent),
+        ))
+        .execute(&mut connection)
+        .traced()
+        .await?;
+    Ok(id)
+}
+
+/// The [`TucanSession`] of an unexpired session, using it extends its expiry.
+pub async fn resolve(
+    pool: &DbPool,
+    config: &Config,
+    id: &str,
+) -> Result<Option<TucanSession>, MyError> {
+    let mut connection = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    Ok(diesel::update(
+        sessions::table
+            .filter(sessions::id.eq(hash(id)))
+            .filter(sessions::expires.gt(now)),
+    )
+    .set((
+        sessions::last_used.eq(now),
+        sessions::expires.eq(now + lifetime(config)),
+    ))
+    .returning((
+        sessions::matriculation_number,
+        sessions::session_nr,
+        sessions::session_id,
+    ))
+    .get_result::<TucanSession>(&mut connection)
+    .traced()
+    .await
+    .optional()?)
+}
+
+/// The unexpired sessions of a user, the most recently used first.
+pub async fn list(
+    pool: &DbPool,
+    matriculation_number: i32,
+    current_id: &str,
+) -> Result<Vec<SessionInfo>, MyError> {
+    let mut connection = pool.get().await?;
+    let current_id = hash(current_id);
+    let rows = sessions::table
+        .filter(sessions::matriculation_number.eq(matriculation_number))
+        .filter(sessions::expires.gt(Utc::now().naive_utc()))
+        .order_by(sessions::last_used.desc())
+        .select((
+            sessions::id,
+            sessions::handle,
+            sessions::created,
+            sessions::last_used,
+            sessions::expires,
+            sessions::user_agent,
+        ))
+        .load::<(
+            String,
+            i64,
+            NaiveDateTime,
+            NaiveDateTime,
+            NaiveDateTime,
+            Option<String>,
+        )>(&mut connection)
+        .traced()
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, handle, created, last_used, expires, user_agent)| SessionInfo {
+                handle,
+                created,
+                last_used,
+                expires,
+                user_agent,
+                current: id == current_id,
+            },
+        )
+        .collect())
+}
+
+/// Deletes the session with this id and returns its [`TucanSession`] to log out at TUCaN.
+pub async fn delete(pool: &DbPool, id: &str) -> Result<Option<TucanSession>, MyError> {
+    let mut connection = pool.get().await?;
+    Ok(
+        diesel::delete(sessions::table.filter(sessions::id.eq(hash(id))))
+            .returning((
+                sessions::matriculation_number,
+                sessions::session_nr,
+                sessions::session_id,
+            ))
+            .get_result::<TucanSession>(&mut connection)
+            .traced()
+            .await
+            .optional()?,
+    )
+}
+
+/// Like [`delete`] but by the handle, only sessions of the user can be revoked.
+pub async fn revoke(
+    pool: &DbPool,
+    matriculation_number: i32,
+    handle: i64,
+) -> Result<TucanSession, MyError> {
+    let mut connection = pool.get().await?;
+    Ok(diesel::delete(
+        sessions::table
+            .filter(sessions::matriculation_number.eq(matriculation_number))
+            .filter(sessions::handle.eq(handle)),
+    )
+    .returning((
+        sessions::matriculation_number,
+        sessions::session_nr,
+        sessions::session_id,
+    ))
+    .get_result::<TucanSession>(&mut connection)
+    .traced()
+    .await?)
+}
+
+/// The id in the session cookie, without checking whether the session exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionId(pub String);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for SessionId
+where
+    SessionKeys: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = MyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        SessionKeys::from_ref(state)
+            .session_id(&parts.headers)
+            .map(Self)
+            .ok_or(MyError::Unauthorized)
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for TucanSession
+where
+    SessionKeys: FromRef<S>,
+    Tucan: FromRef<S>,
+    Config: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = MyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let SessionId(id) = SessionId::from_request_parts(parts, state).await?;
+        // cookies from before sessions were stored contain no known id and have to log in again
+        resolve(&Tucan::from_ref(state).pool, &Config::from_ref(state), &id)
+            .await?
+            .ok_or(MyError::SessionExpired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::{COOKIE, SET_COOKIE};
+    use axum::http::{HeaderMap, HeaderValue, Request};
+    use axum::middleware::from_fn_with_state;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use axum_extra::extract::cookie::Key;
+    use axum_extra::extract::PrivateCookieJar;
+    use tower::ServiceExt;
+
+    use super::{cookie, hash, reissue_cookie, SessionKeys};
+
+    /// The request headers with the cookie a response set, as a browser would send it.
+    fn sent_cookie(response: &Response) -> HeaderMap {
+        let set_cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+        let encrypted = set_cookie.split(';').next().unwrap();
+        HeaderMap::from_iter([(COOKIE, HeaderValue::from_str(encrypted).unwrap())])
+    }
+
+    /// The request headers with the session cookie encrypted with `key`.
+    fn headers(key: &Key, id: &str) -> HeaderMap {
+        sent_cookie(
+            &PrivateCookieJar::new(key.clone())
+                .add(cookie(id.to_string()))
+                .into_response(),
+        )
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = Key::generate();
+        let keys = SessionKeys {
+            current: Key::generate(),
+            previous: vec![old.clone()],
+        };
+
+        assert_eq!(
+            keys.session_id(&headers(&keys.current, "abc")).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            keys.session_id(&headers(&old, "def")).as_deref(),
+            Some("def")
+        );
+        assert_eq!(keys.session_id(&headers(&Key::generate(), "ghi")), None);
+        assert_eq!(
+            keys.session_id(&HeaderMap::from_iter([(
+                COOKIE,
+                HeaderValue::from_static("session=abc")
+            )])),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reissue_cookie() {
+        let old = Key::generate();
+        let keys = SessionKeys {
+            current: Key::generate(),
+            previous: vec![old.clone()],
+        };
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .route(
+                "/logout",
+                get(|| async { [(SET_COOKIE, "session=; Max-Age=0")] }),
+            )
+            .layer(from_fn_with_state(keys.clone(), reissue_cookie));
+        let request = |uri: &str, key: &Key| {
+            let mut request = Request::get(uri).body(Body::empty()).unwrap();
+            *request.headers_mut() = headers(key, "abc");
+            request
+        };
+
+        let response = app.clone().oneshot(request("/", &old)).await.unwrap();
+        assert_eq!(
+            keys.decrypt(&sent_cookie(&response)),
+            Some(("abc".to_string(), true))
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request("/", &keys.current))
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(SET_COOKIE));
+
+        let response = app.oneshot(request("/logout", &old)).await.unwrap();
+        assert_eq!(
+            response.headers().get_all(SET_COOKIE).iter().count(),
+            1,
+            "the logout must not be undone"
+        );
+    }
+
+    #[test]
+    fn test_hash() {
+        // the same as the migration computes in SQL
+        assert_eq!(
+            hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(hash("abc"), hash("abd"));
+    }
+}
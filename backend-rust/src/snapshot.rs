@@ -0,0 +1,322 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Snapshots of the public catalog so new deployments and tests don't need to crawl TUCaN first.
+//!
+//! A snapshot is gzip compressed JSON. It contains the module menus, modules, courses,
+//! course groups, their events and exams but nothing from the user tables.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::ids::{MenuPath, ModuleId};
+use crate::models::{
+    Course, CourseEvent, CourseExam, CourseGroup, CourseGroupEvent, Exam, Module, ModuleCourse,
+    ModuleExam, ModuleMenu, ModuleMenuEntryModule, COURSES_UNFINISHED, MODULES_UNFINISHED,
+};
+use crate::schema::{
+    course_events, course_exams, course_groups_events, course_groups_unfinished,
+    courses_unfinished, exams_unfinished, module_courses, module_exams, module_menu_module,
+    module_menu_unfinished, modules_unfinished,
+};
+use crate::telemetry::Traced;
+
+/// Increase this whenever the format changes, old snapshots are rejected instead of half imported.
+pub const VERSION: u32 = 1;
+
+/// Postgres allows at most 65535 bind parameters per statement.
+const CHUNK_SIZE: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub version: u32,
+    pub module_menus: Vec<ModuleMenu>,
+    pub modules: Vec<Module>,
+    pub module_menu_modules: Vec<ModuleMenuEntryModule>,
+    pub courses: Vec<Course>,
+    pub module_courses: Vec<ModuleCourse>,
+    pub course_groups: Vec<CourseGroup>,
+    pub course_events: Vec<CourseEvent>,
+    pub course_group_events: Vec<CourseGroupEvent>,
+    pub exams: Vec<Exam>,
+    pub module_exams: Vec<ModuleExam>,
+    pub course_exams: Vec<CourseExam>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+macro_rules! insert_chunked {
+    ($connection:expr, $table:expr, $rows:expr) => {
+        for chunk in $rows.chunks(CHUNK_SIZE) {
+            diesel::insert_into($table)
+                .values(chunk)
+                // the migrations already insert the module for courses without a module
+                .on_conflict_do_nothing()
+                .execute($connection)
+                .traced()
+                .await?;
+        }
+    };
+}
+
+/// Menus reference their parent so parents have to be inserted first. Fails if a menu is its own
+/// ancestor.
+fn sort_parents_first(menus: &mut [ModuleMenu]) -> anyhow::Result<()> {
+    let parents: HashMap<MenuPath, Option<MenuPath>> = menus
+        .iter()
+        .map(|menu| (menu.tucan_id.clone(), menu.parent.clone()))
+        .collect();
+
+    let mut depths = HashMap::new();
+    for menu in menus.iter() {
+        let mut ancestors = HashSet::new();
+        let mut parent = menu.parent.as_ref();
+        while let Some(current) = parent {
+            anyhow::ensure!(
+                ancestors.insert(current),
+                "the module menu {current} is its own ancestor"
+            );
+            parent = parents.get(current).and_then(Option::as_ref);
+        }
+        depths.insert(menu.tucan_id.clone(), ancestors.len());
+    }
+
+    menus.sort_by_cached_key(|menu| depths[&menu.tucan_id]);
+    Ok(())
+}
+
+impl Snapshot {
+    pub async fn export(connection: &mut AsyncPgConnection) -> anyhow::Result<Self> {
+        Ok(Self {
+            version: VERSION,
+            module_menus: module_menu_unfinished::table
+                .load::<ModuleMenu>(connection)
+                .traced()
+                .await?,
+            modules: modules_unfinished::table
+                .select(MODULES_UNFINISHED)
+                .load::<Module>(connection)
+                .traced()
+                .await?,
+            module_menu_modules: module_menu_module::table
+                .load::<ModuleMenuEntryModule>(connection)
+                .traced()
+                .await?,
+            courses: courses_unfinished::table
+                .select(COURSES_UNFINISHED)
+                .load::<Course>(connection)
+                .traced()
+                .await?,
+            module_courses: module_courses::table
+                .load::<ModuleCourse>(connection)
+                .traced()
+                .await?,
+            course_groups: course_groups_unfinished::table
+                .load::<CourseGroup>(connection)
+                .traced()
+                .await?,
+            course_events: course_events::table
+                .load::<CourseEvent>(connection)
+                .traced()
+                .await?,
+            course_group_events: course_groups_events::table
+                .load::<CourseGroupEvent>(connection)
+                .traced()
+                .await?,
+            exams: exams_unfinished::table
+                .load::<Exam>(connection)
+                .traced()
+                .await?,
+            module_exams: module_exams::table
+                .load::<ModuleExam>(connection)
+                .traced()
+                .await?,
+            course_exams: course_exams::table
+                .load::<CourseExam>(connection)
+                .traced()
+                .await?,
+        })
+    }
+
+    /// Loads the snapshot into a database that contains no catalog data yet.
+    pub async fn import(mut self, connection: &mut AsyncPgConnection) -> anyhow::Result<()> {
+        let modules = modules_unfinished::table
+            .filter(modules_unfinished::tucan_id.ne(ModuleId::tucanscheiss()))
+            .count()
+            .get_result::<i64>(connection)
+            .traced()
+            .await?;
+        let module_menus = module_menu_unfinished::table
+            .count()
+            .get_result::<i64>(connection)
+            .traced()
+            .await?;
+        let courses = courses_unfinished::table
+            .count()
+            .get_result::<i64>(connection)
+            .traced()
+            .await?;
+        let exams = exams_unfinished::table
+            .count()
+            .get_result::<i64>(connection)
+            .traced()
+            .await?;
+        anyhow::ensure!(
+            modules + module_menus + courses + exams == 0,
+            "the database already contains catalog data, snapshots can only be imported into an empty database"
+        );
+
+        sort_parents_first(&mut self.module_menus)?;
+
+        connection
+            .build_transaction()
+            .run(|mut connection| {
+                Box::pin(async move {
+                    insert_chunked!(
+                        &mut connection,
+                        module_menu_unfinished::table,
+                        self.module_menus
+                    );
+                    insert_chunked!(&mut connection, modules_unfinished::table, self.modules);
+                    insert_chunked!(
+                        &mut connection,
+                        module_menu_module::table,
+                        self.module_menu_modules
+                    );
+                    insert_chunked!(&mut connection, courses_unfinished::table, self.courses);
+                    insert_chunked!(&mut connection, module_courses::table, self.module_courses);
+                    insert_chunked!(
+                        &mut connection,
+                        course_groups_unfinished::table,
+                        self.course_groups
+                    );
+                    insert_chunked!(&mut connection, course_events::table, self.course_events);
+                    insert_chunked!(
+                        &mut connection,
+                        course_groups_events::table,
+                        self.course_group_events
+                    );
+                    insert_chunked!(&mut connection, exams_unfinished::table, self.exams);
+                    insert_chunked!(&mut connection, module_exams::table, self.module_exams);
+                    insert_chunked!(&mut connection, course_exams::table, self.course_exams);
+
+                    Ok::<(), diesel::result::Error>(())
+                })
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub fn write(&self, writer: impl Write) -> anyhow::Result<()> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    pub fn read(reader: impl Read) -> anyhow::Result<Self> {
+        let mut json = Vec::new();
+        GzDecoder::new(reader).read_to_end(&mut json)?;
+
+        let Header { version } = serde_json::from_slice(&json)?;
+        anyhow::ensure!(
+            version == VERSION,
+            "the snapshot has version {version} but only version {VERSION} is supported"
+        );
+
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{sort_parents_first, Snapshot, VERSION};
+    use crate::ids::{MenuPath, ModuleId};
+    use crate::models::{Module, ModuleMenu};
+
+    fn menu(id: u64, parent: Option<u64>) -> ModuleMenu {
+        ModuleMenu {
+            tucan_id: MenuPath::new([id, 0, 0]),
+            tucan_last_checked: NaiveDate::from_ymd_opt(2023, 1, 20)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            name: format!("menu {id}"),
+            done: true,
+            parent: parent.map(|parent| MenuPath::new([parent, 0, 0])),
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            version: VERSION,
+            module_menus: vec![menu(1, None), menu(2, Some(1))],
+            modules: vec![Module {
+                tucan_id: ModuleId::new(42),
+                tucan_last_checked: menu(1, None).tucan_last_checked,
+                title: "Einführung in den Compilerbau".to_string(),
+                module_id: "20-00-0004".to_string(),
+                credits: Some(5),
+                content: String::new(),
+                done: true,
+                title_en: "Introduction to Compiler Construction".to_string(),
+                content_en: String::new(),
+            }],
+            module_menu_modules: Vec::new(),
+            courses: Vec::new(),
+            module_courses: Vec::new(),
+            course_groups: Vec::new(),
+            course_events: Vec::new(),
+            course_group_events: Vec::new(),
+            exams: Vec::new(),
+            module_exams: Vec::new(),
+            course_exams: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut file = Vec::new();
+        snapshot().write(&mut file).unwrap();
+        let read = Snapshot::read(file.as_slice()).unwrap();
+        assert_eq!(read.module_menus, snapshot().module_menus);
+        assert_eq!(read.modules, snapshot().modules);
+    }
+
+    #[test]
+    fn test_version() {
+        let mut file = Vec::new();
+        Snapshot {
+            version: VERSION + 1,
+            ..snapshot()
+        }
+        .write(&mut file)
+        .unwrap();
+        assert!(Snapshot::read(file.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_sort_parents_first() {
+        let mut menus = vec![menu(3, Some(2)), menu(2, Some(1)), menu(1, None)];
+        sort_parents_first(&mut menus).unwrap();
+        assert_eq!(
+            menus,
+            vec![menu(1, None), menu(2, Some(1)), menu(3, Some(2))]
+        );
+
+        let mut cycle = vec![menu(3, Some(2)), menu(2, Some(1)), menu(1, Some(2))];
+        assert!(sort_parents_first(&mut cycle).is_err());
+    }
+}
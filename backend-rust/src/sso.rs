@@ -0,0 +1,455 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Logging in through single sign-on (TU-ID with Shibboleth) instead of `LOGINCHECK`, used by
+//! [`Tucan::login`](crate::tucan::Tucan::login) if [`Config::sso_start_url`] is set.
+//!
+//! Like a browser this follows redirects and submits the forms of the identity provider: the
+//! login form with the credentials and the forms that only post the SAML messages back and forth,
+//! which browsers submit with JavaScript. Cookies are kept across all domains of the chain.
+//! The credentials are only filled into a login form that posts to [`Config::sso_idp_host`] over
+//! https.
+//!
+//! [`Config::sso_start_url`]: crate::config::Config::sso_start_url
+//! [`Config::sso_idp_host`]: crate::config::Config::sso_idp_host
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::{Client, Response, Url};
+use scraper::{ElementRef, Html, Selector};
+
+use crate::tucan::REQUEST_TIMEOUT;
+use crate::url::{parse_tucan_url, TucanUrl};
+use crate::TucanError;
+
+/// Pages to go through at most, the chain usually takes five.
+const MAX_STEPS: usize = 10;
+
+/// The cookie with the CampusNet session id.
+const SESSION_COOKIE: &str = "cnsc";
+
+/// A form as it would be submitted by clicking its first submit button.
+#[derive(Debug, PartialEq, Eq)]
+struct Form {
+    action: Url,
+    post: bool,
+    fields: Vec<(String, String)>,
+    /// The name of the text input next to the password input, if this is a login form.
+    username_field: Option<String>,
+    password_field: Option<String>,
+}
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).unwrap()
+}
+
+fn attr<'a>(element: ElementRef<'a>, name: &str) -> &'a str {
+    element.value().attr(name).unwrap_or_default()
+}
+
+fn parse_form(form: ElementRef, url: &Url) -> anyhow::Result<Form> {
+    let mut fields = Vec::new();
+    let mut username_field = None;
+    let mut password_field = None;
+    let mut submitted = false;
+    for input in form.select(&selector(
+        "input[name], button[name], select[name], textarea[name]",
+    )) {
+        let name = attr(input, "name").to_string();
+        let kind = attr(input, "type").to_ascii_lowercase();
+        let value = match (input.value().name(), kind.as_str()) {
+            ("input" | "button", "submit" | "image") | ("button", "") => {
+                if submitted {
+                    continue;
+                }
+                submitted = true;
+                attr(input, "value").to_string()
+            }
+            ("button", _) => continue,
+            ("input", "checkbox" | "radio") if input.value().attr("checked").is_none() => continue,
+            ("input", "password") => {
+                password_field.get_or_insert_with(|| name.clone());
+                attr(input, "value").to_string()
+            }
+            ("input", "" | "text" | "email") => {
+                username_field.get_or_insert_with(|| name.clone());
+                attr(input, "value").to_string()
+            }
+            // a selector list matches in document order, so the selected option is looked up first
+            ("select", _) => input
+                .select(&selector("option[selected]"))
+                .next()
+                .or_else(|| input.select(&selector("option")).next())
+                .map(|option| {
+                    option
+                        .value()
+                        .attr("value")
+                        .map_or_else(|| option.text().collect(), ToString::to_string)
+                })
+                .unwrap_or_default(),
+            ("textarea", _) => input.text().collect(),
+            _ => attr(input, "value").to_string(),
+        };
+        fields.push((name, value));
+    }
+
+    Ok(Form {
+        action: url
+            .join(attr(form, "action"))
+            .context("the form has an invalid action")?,
+        post: attr(form, "method").eq_ignore_ascii_case("post"),
+        fields,
+        username_field: username_field.filter(|_| password_field.is_some()),
+        password_field,
+    })
+}
+
+/// The login form if the page has one, otherwise its first form.
+fn find_form(html: &str, url: &Url) -> anyhow::Result<Option<Form>> {
+    let document = Html::parse_document(html);
+    let forms = document
+        .select(&selector("form"))
+        .map(|form| parse_form(form, url))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let login = forms
+        .iter()
+        .position(|form| form.password_field.is_some())
+        .unwrap_or(0);
+    Ok(forms.into_iter().nth(login))
+}
+
+/// Whether the credentials may be filled into a form that posts to `action`, which has to have
+/// the same scheme, host and port as `idp`.
+fn may_send_credentials(action: &Url, idp: &Url) -> bool {
+    action.origin() == idp.origin()
+}
+
+/// The CampusNet session once the chain arrived at it, from the `refresh` header like the
+/// response of `LOGINCHECK`.
+fn campusnet_session(response: &Response, jar: &Jar) -> anyhow::Result<Option<(i64, String)>> {
+    let Some(refresh) = response.headers().get("refresh") else {
+        return Ok(None);
+    };
+    let refresh = refresh.to_str()?;
+    let target = refresh
+        .split_once("URL=")
+        .map_or(refresh, |(_, target)| target);
+    let target = Url::parse("https://www.tucan.tu-darmstadt.de")?.join(target)?;
+    let TucanUrl {
+        session_nr: Some(session_nr),
+        ..
+    } = parse_tucan_url(target.as_str())?
+    else {
+        return Ok(None);
+    };
+
+    let cookies = jar
+        .cookies(response.url())
+        .context("CampusNet set no session cookie")?;
+    let session_id = cookies
+        .to_str()?
+        .split(';')
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.to_string())
+        .context("CampusNet set no session cookie")?;
+
+    Ok(Some((i64::try_from(session_nr)?, session_id)))
+}
+
+/// Goes from `start_url` through the identity provider at the origin of `idp` to CampusNet.
+///
+/// Returns the session number and id. Fails with [`TucanError::InvalidCredentials`] if the login
+/// form is shown again after submitting it.
+pub async fn login(
+    start_url: &Url,
+    idp: &Url,
+    username: &str,
+    password: &str,
+) -> anyhow::Result<(i64, String)> {
+    let jar = Arc::new(Jar::default());
+    let client = Client::builder()
+        .cookie_provider(jar.clone())
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+
+    let mut response = client.get(start_url.clone()).send().await?;
+    let mut sent_credentials = false;
+    for _ in 0..MAX_STEPS {
+        if let Some(session) = campusnet_session(&response, &jar)? {
+            return Ok(session);
+        }
+
+        let url = response.url().clone();
+        let html = response.error_for_status()?.text().await?;
+        let mut form = find_form(&html, &url)?
+            .with_context(|| format!("{url} is neither a form nor the CampusNet start page"))?;
+
+        if let Some(password_field) = &form.password_field {
+            if sent_credentials {
+                return Err(TucanError::InvalidCredentials.into());
+            }
+            anyhow::ensure!(
+                may_send_credentials(&form.action, idp),
+                "the login form on {url} posts to {} instead of the identity provider {}",
+                form.action.origin().ascii_serialization(),
+                idp.origin().ascii_serialization()
+            );
+            let username_field = form
+                .username_field
+                .as_ref()
+                .context("the login form has no username input")?;
+            for (name, value) in &mut form.fields {
+                if name == username_field {
+                    *value = username.to_string();
+                } else if name == password_field {
+                    *value = password.to_string();
+                }
+            }
+            sent_credentials = true;
+        }
+
+        let request = if form.post {
+            client.post(form.action).form(&form.fields)
+        } else {
+            client.get(form.action).query(&form.fields)
+        };
+        response = request.send().await?;
+    }
+    anyhow::bail!("single sign-on took more than {MAX_STEPS} steps")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    use axum::extract::Query;
+    use axum::http::header::{COOKIE, LOCATION, SET_COOKIE};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::{Html, IntoResponse};
+    use axum::routing::get;
+    use axum::{Form, Router};
+    use reqwest::Url;
+
+    use super::{find_form, login, may_send_credentials};
+    use crate::TucanError;
+
+    type Fields = HashMap<String, String>;
+
+    const SAML_REQUEST: &str = "SAMLRequest=request&RelayState=ss%3Amem%3A1";
+
+    /// Like the answer of `LOGINCHECK`.
+    const START_PAGE: &str = "0; URL=/scripts/mgrqispi.dll?APPNAME=CampusNet\
+        &PRGNAME=STARTPAGE_DISPATCH&ARGUMENTS=-N123456789012345,-N000019,-N000000000000000";
+
+    fn serve(listener: TcpListener, app: Router) {
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+    }
+
+    fn login_page(error: &str) -> Html<String> {
+        Html(format!(
+            r#"<html><body>{error}
+            <form action="/idp/profile/SAML2/Redirect/SSO?execution=e1s1" method="post">
+                <input type="hidden" name="csrf_token" value="csrf">
+                <input type="text" name="j_username">
+                <input type="password" name="j_password">
+                <input type="checkbox" name="donotcache" value="1">
+                <button type="submit" name="_eventId_proceed">Login</button>
+            </form>
+            <form action="/idp/forgot-password"><input name="email"></form>
+            </body></html>"#
+        ))
+    }
+
+    /// The identity provider on `localhost` and CampusNet on `127.0.0.1` so their cookies are
+    /// separate like for the real domains. Returns the url to start the login at and the one of
+    /// the identity provider.
+    fn mock_servers() -> (Url, Url) {
+        let campusnet_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let idp_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let campusnet = format!(
+            "http://127.0.0.1:{}",
+            campusnet_listener.local_addr().unwrap().port()
+        );
+        let idp = format!(
+            "http://localhost:{}",
+            idp_listener.local_addr().unwrap().port()
+        );
+        let start = Url::parse(&format!("{campusnet}/sso")).unwrap();
+        let idp_url = Url::parse(&idp).unwrap();
+
+        let campusnet_app = Router::new()
+            .route(
+                "/sso",
+                get(move || async move {
+                    let location = format!("{idp}/idp/profile/SAML2/Redirect/SSO?{SAML_REQUEST}");
+                    (StatusCode::FOUND, [(LOCATION, location)])
+                }),
+            )
+            .route(
+                "/acs",
+                axum::routing::post(|Form(fields): Form<Fields>| async move {
+                    if fields.get("SAMLResponse").map(String::as_str) != Some("assertion")
+                        || fields.get("RelayState").map(String::as_str) != Some("ss:mem:1")
+                    {
+                        return StatusCode::FORBIDDEN.into_response();
+                    }
+                    (
+                        [
+                            (SET_COOKIE, "cnsc=0123456789ABCDEF; path=/; HttpOnly"),
+                            ("refresh".parse().unwrap(), START_PAGE),
+                        ],
+                        Html("<html><body>Willkommen</body></html>"),
+                    )
+                        .into_response()
+                }),
+            );
+
+        let idp_app = Router::new().route(
+            "/idp/profile/SAML2/Redirect/SSO",
+            get(|Query(query): Query<Fields>| async move {
+                if !query.contains_key("SAMLRequest") {
+                    return StatusCode::BAD_REQUEST.into_response();
+                }
+                (
+                    [(SET_COOKIE, "JSESSIONID=idp-session; path=/idp")],
+                    login_page(""),
+                )
+                    .into_response()
+            })
+            .post(
+                move |headers: HeaderMap, Form(fields): Form<Fields>| async move {
+                    let cookie = headers.get(COOKIE).and_then(|cookie| cookie.to_str().ok());
+                    if cookie != Some("JSESSIONID=idp-session")
+                        || fields.get("csrf_token").map(String::as_str) != Some("csrf")
+                        || !fields.contains_key("_eventId_proceed")
+                        || fields.contains_key("donotcache")
+                    {
+                        return StatusCode::BAD_REQUEST.into_response();
+                    }
+                    if fields.get("j_username").map(String::as_str) != Some("ab12cdef")
+                        || fields.get("j_password").map(String::as_str) != Some("hunter2")
+                    {
+                        return login_page("The password you entered was incorrect.")
+                            .into_response();
+                    }
+                    Html(format!(
+                        r#"<html><body onload="document.forms[0].submit()">
+                    <form action="{campusnet}/acs" method="post">
+                        <input type="hidden" name="RelayState" value="ss:mem:1"/>
+                        <input type="hidden" name="SAMLResponse" value="assertion"/>
+                        <noscript><input type="submit" value="Continue"/></noscript>
+                    </form></body></html>"#
+                    ))
+                    .into_response()
+                },
+            ),
+        );
+
+        serve(campusnet_listener, campusnet_app);
+        serve(idp_listener, idp_app);
+        (start, idp_url)
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        let (start, idp) = mock_servers();
+
+        assert_eq!(
+            login(&start, &idp, "ab12cdef", "hunter2").await.unwrap(),
+            (123_456_789_012_345, "0123456789ABCDEF".to_string())
+        );
+
+        let err = login(&start, &idp, "ab12cdef", "wrong").await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TucanError>(),
+            Some(&TucanError::InvalidCredentials)
+        );
+
+        // the credentials are not sent to another identity provider or without TLS
+        for other in ["https://idp.example", "https://localhost"] {
+            let err = login(&start, &Url::parse(other).unwrap(), "ab12cdef", "hunter2")
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("instead of the identity provider"));
+        }
+    }
+
+    #[test]
+    fn test_may_send_credentials() {
+        let idp = Url::parse("https://idp.example/").unwrap();
+        let url = |url: &str| Url::parse(url).unwrap();
+        assert!(may_send_credentials(
+            &url("https://idp.example/idp/login?execution=e1s1"),
+            &idp
+        ));
+        assert!(may_send_credentials(
+            &url("https://idp.example:443/idp/login"),
+            &idp
+        ));
+        assert!(!may_send_credentials(
+            &url("http://idp.example/idp/login"),
+            &idp
+        ));
+        assert!(!may_send_credentials(
+            &url("https://idp.example:8443/idp/login"),
+            &idp
+        ));
+        assert!(!may_send_credentials(
+            &url("https://idp.example.attacker.example/idp/login"),
+            &idp
+        ));
+    }
+
+    #[test]
+    fn test_find_form() {
+        let url = Url::parse("https://idp.example/idp/login").unwrap();
+        let form = find_form(&login_page("").0, &url).unwrap().unwrap();
+        assert_eq!(
+            form.action.as_str(),
+            "https://idp.example/idp/profile/SAML2/Redirect/SSO?execution=e1s1"
+        );
+        assert!(form.post);
+        assert_eq!(form.username_field.as_deref(), Some("j_username"));
+        assert_eq!(form.password_field.as_deref(), Some("j_password"));
+        assert_eq!(
+            form.fields,
+            vec![
+                ("csrf_token".to_string(), "csrf".to_string()),
+                ("j_username".to_string(), String::new()),
+                ("j_password".to_string(), String::new()),
+                ("_eventId_proceed".to_string(), String::new()),
+            ]
+        );
+
+        assert_eq!(find_form("<p>no form</p>", &url).unwrap(), None);
+
+        let form = find_form(
+            r#"<form action="/idp/language">
+                <select name="language">
+                    <option value="de">Deutsch</option>
+                    <option value="en" selected>English</option>
+                </select>
+                <select name="theme"><option>hell</option><option>dunkel</option></select>
+            </form>"#,
+            &url,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            form.fields,
+            vec![
+                ("language".to_string(), "en".to_string()),
+                ("theme".to_string(), "hell".to_string()),
+            ]
+        );
+    }
+}
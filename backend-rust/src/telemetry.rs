@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Logging, tracing and metrics.
+//!
+//! Spans and events are printed like `env_logger` did (filtered by `RUST_LOG`) and exported with
+//! OTLP if [`Config::otlp_endpoint`] is set. The metrics are served by the server at `/metrics`.
+
+use std::future::Future;
+use std::panic::Location;
+
+use once_cell::sync::Lazy;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, Encoder, HistogramVec, IntCounterVec,
+    TextEncoder,
+};
+use tracing::{instrument::Instrumented, Instrument};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::Config;
+
+/// How long requests to TUCaN took by [`TucanProgram::name`](crate::url::TucanProgram::name),
+/// including the time waiting for a free connection.
+pub static TUCAN_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "tucant_tucan_request_duration_seconds",
+        "Duration of requests to TUCaN",
+        &["program"],
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+    )
+    .unwrap()
+});
+
+/// Requests to TUCaN that failed before a response was read.
+pub static TUCAN_REQUEST_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tucant_tucan_request_errors_total",
+        "Failed requests to TUCaN",
+        &["program"]
+    )
+    .unwrap()
+});
+
+/// Whether an entity was already in the database (`hit`) or had to be fetched (`miss`).
+pub static CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tucant_cache_lookups_total",
+        "Lookups of crawled entities in the database",
+        &["entity", "result"]
+    )
+    .unwrap()
+});
+
+/// Entities fetched from TUCaN and stored in the database.
+pub static CRAWLED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tucant_crawled_total",
+        "Entities crawled from TUCaN",
+        &["entity"]
+    )
+    .unwrap()
+});
+
+/// Errors answered by the API, by the `type` of [`ApiError`](crate::ApiError).
+pub static API_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "tucant_api_errors_total",
+        "Errors returned by the API",
+        &["type"]
+    )
+    .unwrap()
+});
+
+/// Counts whether `entity` was already in the database.
+pub fn cache_lookup(entity: &str, hit: bool) {
+    CACHE_LOOKUPS
+        .with_label_values(&[entity, if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+/// Counts that `entity` was fetched from TUCaN and stored.
+pub fn crawled(entity: &str) {
+    CRAWLED.with_label_values(&[entity]).inc();
+}
+
+/// Runs a database query in its own `db.query` span, the queries are told apart by where they
+/// are awaited.
+pub trait Traced: Future + Sized {
+    #[track_caller]
+    fn traced(self) -> Instrumented<Self> {
+        let location = Location::caller();
+        self.instrument(tracing::info_span!(
+            "db.query",
+            otel.name = %format!("query {}:{}", location.file(), location.line()),
+            db.system = "postgresql",
+            code.filepath = location.file(),
+            code.lineno = location.line(),
+        ))
+    }
+}
+
+impl<F: Future> Traced for F {}
+
+/// Installs the global subscriber, log records are forwarded to it. Has to be called inside the
+/// tokio runtime if OTLP export is enabled.
+pub fn init(config: &Config) -> anyhow::Result<()> {
+    let otlp = config
+        .otlp_endpoint
+        .as_ref()
+        .map(|endpoint| {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(
+                    trace::config()
+                        .with_resource(Resource::new([KeyValue::new("service.name", "tucant")])),
+                )
+                .install_batch(opentelemetry::runtime::Tokio)?;
+            Ok::<_, anyhow::Error>(tracing_opentelemetry::layer().with_tracer(tracer))
+        })
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp)
+        .try_init()?;
+    Ok(())
+}
+
+/// Exports the spans that are still buffered.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// All metrics in the Prometheus text format.
+pub fn render() -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_lookup, crawled, render, TUCAN_REQUEST_DURATION};
+
+    #[test]
+    fn test_render() {
+        cache_lookup("module", true);
+        cache_lookup("module", false);
+        crawled("module");
+        TUCAN_REQUEST_DURATION
+            .with_label_values(&["moduledetails"])
+            .observe(0.3);
+
+        let metrics = render().unwrap();
+        assert!(metrics.contains(r#"tucant_cache_lookups_total{entity="module",result="hit"}"#));
+        assert!(metrics.contains(r#"tucant_crawled_total{entity="module"}"#));
+        assert!(metrics.contains(
+            r#"tucant_tucan_request_duration_seconds_bucket{program="moduledetails",le="0.5"} 1"#
+        ));
+    }
+}
@@ -2,51 +2,67 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use std::{
-    io::{Error, ErrorKind},
-    sync::Arc,
-};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use deadpool::managed::Pool;
 
 use diesel_async::{pooled_connection::AsyncDieselConnectionManager, AsyncPgConnection};
 
-use opensearch::{
-    auth::Credentials,
-    cert::CertificateValidation,
-    http::transport::{SingleNodeConnectionPool, TransportBuilder},
-    OpenSearch,
-};
+use log::warn;
 use reqwest::{Client, Url};
-use tokio::sync::Semaphore;
+use tokio::sync::{RwLock, Semaphore};
 
 use crate::{
+    config::Config,
+    credentials::{self, CredentialEvent, CredentialKey},
     models::{TucanSession, UndoneUser},
-    schema::{sessions, users_unfinished},
+    schema::users_unfinished,
+    search_backend::{self, SearchBackend},
+    sso,
+    telemetry::Traced,
     tucan_user::TucanUser,
     url::{parse_tucan_url, TucanUrl},
+    TucanError,
 };
 
-use dotenvy::dotenv;
+/// How long a request to TUCaN or the identity provider may take.
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
-fn get_config() -> AsyncDieselConnectionManager<diesel_async::AsyncPgConnection> {
-    dotenv().ok();
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+pub type DbPool = Pool<AsyncDieselConnectionManager<AsyncPgConnection>>;
 
-    AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(database_url)
+fn create_pool(database_url: &str) -> DbPool {
+    let config = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(database_url);
+    Pool::builder(config).build().unwrap()
 }
 
-fn create_pool() -> deadpool::managed::Pool<AsyncDieselConnectionManager<AsyncPgConnection>> {
-    let config = get_config();
-    Pool::builder(config).build().unwrap()
+/// TUCaN stores the selected language in the session, so fetching english pages switches the
+/// language of the session for a moment. German fetches hold `lock` for reading, english ones for
+/// writing.
+#[derive(Debug, Default)]
+pub(crate) struct SessionLanguage {
+    pub(crate) lock: RwLock<()>,
+    /// Set before switching to english and only cleared once switching back succeeded, so a
+    /// failed or cancelled switch is retried before the next german fetch.
+    pub(crate) english: AtomicBool,
 }
 
 #[derive(Clone)]
 pub struct Tucan {
     pub(crate) client: Client,
     pub(crate) semaphore: Arc<Semaphore>,
-    pub pool: Pool<AsyncDieselConnectionManager<AsyncPgConnection>>,
-    pub opensearch: OpenSearch,
+    /// The language state of the sessions that are fetching right now, by session id.
+    session_languages: Arc<Mutex<HashMap<String, Arc<SessionLanguage>>>>,
+    pub pool: DbPool,
+    pub search: Arc<dyn SearchBackend>,
+    /// Set if storing credentials is enabled by [`Config::credential_key_file`].
+    pub credential_key: Option<CredentialKey>,
+    /// Set if logins go through single sign-on, see [`Config::sso_start_url`].
+    pub(crate) sso_start_url: Option<Url>,
+    /// The origin of [`Config::sso_idp_host`], only its login form gets the credentials.
+    sso_idp: Url,
 }
 
 impl std::fmt::Debug for Tucan {
@@ -56,25 +72,48 @@ impl std::fmt::Debug for Tucan {
 }
 
 impl Tucan {
-    pub fn new() -> anyhow::Result<Self> {
-        let pool = create_pool();
-
-        let url = Url::parse("https://localhost:9200")?;
-        let conn_pool = SingleNodeConnectionPool::new(url);
-        let transport = TransportBuilder::new(conn_pool)
-            .auth(Credentials::Basic("admin".to_string(), "admin".to_string()))
-            .cert_validation(CertificateValidation::None)
-            .build()?;
-        let opensearch = OpenSearch::new(transport);
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let pool = create_pool(&config.database_url);
+        let search = search_backend::from_config(config, pool.clone())?;
+        let credential_key = config
+            .credential_key_file
+            .as_deref()
+            .map(CredentialKey::load)
+            .transpose()?;
+        let sso_start_url = config
+            .sso_start_url
+            .as_deref()
+            .map(Url::parse)
+            .transpose()?;
+        let sso_idp = Url::parse(&format!("https://{}/", config.sso_idp_host))?;
 
         Ok(Self {
             pool,
-            client: reqwest::Client::builder().build()?,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
             semaphore: Arc::new(Semaphore::new(3)),
-            opensearch,
+            session_languages: Arc::default(),
+            search,
+            credential_key,
+            sso_start_url,
+            sso_idp,
         })
     }
 
+    pub(crate) fn session_language(&self, session_id: &str) -> Arc<SessionLanguage> {
+        let mut session_languages = self.session_languages.lock().unwrap();
+        // only keep the sessions someone is fetching with or that may still be english because
+        // switching back failed
+        session_languages.retain(|_, language| {
+            Arc::strong_count(language) > 1 || language.english.load(Ordering::SeqCst)
+        });
+        session_languages
+            .entry(session_id.to_string())
+            .or_default()
+            .clone()
+    }
+
     #[must_use]
     pub fn continue_session(&self, session: TucanSession) -> TucanUser {
         TucanUser {
@@ -114,7 +153,11 @@ impl Tucan {
     }
 
     pub async fn login(&self, username: &str, password: &str) -> anyhow::Result<TucanUser> {
-        use diesel_async::RunQueryDsl;
+        if let Some(start_url) = &self.sso_start_url {
+            let (session_nr, session_id) =
+                sso::login(start_url, &self.sso_idp, username, password).await?;
+            return self.logged_in(session_nr, session_id).await;
+        }
 
         let params: [(&str, &str); 10] = [
             ("usrname", username),
@@ -146,7 +189,7 @@ impl Tucan {
                 &refresh_header.unwrap().to_str()?[7..]
             );
 
-            let url = parse_tucan_url(redirect_url);
+            let url = parse_tucan_url(redirect_url)?;
 
             if let TucanUrl {
                 session_nr: Some(nr),
@@ -158,46 +201,104 @@ impl Tucan {
 
                 res_headers.text().await?;
 
-                let session_nr = nr.try_into().unwrap();
-                let session_id = id.to_string();
-
-                let user = self
-                    .tucan_session_from_session_data(session_nr, session_id.clone())
-                    .await?;
-
-                let mut connection = self.pool.get().await?;
-
-                {
-                    let user_session = user.session.clone();
-                    connection
-                        .build_transaction()
-                        .run(|mut connection| {
-                            Box::pin(async move {
-                                diesel::insert_into(users_unfinished::table)
-                                    .values(UndoneUser::new(user.session.matriculation_number))
-                                    .on_conflict(users_unfinished::matriculation_number)
-                                    .do_nothing()
-                                    .execute(&mut connection)
-                                    .await?;
-
-                                diesel::insert_into(sessions::table)
-                                    .values(user_session)
-                                    .execute(&mut connection)
-                                    .await?;
-
-                                Ok::<(), diesel::result::Error>(())
-                            })
-                        })
-                        .await?;
-                }
-
-                return Ok(user);
+                return self.logged_in(nr.try_into().unwrap(), id).await;
             }
             panic!("Failed to extract session_nr");
         }
 
         res_headers.text().await?;
 
-        Err(Error::new(ErrorKind::Other, "Invalid username or password").into())
+        Err(TucanError::InvalidCredentials.into())
+    }
+
+    async fn logged_in(&self, session_nr: i64, session_id: String) -> anyhow::Result<TucanUser> {
+        use diesel_async::RunQueryDsl;
+
+        let user = self
+            .tucan_session_from_session_data(session_nr, session_id)
+            .await?;
+
+        let mut connection = self.pool.get().await?;
+
+        // the session itself is stored by the caller, see `crate::session::create`
+        diesel::insert_into(users_unfinished::table)
+            .values(UndoneUser::new(user.session.matriculation_number))
+            .on_conflict(users_unfinished::matriculation_number)
+            .do_nothing()
+            .execute(&mut connection)
+            .traced()
+            .await?;
+
+        Ok(user)
+    }
+
+    /// Logs in with the credentials the user stored for background jobs, `None` if there are
+    /// none. Credentials TUCaN rejects are deleted so they don't get the account locked, as are
+    /// credentials that log in as someone else.
+    #[tracing::instrument(skip(self))]
+    pub async fn relogin(&self, matriculation_number: i32) -> anyhow::Result<Option<TucanUser>> {
+        let Some(key) = &self.credential_key else {
+            return Ok(None);
+        };
+        let Some(stored) = credentials::open(&self.pool, key, matriculation_number).await? else {
+            return Ok(None);
+        };
+
+        let user = match self.login(&stored.username, &stored.password).await {
+            Ok(user) => user,
+            Err(err) if err.downcast_ref() == Some(&TucanError::InvalidCredentials) => {
+                credentials::revoke(
+                    &self.pool,
+                    matriculation_number,
+                    CredentialEvent::LoginFailed,
+                )
+                .await?;
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+
+        if user.session.matriculation_number != matriculation_number {
+            if let Err(err) = user.logout().await {
+                warn!("failed to log out at TUCaN: {err:#}");
+            }
+            credentials::revoke(&self.pool, matriculation_number, CredentialEvent::WrongUser)
+                .await?;
+            anyhow::bail!("the stored credentials logged in as someone else and were deleted");
+        }
+
+        credentials::used(&self.pool, matriculation_number).await?;
+        Ok(Some(user))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::Tucan;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_session_language() {
+        let config = Config::from_toml("database_url = \"postgres://localhost/tucant\"").unwrap();
+        let tucan = Tucan::new(&config).unwrap();
+
+        let language = tucan.session_language("german");
+        assert!(!language.english.load(Ordering::SeqCst));
+        drop(language);
+
+        // like an english fetch after which switching back to german failed
+        let language = tucan.session_language("english");
+        language.english.store(true, Ordering::SeqCst);
+        drop(language);
+
+        assert!(tucan
+            .session_language("english")
+            .english
+            .load(Ordering::SeqCst));
+        let session_languages = tucan.session_languages.lock().unwrap();
+        assert!(!session_languages.contains_key("german"));
+        assert!(session_languages.contains_key("english"));
     }
 }
@@ -2,27 +2,29 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use std::{
-    convert::TryInto,
-    io::{Error, ErrorKind},
-};
+use std::convert::TryInto;
+use std::sync::atomic::Ordering;
 
 use crate::{
+    ids::ModuleId,
     models::{
-        Course, CourseEvent, CourseExam, CourseGroup, CourseGroupEvent, Exam, Module, ModuleCourse,
-        ModuleExam, ModuleMenu, ModuleMenuEntryModule, UndoneUser, UserCourseGroup, UserExam,
-        COURSES_UNFINISHED, MODULES_UNFINISHED,
+        Course, CourseEvent, CourseExam, CourseGroup, CourseGroupEvent, Exam, Language, Localize,
+        Module, ModuleCourse, ModuleExam, ModuleMenu, ModuleMenuEntryModule, UndoneUser,
+        UserCourseGroup, UserExam, COURSES_UNFINISHED, MODULES_UNFINISHED,
     },
-    tucan::Tucan,
+    telemetry::{self, Traced},
+    tucan::{SessionLanguage, Tucan},
     url::{
-        parse_tucan_url, Coursedetails, Examdetails, Moduledetails, Myexams, Mymodules,
-        Persaddress, Registration, RootRegistration, TucanProgram, TucanUrl,
+        parse_tucan_url, Changelanguage, Coursedetails, Examdetails, Logout, Moduledetails,
+        Myexams, Mymodules, Persaddress, Registration, RootRegistration, TucanProgram,
     },
+    TucanError,
 };
 use crate::{
     models::{TucanSession, UserCourse, UserModule},
     url::Profcourses,
 };
+use anyhow::Context;
 use chrono::{NaiveDateTime, TimeZone, Utc};
 use deadpool::managed::Object;
 use diesel_async::{pooled_connection::AsyncDieselConnectionManager, AsyncPgConnection};
@@ -50,7 +52,7 @@ use diesel::upsert::excluded;
 use diesel::GroupedBy;
 use diesel::OptionalExtension;
 use diesel::QueryDsl;
-use log::debug;
+use log::{debug, warn};
 use scraper::Selector;
 
 fn s(selector: &str) -> Selector {
@@ -61,6 +63,16 @@ fn element_by_selector<'a>(document: &'a Html, selector: &str) -> Option<Element
     document.select(&s(selector)).next()
 }
 
+/// Parses the `href` of a link on a TUCaN page that should lead to a `T`.
+fn parse_link<T: TryFrom<TucanProgram>>(href: &str) -> anyhow::Result<T> {
+    let url = parse_tucan_url(&format!("https://www.tucan.tu-darmstadt.de{href}"))
+        .context(TucanError::UnexpectedLink)?;
+    let name = url.program.name();
+    T::try_from(url.program)
+        .map_err(|_| anyhow::anyhow!("the link leads to {name}"))
+        .context(TucanError::UnexpectedLink)
+}
+
 #[derive(Clone, Debug)]
 pub struct TucanUser {
     pub tucan: Tucan,
@@ -74,23 +86,27 @@ pub enum CourseOrCourseGroup {
     CourseGroup((CourseGroup, Vec<CourseGroupEvent>)),
 }
 
+impl Localize for CourseOrCourseGroup {
+    fn localize(self, language: Language) -> Self {
+        match self {
+            Self::Course(course) => Self::Course(course.localize(language)),
+            Self::CourseGroup(course_group) => Self::CourseGroup(course_group),
+        }
+    }
+}
+
 static NORMALIZED_NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ /)(.]+").unwrap());
 
 static TUCANSCHEISS: Lazy<Module> = Lazy::new(|| Module {
-    tucan_id: base64::decode_engine(
-        "TUCANSCHEISS",
-        &base64::engine::fast_portable::FastPortable::from(
-            &base64::alphabet::URL_SAFE,
-            base64::engine::fast_portable::NO_PAD,
-        ),
-    )
-    .unwrap(),
+    tucan_id: ModuleId::tucanscheiss(),
     tucan_last_checked: Utc::now().naive_utc(),
     title: "TUCANSCHEISS".to_string(),
     module_id: "TUCANSCHEISS".to_string(),
     credits: Some(0),
     content: "TUCANSCHEISS".to_string(),
     done: true,
+    title_en: String::new(),
+    content_en: String::new(),
 });
 
 impl TucanUser {
@@ -108,12 +124,67 @@ impl TucanUser {
     }
 
     pub(crate) async fn fetch_document(&self, url: &TucanProgram) -> anyhow::Result<String> {
+        self.fetch_document_in(url, Language::German).await
+    }
+
+    pub(crate) async fn fetch_document_in(
+        &self,
+        url: &TucanProgram,
+        language: Language,
+    ) -> anyhow::Result<String> {
+        let state = self.tucan.session_language(&self.session.session_id);
+        match language {
+            Language::German => {
+                let mut guard = state.lock.read().await;
+                if state.english.load(Ordering::SeqCst) {
+                    drop(guard);
+                    let write_guard = state.lock.write().await;
+                    self.restore_german(&state).await?;
+                    guard = write_guard.downgrade();
+                }
+                let result = self.fetch_document_unlocked(url).await;
+                drop(guard);
+                result
+            }
+            Language::English => {
+                let _guard = state.lock.write().await;
+                state.english.store(true, Ordering::SeqCst);
+                let result = match self.change_language(Language::English).await {
+                    Ok(()) => self.fetch_document_unlocked(url).await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = self.restore_german(&state).await {
+                    warn!("failed to switch the session back to german: {err:#}");
+                }
+                result
+            }
+        }
+    }
+
+    async fn change_language(&self, language: Language) -> anyhow::Result<()> {
+        self.fetch_document_unlocked(&Changelanguage { language }.into())
+            .await?;
+        Ok(())
+    }
+
+    /// Switches the session back to german if it may still be english, the caller holds the
+    /// write lock of `state`.
+    async fn restore_german(&self, state: &SessionLanguage) -> anyhow::Result<()> {
+        if state.english.load(Ordering::SeqCst) {
+            self.change_language(Language::German).await?;
+            state.english.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(program = url.name()))]
+    async fn fetch_document_unlocked(&self, url: &TucanProgram) -> anyhow::Result<String> {
         let cookie = format!("cnsc={}", self.session.session_id);
 
         let mut request = self
             .tucan
             .client
-            .get(url.to_tucan_url(Some(self.session.session_nr.try_into().unwrap())))
+            .get(url.to_tucan_url(Some(self.session.session_nr.try_into().unwrap()))?)
             .build()
             .unwrap();
 
@@ -121,11 +192,23 @@ impl TucanUser {
             .headers_mut()
             .insert("Cookie", HeaderValue::from_str(&cookie).unwrap());
 
+        let timer = telemetry::TUCAN_REQUEST_DURATION
+            .with_label_values(&[url.name()])
+            .start_timer();
         let permit = self.tucan.semaphore.clone().acquire_owned().await?;
-        let resp = self.tucan.client.execute(request).await?.text().await?;
+        let resp = match self.tucan.client.execute(request).await {
+            Ok(resp) => resp.text().await,
+            Err(err) => Err(err),
+        };
         drop(permit);
+        timer.observe_duration();
 
-        Ok(resp)
+        if resp.is_err() {
+            telemetry::TUCAN_REQUEST_ERRORS
+                .with_label_values(&[url.name()])
+                .inc();
+        }
+        Ok(resp?)
     }
 
     pub(crate) fn parse_document(resp: &str) -> anyhow::Result<Html> {
@@ -135,12 +218,37 @@ impl TucanUser {
             .select(&s("h1"))
             .any(|s| s.inner_html() == "Timeout!")
         {
-            return Err(Error::new(ErrorKind::Other, "well we got a timeout here. relogin").into());
-            // TODO FIXME propagate error better
+            return Err(TucanError::SessionExpired.into());
         }
         Ok(html_doc)
     }
 
+    /// Extracts the title and description of a module or course page in the language it was fetched in.
+    fn extract_texts(
+        document: &Html,
+        title_separator: &str,
+        content_selector: &str,
+    ) -> (String, String) {
+        let title = element_by_selector(document, "h1")
+            .and_then(|name| {
+                name.inner_html()
+                    .trim()
+                    .split(title_separator)
+                    .nth(1)
+                    .map(|title| title.trim().to_string())
+            })
+            .unwrap_or_default();
+
+        let content = document
+            .select(&s(content_selector))
+            .next()
+            .map(|content| content.inner_html())
+            .unwrap_or_default();
+
+        (title, content)
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn cached_module(
         &self,
         url: Moduledetails,
@@ -154,6 +262,7 @@ impl TucanUser {
             .filter(modules_unfinished::done)
             .select(MODULES_UNFINISHED)
             .get_result::<Module>(&mut connection)
+            .traced()
             .await
             .optional()?;
 
@@ -164,6 +273,7 @@ impl TucanUser {
                 .inner_join(courses_unfinished::table)
                 .select(COURSES_UNFINISHED)
                 .load::<Course>(&mut connection)
+                .traced()
                 .await?;
 
             Ok(Some((existing_module, course_list)))
@@ -172,12 +282,21 @@ impl TucanUser {
         }
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn fetch_module(&self, url: Moduledetails) -> anyhow::Result<()> {
         use diesel_async::RunQueryDsl;
 
         let document = self.fetch_document(&url.clone().into()).await?;
+        let english_document = self
+            .fetch_document_in(&url.clone().into(), Language::English)
+            .await?;
         let mut connection = self.tucan.pool.get().await?;
 
+        let (title_en, content_en) = {
+            let document = Self::parse_document(&english_document)?;
+            Self::extract_texts(&document, "&nbsp;", "#contentlayoutleft tr.tbdata")
+        };
+
         let (module, courses) = {
             let document = Self::parse_document(&document)?;
 
@@ -215,29 +334,26 @@ impl TucanUser {
                 .select(&s(r#"a[name="eventLink"]"#))
                 .map(|e| e.parent().unwrap().parent().unwrap())
                 .unique_by(NodeRef::id)
-                .map(|node| {
+                .map(|node| -> anyhow::Result<_> {
                     let element_ref = ElementRef::wrap(node).unwrap();
                     let selector = &s("a");
                     let mut links = element_ref.select(selector);
-                    Course {
+                    Ok(Course {
                         tucan_last_checked: Utc::now().naive_utc(),
                         course_id: links.next().unwrap().inner_html(),
                         title: links.next().unwrap().inner_html(),
-                        tucan_id: TryInto::<Coursedetails>::try_into(
-                            parse_tucan_url(&format!(
-                                "https://www.tucan.tu-darmstadt.de{}",
-                                links.next().unwrap().value().attr("href").unwrap()
-                            ))
-                            .program,
-                        )
-                        .unwrap()
+                        tucan_id: parse_link::<Coursedetails>(
+                            links.next().unwrap().value().attr("href").unwrap(),
+                        )?
                         .id,
                         sws: 0,
                         content: String::new(),
                         done: false,
-                    }
+                        title_en: String::new(),
+                        content_en: String::new(),
+                    })
                 })
-                .collect::<Vec<_>>();
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
             let module = Module {
                 tucan_id: url.clone().id,
@@ -247,6 +363,8 @@ impl TucanUser {
                 module_id: Self::normalize(module_id),
                 content,
                 done: true,
+                title_en,
+                content_en,
             };
 
             (module, courses)
@@ -260,6 +378,7 @@ impl TucanUser {
             .do_update()
             .set(&module)
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(courses_unfinished::table)
@@ -267,6 +386,7 @@ impl TucanUser {
             .on_conflict(courses_unfinished::tucan_id)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(module_courses::table)
@@ -282,17 +402,22 @@ impl TucanUser {
             .on_conflict(module_courses::all_columns)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn module(&self, url: Moduledetails) -> anyhow::Result<(Module, Vec<Course>)> {
         if let Some(value) = self.cached_module(url.clone()).await? {
+            telemetry::cache_lookup("module", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("module", false);
 
         self.fetch_module(url.clone()).await?;
+        telemetry::crawled("module");
 
         Ok(self.cached_module(url).await?.unwrap())
     }
@@ -300,7 +425,7 @@ impl TucanUser {
     fn extract_events(&self, url: &Coursedetails, document: &Html) -> Vec<CourseEvent> {
         let unwrap_handler = || -> ! {
             panic!(
-                "{}",
+                "{:?}",
                 Into::<TucanProgram>::into(url.clone())
                     .to_tucan_url(Some(self.session.session_nr.try_into().unwrap()))
             );
@@ -339,7 +464,7 @@ impl TucanUser {
                     start_time_column.inner_html(),
                     end_time_column.inner_html()
                 );
-                println!("{val}");
+                debug!("event date {val}");
                 let date = Self::parse_datetime(&val);
                 let room = room_column
                     .select(&s("a"))
@@ -363,6 +488,7 @@ impl TucanUser {
             .collect_vec()
     }
 
+    #[tracing::instrument(skip_all)]
     async fn fetch_course(
         &self,
         url: Coursedetails,
@@ -373,12 +499,21 @@ impl TucanUser {
 
         let unwrap_handler = || -> ! {
             panic!(
-                "{}",
+                "{:?}",
                 Into::<TucanProgram>::into(url.clone())
                     .to_tucan_url(Some(self.session.session_nr.try_into().unwrap()))
             );
         };
 
+        let english_document = self
+            .fetch_document_in(&url.clone().into(), Language::English)
+            .await?;
+
+        let (title_en, content_en) = {
+            let document = Self::parse_document(&english_document)?;
+            Self::extract_texts(&document, "\n", "#contentlayoutleft td.tbdata")
+        };
+
         let (course, course_groups, events) = {
             let document = Self::parse_document(&document)?;
 
@@ -418,24 +553,22 @@ impl TucanUser {
                 course_id: Self::normalize(course_id),
                 content,
                 done: true,
+                title_en,
+                content_en,
             };
 
             let course_groups: Vec<CourseGroup> = document
                 .select(&s(".dl-ul-listview .listelement"))
-                .map(|e| {
-                    let coursegroupdetails: Coursedetails = parse_tucan_url(&format!(
-                        "https://www.tucan.tu-darmstadt.de{}",
+                .map(|e| -> anyhow::Result<_> {
+                    let coursegroupdetails: Coursedetails = parse_link(
                         e.select(&s(".img_arrowLeft"))
                             .next()
                             .unwrap_or_else(|| unwrap_handler())
                             .value()
                             .attr("href")
-                            .unwrap_or_else(|| unwrap_handler())
-                    ))
-                    .program
-                    .try_into()
-                    .unwrap_or_else(|_| unwrap_handler());
-                    CourseGroup {
+                            .unwrap_or_else(|| unwrap_handler()),
+                    )?;
+                    Ok(CourseGroup {
                         tucan_id: coursegroupdetails.id,
                         course: url.id.clone(),
                         title: e
@@ -444,9 +577,9 @@ impl TucanUser {
                             .unwrap_or_else(|| unwrap_handler())
                             .inner_html(),
                         done: false,
-                    }
+                    })
                 })
-                .collect();
+                .collect::<anyhow::Result<_>>()?;
 
             (course, course_groups, events)
         };
@@ -458,6 +591,7 @@ impl TucanUser {
             .do_update()
             .set(&course)
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(course_groups_unfinished::table)
@@ -465,6 +599,7 @@ impl TucanUser {
             .on_conflict(course_groups_unfinished::tucan_id)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(course_events::table)
@@ -478,11 +613,13 @@ impl TucanUser {
             .do_update()
             .set(course_events::teachers.eq(excluded(course_events::teachers)))
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn fetch_course_group(
         &self,
         url: Coursedetails,
@@ -499,12 +636,8 @@ impl TucanUser {
                 .find(|e| e.inner_html() == "Plenumsveranstaltung anzeigen")
                 .unwrap();
 
-            let plenum_url = parse_tucan_url(&format!(
-                "https://www.tucan.tu-darmstadt.de{}",
-                plenum_element.value().attr("href").unwrap()
-            ));
-
-            let course_details: Coursedetails = plenum_url.program.try_into().unwrap();
+            let course_details: Coursedetails =
+                parse_link(plenum_element.value().attr("href").unwrap())?;
 
             let name = element_by_selector(
                 &document,
@@ -546,6 +679,8 @@ impl TucanUser {
             course_id: String::new(),
             content: String::new(),
             done: false,
+            title_en: String::new(),
+            content_en: String::new(),
         };
 
         diesel::insert_into(courses_unfinished::table)
@@ -554,6 +689,7 @@ impl TucanUser {
             .do_update()
             .set(&course)
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(course_groups_unfinished::table)
@@ -562,6 +698,7 @@ impl TucanUser {
             .do_update()
             .set(&course_group)
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(course_groups_events::table)
@@ -575,11 +712,13 @@ impl TucanUser {
             .do_update()
             .set(course_groups_events::teachers.eq(excluded(course_groups_events::teachers)))
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn cached_course(
         &self,
         url: Coursedetails,
@@ -593,6 +732,7 @@ impl TucanUser {
             .filter(courses_unfinished::done)
             .select(COURSES_UNFINISHED)
             .get_result::<Course>(&mut connection)
+            .traced()
             .await
             .optional()?;
 
@@ -604,6 +744,7 @@ impl TucanUser {
                 .inner_join(course_groups_unfinished::table)
                 .select(course_groups_unfinished::all_columns)
                 .load::<CourseGroup>(&mut connection)
+                .traced()
                 .await?;
 
             let course_events = courses_unfinished::table
@@ -611,6 +752,7 @@ impl TucanUser {
                 .inner_join(course_events::table)
                 .select(course_events::all_columns)
                 .load::<CourseEvent>(&mut connection)
+                .traced()
                 .await?;
 
             return Ok(Some((existing, course_groups, course_events)));
@@ -619,6 +761,7 @@ impl TucanUser {
         Ok(None)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn cached_course_group(
         &self,
         url: Coursedetails,
@@ -637,6 +780,7 @@ impl TucanUser {
                 course_groups_unfinished::done,
             ))
             .get_result::<CourseGroup>(&mut connection)
+            .traced()
             .await
             .optional()?;
 
@@ -647,6 +791,7 @@ impl TucanUser {
                 .filter(course_groups_events::course.eq(&existing.tucan_id))
                 .select(course_groups_events::all_columns)
                 .load::<CourseGroupEvent>(&mut connection)
+                .traced()
                 .await?;
 
             return Ok(Some((existing, course_group_events)));
@@ -655,48 +800,59 @@ impl TucanUser {
         Ok(None)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn course(
         &self,
         url: Coursedetails,
     ) -> anyhow::Result<(Course, Vec<CourseGroup>, Vec<CourseEvent>)> {
         if let Some(value) = self.cached_course(url.clone()).await? {
+            telemetry::cache_lookup("course", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("course", false);
 
         let document = self.fetch_document(&url.clone().into()).await?;
         let connection = self.tucan.pool.get().await?;
 
         self.fetch_course(url.clone(), document, connection).await?;
+        telemetry::crawled("course");
 
         Ok(self.cached_course(url).await?.unwrap())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn course_group(
         &self,
         url: Coursedetails,
     ) -> anyhow::Result<(CourseGroup, Vec<CourseGroupEvent>)> {
         if let Some(value) = self.cached_course_group(url.clone()).await? {
+            telemetry::cache_lookup("course_group", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("course_group", false);
 
         let document = self.fetch_document(&url.clone().into()).await?;
         let connection = self.tucan.pool.get().await?;
 
         self.fetch_course_group(url.clone(), document, connection)
             .await?;
+        telemetry::crawled("course_group");
 
         Ok(self.cached_course_group(url).await?.unwrap())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn course_or_course_group(
         &self,
         url: Coursedetails,
     ) -> anyhow::Result<CourseOrCourseGroup> {
         if let Some(value) = self.cached_course(url.clone()).await? {
+            telemetry::cache_lookup("course", true);
             return Ok(CourseOrCourseGroup::Course(value));
         }
 
         if let Some(value) = self.cached_course_group(url.clone()).await? {
+            telemetry::cache_lookup("course_group", true);
             return Ok(CourseOrCourseGroup::CourseGroup(value));
         }
 
@@ -706,26 +862,33 @@ impl TucanUser {
         let is_course_group =
             element_by_selector(&Self::parse_document(&document)?, "form h1 + h2").is_some();
 
-        println!("is_course_group {is_course_group}");
+        debug!("is_course_group {is_course_group}");
 
         if is_course_group {
+            telemetry::cache_lookup("course_group", false);
             Ok(CourseOrCourseGroup::CourseGroup({
                 self.fetch_course_group(url.clone(), document, connection)
                     .await?;
+                telemetry::crawled("course_group");
                 self.cached_course_group(url.clone()).await?.unwrap()
             }))
         } else {
+            telemetry::cache_lookup("course", false);
             Ok(CourseOrCourseGroup::Course({
                 self.fetch_course(url.clone(), document, connection).await?;
+                telemetry::crawled("course");
                 self.cached_course(url.clone()).await?.unwrap()
             }))
         }
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn root_registration(&self) -> anyhow::Result<ModuleMenu> {
         // TODO FIXME cache this
 
-        let document = self.fetch_document(&RootRegistration {}.into()).await?;
+        let document = self
+            .fetch_document(&RootRegistration::default().into())
+            .await?;
         let document = Self::parse_document(&document)?;
 
         let url_element = document
@@ -734,15 +897,7 @@ impl TucanUser {
             .last()
             .unwrap();
 
-        let url = parse_tucan_url(&format!(
-            "https://www.tucan.tu-darmstadt.de{}",
-            url_element.value().attr("href").unwrap()
-        ));
-
-        let TucanUrl {
-            program: TucanProgram::Registration(url),
-            ..
-        } = url else { panic!() };
+        let url: Registration = parse_link(url_element.value().attr("href").unwrap())?;
 
         let name = url_element.inner_html();
         let _normalized_name = Self::normalize(&name);
@@ -756,6 +911,7 @@ impl TucanUser {
         })
     }
 
+    #[tracing::instrument(skip_all)]
     async fn cached_registration(
         &self,
         url: Registration,
@@ -773,6 +929,7 @@ impl TucanUser {
             .filter(module_menu_unfinished::tucan_id.eq(&url.path))
             .filter(module_menu_unfinished::done)
             .get_result::<ModuleMenu>(&mut connection)
+            .traced()
             .await
             .optional()?;
 
@@ -784,6 +941,7 @@ impl TucanUser {
                 .select(module_menu_unfinished::all_columns)
                 .filter(module_menu_unfinished::parent.eq(&url.path))
                 .load::<ModuleMenu>(&mut connection)
+                .traced()
                 .await?;
 
             // existing submodules
@@ -792,6 +950,7 @@ impl TucanUser {
                 .select(MODULES_UNFINISHED)
                 .filter(module_menu_module::module_menu_id.eq(&url.path))
                 .load::<Module>(&mut connection)
+                .traced()
                 .await?;
 
             // TODO FIXME maybe only return the latest course for courses with same course_id
@@ -803,6 +962,7 @@ impl TucanUser {
                         COURSES_UNFINISHED,
                     ))
                     .load::<(ModuleCourse, Course)>(&mut connection)
+                    .traced()
                     .await?;
             let grouped_module_courses: Vec<Vec<(ModuleCourse, Course)>> =
                 module_courses.grouped_by(&submodules);
@@ -826,6 +986,7 @@ impl TucanUser {
 
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::unused_peekable)]
+    #[tracing::instrument(skip_all)]
     pub async fn fetch_registration(&self, url: Registration) -> anyhow::Result<()> {
         use diesel_async::RunQueryDsl;
 
@@ -874,20 +1035,15 @@ impl TucanUser {
             });
 
             let modules: Vec<(Module, Vec<Course>)> = d
-                .map(|e| {
+                .map(|e| -> anyhow::Result<_> {
                     let module = e.0.map_or_else(
-                        || TUCANSCHEISS.clone(),
-                        |i| {
+                        || Ok(TUCANSCHEISS.clone()),
+                        |i| -> anyhow::Result<_> {
                             let mut text = i.text();
-                            Module {
-                                tucan_id: TryInto::<Moduledetails>::try_into(
-                                    parse_tucan_url(&format!(
-                                        "https://www.tucan.tu-darmstadt.de{}",
-                                        i.value().attr("href").unwrap()
-                                    ))
-                                    .program,
-                                )
-                                .unwrap()
+                            Ok(Module {
+                                tucan_id: parse_link::<Moduledetails>(
+                                    i.value().attr("href").unwrap(),
+                                )?
                                 .id,
                                 tucan_last_checked: Utc::now().naive_utc(),
                                 module_id: text
@@ -901,24 +1057,21 @@ impl TucanUser {
                                 credits: None,
                                 content: String::new(),
                                 done: false,
-                            }
+                                title_en: String::new(),
+                                content_en: String::new(),
+                            })
                         },
-                    );
+                    )?;
 
                     let courses =
                         e.1.into_iter()
-                            .map(|course| {
+                            .map(|course| -> anyhow::Result<_> {
                                 let mut text = course.text();
 
-                                Course {
-                                    tucan_id: TryInto::<Coursedetails>::try_into(
-                                        parse_tucan_url(&format!(
-                                            "https://www.tucan.tu-darmstadt.de{}",
-                                            course.value().attr("href").unwrap()
-                                        ))
-                                        .program,
-                                    )
-                                    .unwrap()
+                                Ok(Course {
+                                    tucan_id: parse_link::<Coursedetails>(
+                                        course.value().attr("href").unwrap(),
+                                    )?
                                     .id,
                                     tucan_last_checked: Utc::now().naive_utc(),
                                     course_id: text
@@ -936,37 +1089,31 @@ impl TucanUser {
                                     sws: 0,
                                     content: String::new(),
                                     done: false,
-                                }
+                                    title_en: String::new(),
+                                    content_en: String::new(),
+                                })
                             })
-                            .collect_vec();
+                            .collect::<anyhow::Result<Vec<_>>>()?;
 
-                    (module, courses)
+                    Ok((module, courses))
                 })
-                .collect();
+                .collect::<anyhow::Result<_>>()?;
 
             let utc = Utc::now().naive_utc();
             let submenus: Vec<ModuleMenu> = document
                 .select(&s("#contentSpacer_IE ul a[href]"))
-                .map(|e| {
-                    let child = TryInto::<Registration>::try_into(
-                        parse_tucan_url(&format!(
-                            "https://www.tucan.tu-darmstadt.de{}",
-                            e.value().attr("href").unwrap()
-                        ))
-                        .program,
-                    )
-                    .unwrap()
-                    .path;
+                .map(|e| -> anyhow::Result<_> {
+                    let child = parse_link::<Registration>(e.value().attr("href").unwrap())?.path;
 
-                    ModuleMenu {
+                    Ok(ModuleMenu {
                         tucan_id: child,
                         tucan_last_checked: utc,
                         name: e.inner_html().trim().to_string(),
                         done: false,
                         parent: Some(url.path.clone()),
-                    }
+                    })
                 })
-                .collect::<Vec<_>>();
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
             (module_menu, submenus, modules)
         };
@@ -977,12 +1124,14 @@ impl TucanUser {
             .do_update()
             .set(&module_menu) // treat_none_as_null is false so parent should't be overwritten
             .get_result::<ModuleMenu>(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(modules_unfinished::table)
             .values(modules.iter().map(|m| &m.0).collect_vec())
             .on_conflict_do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(module_menu_module::table)
@@ -998,12 +1147,14 @@ impl TucanUser {
             )
             .on_conflict_do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(courses_unfinished::table)
             .values(modules.iter().flat_map(|m| &m.1).collect_vec())
             .on_conflict_do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(module_courses::table)
@@ -1020,6 +1171,7 @@ impl TucanUser {
             )
             .on_conflict_do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(module_menu_unfinished::table)
@@ -1028,30 +1180,37 @@ impl TucanUser {
             .do_update()
             .set(module_menu_unfinished::parent.eq(excluded(module_menu_unfinished::parent)))
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::update(module_menu_unfinished::table)
             .filter(module_menu_unfinished::tucan_id.eq(url.path.clone()))
             .set(module_menu_unfinished::done.eq(true))
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn registration(
         &self,
         url: Registration,
     ) -> anyhow::Result<(ModuleMenu, crate::models::Registration)> {
         if let Some(value) = self.cached_registration(url.clone()).await? {
+            telemetry::cache_lookup("menu", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("menu", false);
 
         self.fetch_registration(url.clone()).await?;
+        telemetry::crawled("menu");
 
         Ok(self.cached_registration(url.clone()).await?.unwrap())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn cached_my_modules(&self) -> anyhow::Result<Option<Vec<Module>>> {
         use diesel_async::RunQueryDsl;
 
@@ -1066,6 +1225,7 @@ impl TucanUser {
                         .filter(users_unfinished::matriculation_number.eq(&tu_id))
                         .select(users_unfinished::user_modules_last_checked)
                         .get_result::<Option<NaiveDateTime>>(&mut connection)
+                        .traced()
                         .await?;
 
                     if user_studies_already_fetched.is_some() {
@@ -1075,6 +1235,7 @@ impl TucanUser {
                                 .inner_join(modules_unfinished::table)
                                 .select(MODULES_UNFINISHED)
                                 .load::<Module>(&mut connection)
+                                .traced()
                                 .await?,
                         ))
                     } else {
@@ -1087,25 +1248,21 @@ impl TucanUser {
         Ok(modules)
     }
 
+    #[tracing::instrument(skip_all)]
     async fn fetch_my_modules(&self) -> anyhow::Result<()> {
         use diesel_async::RunQueryDsl;
 
-        let document = self.fetch_document(&Mymodules.clone().into()).await?;
+        let document = self.fetch_document(&Mymodules::default().into()).await?;
         let my_modules = {
             let document = Self::parse_document(&document)?;
 
             document
                 .select(&s("tbody tr a"))
-                .map(|link| {
-                    TryInto::<Moduledetails>::try_into(
-                        parse_tucan_url(&format!(
-                            "https://www.tucan.tu-darmstadt.de{}",
-                            link.value().attr("href").unwrap()
-                        ))
-                        .program,
-                    )
-                    .unwrap()
+                .map(|link| -> anyhow::Result<_> {
+                    parse_link::<Moduledetails>(link.value().attr("href").unwrap())
                 })
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .into_iter()
                 .map(|moduledetails| self.module(moduledetails))
                 .collect::<FuturesUnordered<_>>()
         };
@@ -1136,12 +1293,14 @@ impl TucanUser {
                         .on_conflict((user_modules::user_id, user_modules::module_id))
                         .do_nothing()
                         .execute(&mut connection)
+                        .traced()
                         .await?;
 
                     diesel::update(users_unfinished::table)
                         .filter(users_unfinished::matriculation_number.eq(matriculation_number))
                         .set(users_unfinished::user_modules_last_checked.eq(Utc::now().naive_utc()))
                         .execute(&mut connection)
+                        .traced()
                         .await?;
 
                     Ok::<(), diesel::result::Error>(())
@@ -1152,16 +1311,21 @@ impl TucanUser {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn my_modules(&self) -> anyhow::Result<Vec<Module>> {
         if let Some(value) = self.cached_my_modules().await? {
+            telemetry::cache_lookup("my_modules", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("my_modules", false);
 
         self.fetch_my_modules().await?;
+        telemetry::crawled("my_modules");
 
         Ok(self.cached_my_modules().await?.unwrap())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn my_courses(&self) -> anyhow::Result<Vec<CourseOrCourseGroup>> {
         use diesel_async::RunQueryDsl;
 
@@ -1182,6 +1346,7 @@ impl TucanUser {
                             )
                             .select(users_unfinished::user_courses_last_checked)
                             .get_result::<Option<NaiveDateTime>>(&mut connection)
+                            .traced()
                             .await?;
 
                         if user_courses_already_fetched.is_some() {
@@ -1191,6 +1356,7 @@ impl TucanUser {
                                     .inner_join(courses_unfinished::table)
                                     .select(COURSES_UNFINISHED)
                                     .load::<Course>(&mut connection)
+                                    .traced()
                                     .await?,
                             ))
                         } else {
@@ -1206,22 +1372,17 @@ impl TucanUser {
         }
         */
 
-        let document = self.fetch_document(&Profcourses.clone().into()).await?;
+        let document = self.fetch_document(&Profcourses::default().into()).await?;
         let my_courses = {
             let document = Self::parse_document(&document)?;
 
             document
                 .select(&s("tbody tr a"))
-                .map(|link| {
-                    TryInto::<Coursedetails>::try_into(
-                        parse_tucan_url(&format!(
-                            "https://www.tucan.tu-darmstadt.de{}",
-                            link.value().attr("href").unwrap()
-                        ))
-                        .program,
-                    )
-                    .unwrap()
+                .map(|link| -> anyhow::Result<_> {
+                    parse_link::<Coursedetails>(link.value().attr("href").unwrap())
                 })
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .into_iter()
                 .map(|details| self.course_or_course_group(details))
                 .collect::<FuturesUnordered<_>>()
         };
@@ -1259,6 +1420,7 @@ impl TucanUser {
                             .on_conflict((user_courses::user_id, user_courses::course_id))
                             .do_nothing()
                             .execute(&mut connection)
+                            .traced()
                             .await?;
 
                         diesel::insert_into(user_course_groups::table)
@@ -1269,6 +1431,7 @@ impl TucanUser {
                             ))
                             .do_nothing()
                             .execute(&mut connection)
+                            .traced()
                             .await?;
 
                         diesel::update(users_unfinished::table)
@@ -1278,6 +1441,7 @@ impl TucanUser {
                                     .eq(Utc::now().naive_utc()),
                             )
                             .execute(&mut connection)
+                            .traced()
                             .await?;
 
                         Ok::<(), diesel::result::Error>(())
@@ -1289,8 +1453,16 @@ impl TucanUser {
         Ok(courses_or_course_groups)
     }
 
+    /// Ends the session at TUCaN, requests with it fail afterwards.
+    #[tracing::instrument(skip(self))]
+    pub async fn logout(&self) -> anyhow::Result<()> {
+        self.fetch_document(&Logout.into()).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
     pub async fn personal_data(&self) -> anyhow::Result<UndoneUser> {
-        let document = self.fetch_document(&Persaddress.clone().into()).await?;
+        let document = self.fetch_document(&Persaddress::default().into()).await?;
         let document = Self::parse_document(&document)?;
 
         let matriculation_number: i32 = document
@@ -1305,6 +1477,7 @@ impl TucanUser {
         Ok(UndoneUser::new(matriculation_number))
     }
 
+    #[tracing::instrument(skip_all)]
     async fn cached_exam_details(
         &self,
         exam_details: Examdetails,
@@ -1317,6 +1490,7 @@ impl TucanUser {
             .filter(exams_unfinished::tucan_id.eq(&exam_details.id))
             .filter(exams_unfinished::done)
             .get_result::<Exam>(&mut connection)
+            .traced()
             .await
             .optional()?;
 
@@ -1326,6 +1500,7 @@ impl TucanUser {
                 .inner_join(modules_unfinished::table)
                 .select(MODULES_UNFINISHED)
                 .load(&mut connection)
+                .traced()
                 .await?;
 
             let course_exams: Vec<Course> = course_exams::table
@@ -1333,6 +1508,7 @@ impl TucanUser {
                 .inner_join(courses_unfinished::table)
                 .select(COURSES_UNFINISHED)
                 .load(&mut connection)
+                .traced()
                 .await?;
 
             Ok(Some((existing, module_exams, course_exams)))
@@ -1342,6 +1518,7 @@ impl TucanUser {
     }
 
     #[allow(clippy::too_many_lines)]
+    #[tracing::instrument(skip_all)]
     pub async fn fetch_exam_details(&self, exam_details: Examdetails) -> anyhow::Result<()> {
         use diesel_async::RunQueryDsl;
 
@@ -1479,20 +1656,25 @@ impl TucanUser {
             .do_update()
             .set(&exam)
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn exam_details(
         &self,
         exam_details: Examdetails,
     ) -> anyhow::Result<(Exam, Vec<Module>, Vec<Course>)> {
         if let Some(value) = self.cached_exam_details(exam_details.clone()).await? {
+            telemetry::cache_lookup("exam", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("exam", false);
 
         self.fetch_exam_details(exam_details.clone()).await?;
+        telemetry::crawled("exam");
 
         Ok(self
             .cached_exam_details(exam_details.clone())
@@ -1561,6 +1743,7 @@ impl TucanUser {
         )
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn cached_my_exams(
         &self,
     ) -> anyhow::Result<Option<(Vec<(Module, Exam)>, Vec<(Course, Exam)>)>> {
@@ -1574,6 +1757,7 @@ impl TucanUser {
             .filter(users_unfinished::matriculation_number.eq(&matriculation_number))
             .select(users_unfinished::user_exams_last_checked)
             .get_result::<Option<NaiveDateTime>>(&mut connection)
+            .traced()
             .await?;
 
         if exams_already_fetched.is_some() {
@@ -1585,6 +1769,7 @@ impl TucanUser {
                 )
                 .select((MODULES_UNFINISHED, exams_unfinished::all_columns))
                 .load::<(Module, Exam)>(&mut connection)
+                .traced()
                 .await?;
 
             let courses = user_exams::table
@@ -1595,6 +1780,7 @@ impl TucanUser {
                 )
                 .select((COURSES_UNFINISHED, exams_unfinished::all_columns))
                 .load::<(Course, Exam)>(&mut connection)
+                .traced()
                 .await?;
 
             Ok(Some((modules, courses)))
@@ -1604,6 +1790,7 @@ impl TucanUser {
     }
 
     #[allow(clippy::too_many_lines)]
+    #[tracing::instrument(skip_all)]
     async fn fetch_my_exams(&self) -> anyhow::Result<()> {
         use diesel_async::RunQueryDsl;
 
@@ -1613,12 +1800,12 @@ impl TucanUser {
         let matriculation_number = self.session.matriculation_number;
 
         let exams = {
-            let document = self.fetch_document(&Myexams.clone().into()).await?;
+            let document = self.fetch_document(&Myexams::default().into()).await?;
             let document = Self::parse_document(&document)?;
 
             document
                 .select(&s("table tbody tr"))
-                .map(|exam| {
+                .map(|exam| -> anyhow::Result<_> {
                     let selector = s(r#"td"#);
                     let mut tds = exam.select(&selector);
                     let _nr_column = tds.next().unwrap();
@@ -1631,24 +1818,24 @@ impl TucanUser {
                     let name_link = name_column.select(&s("a")).next().unwrap();
                     let date_link = date_column.select(&s("a")).next();
 
-                    let module_program = parse_tucan_url(&format!(
-                        "https://www.tucan.tu-darmstadt.de{}",
-                        module_link.value().attr("href").unwrap()
-                    ))
-                    .program;
+                    let module_or_course = match parse_link::<TucanProgram>(
+                        module_link.value().attr("href").unwrap(),
+                    )? {
+                        TucanProgram::Moduledetails(moduledetails) => Either::Left(moduledetails),
+                        TucanProgram::Coursedetails(coursedetails) => Either::Right(coursedetails),
+                        other => {
+                            return Err(anyhow::anyhow!("the link leads to {}", other.name())
+                                .context(TucanError::UnexpectedLink));
+                        }
+                    };
 
-                    let name_program = parse_tucan_url(&format!(
-                        "https://www.tucan.tu-darmstadt.de{}",
-                        name_link.value().attr("href").unwrap()
-                    ))
-                    .program;
+                    let examdetails: Examdetails =
+                        parse_link(name_link.value().attr("href").unwrap())?;
 
                     let date = date_link.map(|date| Self::parse_datetime(&date.inner_html()));
 
-                    let examdetails = TryInto::<Examdetails>::try_into(name_program).unwrap();
-
-                    (
-                        module_program,
+                    Ok((
+                        module_or_course,
                         Exam {
                             tucan_id: examdetails.id,
                             exam_type: name_link.inner_html(),
@@ -1664,9 +1851,9 @@ impl TucanUser {
                             done: false,
                         },
                         module_link.inner_html(),
-                    )
+                    ))
                 })
-                .collect_vec()
+                .collect::<anyhow::Result<Vec<_>>>()?
         };
 
         let mut connection = self.tucan.pool.get().await?;
@@ -1676,6 +1863,7 @@ impl TucanUser {
             .on_conflict(exams_unfinished::tucan_id)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(user_exams::table)
@@ -1691,11 +1879,12 @@ impl TucanUser {
             .on_conflict(user_exams::all_columns)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         let (module_exams, course_exams): (ModuleExams, CourseExams) =
             exams.into_iter().partition_map(|v| match v.0 {
-                TucanProgram::Moduledetails(moduledetails) => Either::Left((
+                Either::Left(moduledetails) => Either::Left((
                     Module {
                         tucan_id: moduledetails.id,
                         tucan_last_checked: Utc::now().naive_utc(),
@@ -1704,10 +1893,12 @@ impl TucanUser {
                         credits: None,
                         content: String::new(),
                         done: false,
+                        title_en: String::new(),
+                        content_en: String::new(),
                     },
                     v.1,
                 )),
-                TucanProgram::Coursedetails(coursedetails) => Either::Right((
+                Either::Right(coursedetails) => Either::Right((
                     Course {
                         tucan_id: coursedetails.id,
                         tucan_last_checked: Utc::now().naive_utc(),
@@ -1716,10 +1907,11 @@ impl TucanUser {
                         sws: 0,
                         content: String::new(),
                         done: false,
+                        title_en: String::new(),
+                        content_en: String::new(),
                     },
                     v.1,
                 )),
-                _ => panic!(),
             });
 
         diesel::insert_into(modules_unfinished::table)
@@ -1727,6 +1919,7 @@ impl TucanUser {
             .on_conflict(modules_unfinished::tucan_id)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(module_exams::table)
@@ -1742,6 +1935,7 @@ impl TucanUser {
             .on_conflict(module_exams::all_columns)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(courses_unfinished::table)
@@ -1749,6 +1943,7 @@ impl TucanUser {
             .on_conflict(courses_unfinished::tucan_id)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::insert_into(course_exams::table)
@@ -1764,23 +1959,29 @@ impl TucanUser {
             .on_conflict(course_exams::all_columns)
             .do_nothing()
             .execute(&mut connection)
+            .traced()
             .await?;
 
         diesel::update(users_unfinished::table)
             .filter(users_unfinished::matriculation_number.eq(matriculation_number))
             .set(users_unfinished::user_exams_last_checked.eq(Utc::now().naive_utc()))
             .execute(&mut connection)
+            .traced()
             .await?;
 
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn my_exams(&self) -> anyhow::Result<(Vec<(Module, Exam)>, Vec<(Course, Exam)>)> {
         if let Some(value) = self.cached_my_exams().await? {
+            telemetry::cache_lookup("my_exams", true);
             return Ok(value);
         }
+        telemetry::cache_lookup("my_exams", false);
 
         self.fetch_my_exams().await?;
+        telemetry::crawled("my_exams");
 
         Ok(self.cached_my_exams().await?.unwrap())
     }
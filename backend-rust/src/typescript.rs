@@ -1,14 +1,22 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use axum::{routing::MethodRouter, Router};
+use axum::{middleware::from_fn, routing::MethodRouter, Router};
+use serde_json::{json, Value};
 use tucant_derive_lib::TypescriptRoute;
 
+use crate::csrf::{require_csrf_header, CSRF_HEADER};
+
 pub struct TypescriptableApp<S: Clone + Send + Sync + 'static = ()> {
     pub app: Router<S>,
     pub codes: BTreeSet<String>,
+    /// The OpenAPI path items by path.
+    pub paths: BTreeMap<String, Value>,
+    /// The OpenAPI component schemas by name.
+    pub schemas: BTreeMap<String, Value>,
 }
 
 impl<S: Clone + Send + Sync> TypescriptableApp<S> {
+    /// Adds the route and its TypeScript client. All of these routes are protected against CSRF.
     #[must_use]
     pub fn route<TR: TypescriptRoute>(
         mut self,
@@ -16,7 +24,111 @@ impl<S: Clone + Send + Sync> TypescriptableApp<S> {
         method_router: MethodRouter<S>,
     ) -> Self {
         self.codes.extend(TR::code(path));
-        self.app = self.app.route(path, method_router);
+
+        let mut operation = TR::operation();
+        operation["parameters"] = json!([{
+            "name": CSRF_HEADER,
+            "in": "header",
+            "required": true,
+            "description": "Any value, browsers only send it for requests allowed by CORS.",
+            "schema": { "type": "string" },
+        }]);
+        self.paths
+            .insert(path.to_string(), json!({ "post": operation }));
+        self.schemas.extend(TR::schemas());
+
+        self.app = self
+            .app
+            .route(path, method_router.layer(from_fn(require_csrf_header)));
         self
     }
+
+    /// The OpenAPI 3.1 document describing all routes added with [`Self::route`].
+    #[must_use]
+    pub fn openapi(&self) -> Value {
+        json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": "tucant",
+                "description": env!("CARGO_PKG_DESCRIPTION"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "license": {
+                    "name": "AGPL-3.0-or-later",
+                    "identifier": "AGPL-3.0-or-later",
+                },
+            },
+            "paths": self.paths,
+            "components": {
+                "schemas": self.schemas,
+                "securitySchemes": {
+                    "session": { "type": "apiKey", "in": "cookie", "name": "session" },
+                },
+            },
+            "security": [{ "session": [] }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use axum::{routing::post, Json};
+    use tucant_derive::{ts, Typescriptable};
+    use tucant_derive_lib::Typescriptable;
+
+    use super::TypescriptableApp;
+    use crate::{ids::ModuleId, MyError};
+
+    #[derive(Typescriptable)]
+    #[allow(dead_code)]
+    struct Wrapper<T: Typescriptable> {
+        inner: T,
+    }
+
+    /// Returns the id.
+    #[ts]
+    #[allow(clippy::unused_async)]
+    async fn echo(input: Json<ModuleId>) -> Result<Json<ModuleId>, MyError> {
+        Ok(input)
+    }
+
+    #[test]
+    fn test_openapi() {
+        let app = TypescriptableApp::<()> {
+            app: axum::Router::new(),
+            codes: BTreeSet::new(),
+            paths: BTreeMap::new(),
+            schemas: BTreeMap::new(),
+        }
+        .route::<EchoTs>("/echo", post(echo));
+
+        let openapi = app.openapi();
+        let operation = &openapi["paths"]["/echo"]["post"];
+        assert_eq!(operation["operationId"], "echo");
+        assert_eq!(operation["description"], "Returns the id.");
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ModuleId"
+        );
+        assert_eq!(
+            operation["responses"]["default"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ApiError"
+        );
+        assert_eq!(operation["parameters"][0]["name"], "x-csrf-protection");
+        assert!(openapi["components"]["schemas"]["ApiError"]["oneOf"].is_array());
+        assert!(openapi["components"]["schemas"]["ModuleId"].is_object());
+    }
+
+    #[test]
+    fn test_generic_name() {
+        assert_eq!(
+            Wrapper::<(ModuleId, Vec<ModuleId>)>::name(),
+            "Wrapper_ModuleId_ModuleIdList"
+        );
+        assert_eq!(
+            Wrapper::<Vec<(ModuleId, Option<ModuleId>)>>::name(),
+            "Wrapper_ModuleId_ModuleIdOrNull_List"
+        );
+    }
 }
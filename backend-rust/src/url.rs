@@ -3,11 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use itertools::Itertools;
-use std::{collections::HashMap, fmt::Display, iter::Peekable};
+use std::{collections::HashMap, fmt::Display};
 
 use derive_more::{From, TryInto};
 use serde::{Deserialize, Serialize};
-use url::{Host, Origin, Url};
+use url::{form_urlencoded, Host, Origin, Url};
+
+use crate::{
+    ids::{CourseId, ExamId, MenuPath, ModuleId},
+    models::Language,
+};
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
@@ -17,67 +22,181 @@ pub struct TucanUrl {
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct StartpageDispatch;
+pub struct StartpageDispatch {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Externalpages {
-    id: u64,
-    name: String,
+    pub id: u64,
+    pub name: String,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Moduledetails {
-    pub id: Vec<u8>,
+    pub menu: u64,
+    pub id: ModuleId,
+    /// The last argument of links to modules, the page also loads without it.
+    pub checksum: Option<String>,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Coursedetails {
-    pub id: Vec<u8>,
+    pub menu: u64,
+    pub id: CourseId,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Registration {
-    pub path: Vec<u8>,
+    pub menu: u64,
+    pub path: MenuPath,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct RootRegistration {
+    pub menu: u64,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct RootRegistration {}
+pub struct Mlsstart {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Mlsstart;
+pub struct Mymodules {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Mymodules;
+pub struct Profcourses {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Profcourses;
+pub struct Studentchoicecourses {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Studentchoicecourses;
+pub struct Myexams {
+    pub menu: u64,
+    /// Only the exams of this semester, all of them if it is 999.
+    pub semester: Option<u64>,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Myexams;
+pub struct Courseresults {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Courseresults;
+pub struct Examresults {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Examresults;
+pub struct StudentResult {
+    pub menu: u64,
+}
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct StudentResult;
+pub struct Persaddress {
+    pub menu: u64,
+}
 
+/// Ends the session at TUCaN.
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
-pub struct Persaddress;
+pub struct Logout;
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Examdetails {
-    pub id: Vec<u8>,
+    pub menu: u64,
+    pub id: ExamId,
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct Courseprep {
-    pub id: i64,
+    pub menu: u64,
+    pub id: u64,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Changelanguage {
+    pub language: Language,
+}
+
+/// A program we don't know how to parse yet. `args` contains the remaining
+/// arguments after the session number.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Unknown {
+    pub prgname: String,
+    pub args: String,
+}
+
+/// The menu numbers of TUCaN's navigation, for urls we build ourselves instead of taking them
+/// from a link.
+macro_rules! default_menu {
+    ($($program:ident = $menu:literal),* $(,)?) => {
+        $(impl Default for $program {
+            fn default() -> Self {
+                Self { menu: $menu }
+            }
+        })*
+    };
+}
+
+default_menu!(
+    StartpageDispatch = 19,
+    Mlsstart = 19,
+    Mymodules = 275,
+    Profcourses = 274,
+    Studentchoicecourses = 307,
+    RootRegistration = 311,
+    Courseresults = 324,
+    Examresults = 325,
+    StudentResult = 316,
+    Persaddress = 339,
+);
+
+impl Default for Myexams {
+    fn default() -> Self {
+        Self {
+            menu: 318,
+            semester: Some(999),
+        }
+    }
+}
+
+impl Registration {
+    #[must_use]
+    pub const fn new(path: MenuPath) -> Self {
+        Self { menu: 311, path }
+    }
+}
+
+impl Moduledetails {
+    #[must_use]
+    pub const fn new(id: ModuleId) -> Self {
+        Self {
+            menu: 311,
+            id,
+            checksum: None,
+        }
+    }
+}
+
+impl Coursedetails {
+    #[must_use]
+    pub const fn new(id: CourseId) -> Self {
+        Self { menu: 311, id }
+    }
+}
+
+impl Examdetails {
+    #[must_use]
+    pub const fn new(id: ExamId) -> Self {
+        Self { menu: 318, id }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, TryInto, From)]
@@ -99,201 +218,301 @@ pub enum TucanProgram {
     Persaddress(Persaddress),
     Examdetails(Examdetails),
     Courseprep(Courseprep),
+    Changelanguage(Changelanguage),
+    Logout(Logout),
+    Unknown(Unknown),
 }
 
 impl TucanProgram {
-    #[allow(clippy::too_many_lines)]
+    /// A name for the kind of page that doesn't depend on the arguments, used as metric label.
     #[must_use]
-    pub fn to_tucan_url(&self, session_nr: Option<u64>) -> String {
-        let (progname, args): (&str, Box<dyn Iterator<Item = TucanArgument>>) = match self {
-            Self::Mlsstart(_) => todo!(),
-            Self::Mymodules(_) => (
-                "MYMODULES",
-                Box::new([TucanArgument::Number(275), TucanArgument::Number(999)].into_iter()),
-            ),
-            Self::Profcourses(_) => (
-                "PROFCOURSES",
-                Box::new([TucanArgument::Number(274), TucanArgument::Number(999)].into_iter()),
-            ),
-            Self::Studentchoicecourses(_) => todo!(),
-            Self::Registration(Registration { path }) => {
-                let mut a = path.chunks(std::mem::size_of::<u64>());
-                (
-                    "REGISTRATION",
-                    Box::new(
-                        [
-                            TucanArgument::Number(311),
-                            TucanArgument::Number(u64::from_be_bytes(
-                                a.next().unwrap().try_into().unwrap(),
-                            )),
-                            TucanArgument::Number(0),
-                            TucanArgument::Number(u64::from_be_bytes(
-                                a.next().unwrap().try_into().unwrap(),
-                            )),
-                            TucanArgument::Number(u64::from_be_bytes(
-                                a.next().unwrap().try_into().unwrap(),
-                            )),
-                        ]
-                        .into_iter(),
-                    ),
-                )
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Mlsstart(_) => "mlsstart",
+            Self::Mymodules(_) => "mymodules",
+            Self::Profcourses(_) => "profcourses",
+            Self::Studentchoicecourses(_) => "studentchoicecourses",
+            Self::Registration(_) => "registration",
+            Self::RootRegistration(_) => "root_registration",
+            Self::Myexams(_) => "myexams",
+            Self::Courseresults(_) => "courseresults",
+            Self::Examresults(_) => "examresults",
+            Self::StudentResult(_) => "student_result",
+            Self::Moduledetails(_) => "moduledetails",
+            Self::Coursedetails(_) => "coursedetails",
+            Self::StartpageDispatch(_) => "startpage_dispatch",
+            Self::Externalpages(_) => "externalpages",
+            Self::Persaddress(_) => "persaddress",
+            Self::Examdetails(_) => "examdetails",
+            Self::Courseprep(_) => "courseprep",
+            Self::Changelanguage(_) => "changelanguage",
+            Self::Logout(_) => "logout",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+
+    /// The url of the page, fails for programs that have no page on TUCaN.
+    #[allow(clippy::too_many_lines)]
+    pub fn to_tucan_url(&self, session_nr: Option<u64>) -> Result<String, UrlError> {
+        use TucanArgument::Number;
+
+        let (progname, args): (&str, Vec<TucanArgument>) = match self {
+            Self::Mlsstart(Mlsstart { menu }) => ("MLSSTART", vec![Number(*menu)]),
+            Self::Mymodules(Mymodules { menu }) => ("MYMODULES", vec![Number(*menu), Number(999)]),
+            Self::Profcourses(Profcourses { menu }) => {
+                ("PROFCOURSES", vec![Number(*menu), Number(999)])
             }
-            Self::RootRegistration(_) => (
+            Self::Studentchoicecourses(Studentchoicecourses { menu }) => {
+                ("STUDENTCHOICECOURSES", vec![Number(*menu)])
+            }
+            Self::Registration(Registration { menu, path }) => {
+                let [a, b, c] = path.parts();
+                ("REGISTRATION", [*menu, a, 0, b, c].map(Number).to_vec())
+            }
+            Self::RootRegistration(RootRegistration { menu }) => (
                 "REGISTRATION",
-                Box::new([TucanArgument::Number(311), TucanArgument::String("")].into_iter()),
+                vec![Number(*menu), TucanArgument::String("")],
             ),
-            Self::Myexams(_) => (
+            Self::Myexams(Myexams { menu, semester }) => (
                 "MYEXAMS",
-                Box::new([TucanArgument::Number(318), TucanArgument::Number(999)].into_iter()),
+                std::iter::once(*menu)
+                    .chain(*semester)
+                    .map(Number)
+                    .collect(),
+            ),
+            Self::Courseresults(Courseresults { menu }) => ("COURSERESULTS", vec![Number(*menu)]),
+            Self::Examresults(Examresults { menu }) => ("EXAMRESULTS", vec![Number(*menu)]),
+            Self::StudentResult(StudentResult { menu }) => (
+                "STUDENT_RESULT",
+                [*menu, 0, 0, 0, 0, 0, 0].map(Number).to_vec(),
             ),
-            Self::Courseresults(_) => todo!(),
-            Self::Examresults(_) => todo!(),
-            Self::StudentResult(_) => todo!(),
-            Self::Moduledetails(Moduledetails { id }) => (
+            Self::Moduledetails(Moduledetails { menu, id, checksum }) => (
                 "MODULEDETAILS",
-                Box::new(
-                    [
-                        TucanArgument::Number(311),
-                        TucanArgument::Number(u64::from_be_bytes(
-                            id.as_slice().try_into().unwrap(),
-                        )),
-                    ]
-                    .into_iter(),
-                ),
+                [
+                    Number(*menu),
+                    Number(
+                        id.number()
+                            .ok_or_else(|| UrlError::NoTucanPage(format!("module {id}")))?,
+                    ),
+                ]
+                .into_iter()
+                .chain(checksum.as_deref().map(TucanArgument::String))
+                .collect(),
             ),
-            Self::Coursedetails(Coursedetails { id }) => (
+            Self::Coursedetails(Coursedetails { menu, id }) => (
                 "COURSEDETAILS",
-                Box::new(
-                    [TucanArgument::Number(311), TucanArgument::Number(0)]
-                        .into_iter()
-                        .chain(id.chunks(std::mem::size_of::<u64>()).map(|n| {
-                            TucanArgument::Number(u64::from_be_bytes(n.try_into().unwrap()))
-                        }))
-                        .chain([TucanArgument::Number(0), TucanArgument::Number(0)].into_iter()),
-                ),
+                [*menu, 0, id.course(), id.group(), 0, 0]
+                    .map(Number)
+                    .to_vec(),
             ),
-            Self::Persaddress(_) => (
-                "PERSADDRESS",
-                Box::new(std::iter::once(TucanArgument::Number(339))),
-            ),
-            Self::StartpageDispatch(_) => todo!(),
-            Self::Externalpages(_) => todo!(),
-            Self::Examdetails(Examdetails { id }) => {
-                let mut a = id.chunks(std::mem::size_of::<u64>());
-                (
-                    "EXAMDETAILS",
-                    Box::new(
-                        [
-                            TucanArgument::Number(318),
-                            TucanArgument::Number(u64::from_be_bytes(
-                                a.next().unwrap().try_into().unwrap(),
-                            )),
-                        ]
-                        .into_iter(),
-                    ),
-                )
+            Self::Persaddress(Persaddress { menu }) => ("PERSADDRESS", vec![Number(*menu)]),
+            Self::StartpageDispatch(StartpageDispatch { menu }) => {
+                ("STARTPAGE_DISPATCH", vec![Number(*menu), Number(0)])
             }
-            Self::Courseprep(Courseprep { id }) => (
-                "COURSEPREP",
-                Box::new(
-                    [
-                        TucanArgument::Number(318),
-                        TucanArgument::Number(0),
-                        TucanArgument::Number((*id).try_into().unwrap()),
-                    ]
-                    .into_iter(),
-                ),
+            Self::Externalpages(Externalpages { id, name }) => (
+                "EXTERNALPAGES",
+                vec![Number(*id), TucanArgument::String(name)],
             ),
+            Self::Examdetails(Examdetails { menu, id }) => {
+                ("EXAMDETAILS", vec![Number(*menu), Number(id.number())])
+            }
+            Self::Courseprep(Courseprep { menu, id }) => {
+                ("COURSEPREP", vec![Number(*menu), Number(0), Number(*id)])
+            }
+            Self::Changelanguage(Changelanguage { language }) => {
+                ("CHANGELANGUAGE", vec![Number(language.tucan_id())])
+            }
+            Self::Logout(_) => ("LOGOUT", vec![Number(1)]),
+            Self::Unknown(Unknown { prgname, args }) => {
+                return Ok(format!(
+                    "https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME={}&ARGUMENTS=-N{},{args}",
+                    form_urlencoded::byte_serialize(prgname.as_bytes()).collect::<String>(),
+                    session_nr.unwrap_or(1)
+                ));
+            }
         };
-        let args = args.format(",");
+        let args = args.iter().format(",");
 
-        format!("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME={progname}&ARGUMENTS=-N{},{args}", session_nr.unwrap_or(1))
+        Ok(format!("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME={progname}&ARGUMENTS=-N{},{args}", session_nr.unwrap_or(1)))
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TucanArgument<'a> {
     Number(u64),
     String(&'a str),
 }
 
-impl<'a> TucanArgument<'a> {
-    #[must_use]
-    pub fn number(&self) -> u64 {
+impl Display for TucanArgument<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TucanArgument::Number(number) => *number,
-            TucanArgument::String(_) => panic!(),
+            TucanArgument::Number(v) => write!(f, "-N{v}"),
+            TucanArgument::String(v) => write!(
+                f,
+                "-A{}",
+                form_urlencoded::byte_serialize(v.as_bytes()).collect::<String>()
+            ),
         }
     }
+}
 
-    #[must_use]
-    pub fn string(&self) -> &'a str {
+/// Why a url could not be parsed as a [`TucanUrl`] or built from a [`TucanProgram`].
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum UrlError {
+    InvalidUrl(url::ParseError),
+    InvalidOrigin(String),
+    InvalidPath(String),
+    MissingQueryParameter(&'static str),
+    InvalidAppname(String),
+    InvalidArgument(String),
+    MissingArgument,
+    UnexpectedArgument {
+        expected: &'static str,
+        found: String,
+    },
+    TooManyArguments(String),
+    /// The program can't be turned into a url, like the module for courses without one.
+    NoTucanPage(String),
+}
+
+impl Display for UrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TucanArgument::String(string) => string,
-            TucanArgument::Number(_) => panic!(),
+            Self::InvalidUrl(err) => write!(f, "invalid url: {err}"),
+            Self::InvalidOrigin(origin) => write!(f, "not a tucan url: {origin}"),
+            Self::InvalidPath(path) => write!(f, "invalid path: {path}"),
+            Self::MissingQueryParameter(name) => write!(f, "missing query parameter {name}"),
+            Self::InvalidAppname(app_name) => write!(f, "invalid appname: {app_name}"),
+            Self::InvalidArgument(argument) => write!(f, "invalid argument: {argument}"),
+            Self::MissingArgument => write!(f, "missing argument"),
+            Self::UnexpectedArgument { expected, found } => {
+                write!(f, "expected {expected} but found {found}")
+            }
+            Self::TooManyArguments(rest) => write!(f, "too many arguments: {rest}"),
+            Self::NoTucanPage(program) => write!(f, "{program} has no page on TUCaN"),
         }
     }
 }
 
-impl Display for TucanArgument<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl std::error::Error for UrlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            TucanArgument::Number(v) => write!(f, "-N{v}"),
-            TucanArgument::String(v) => write!(f, "-A{v}"),
+            Self::InvalidUrl(err) => Some(err),
+            _ => None,
         }
     }
 }
 
-pub fn parse_arguments(
-    arguments: &str,
-) -> Peekable<impl Iterator<Item = TucanArgument> + std::fmt::Debug> {
+impl From<url::ParseError> for UrlError {
+    fn from(value: url::ParseError) -> Self {
+        Self::InvalidUrl(value)
+    }
+}
+
+pub fn parse_arguments(arguments: &str) -> Result<Vec<TucanArgument<'_>>, UrlError> {
     arguments
         .split_terminator(',')
         .map(|a| match a.get(0..2) {
-            Some("-N") => TucanArgument::Number(a[2..].parse().unwrap()),
-            Some("-A") => TucanArgument::String(&a[2..]),
-            _ => panic!(),
+            Some("-N") => a[2..]
+                .parse()
+                .map(TucanArgument::Number)
+                .map_err(|_| UrlError::InvalidArgument(a.to_string())),
+            Some("-A") => Ok(TucanArgument::String(&a[2..])),
+            _ => Err(UrlError::InvalidArgument(a.to_string())),
         })
-        .peekable()
+        .collect()
 }
 
-fn number<'a>(arguments: &mut (impl Iterator<Item = TucanArgument<'a>> + std::fmt::Debug)) -> u64 {
-    arguments.next().unwrap().number()
+fn number<'a>(arguments: &mut impl Iterator<Item = TucanArgument<'a>>) -> Result<u64, UrlError> {
+    match arguments.next() {
+        Some(TucanArgument::Number(number)) => Ok(number),
+        Some(other) => Err(UrlError::UnexpectedArgument {
+            expected: "a number",
+            found: other.to_string(),
+        }),
+        None => Err(UrlError::MissingArgument),
+    }
 }
 
 fn string<'a>(
-    arguments: &mut (impl Iterator<Item = TucanArgument<'a>> + std::fmt::Debug),
-) -> &'a str {
-    arguments.next().unwrap().string()
+    arguments: &mut impl Iterator<Item = TucanArgument<'a>>,
+) -> Result<&'a str, UrlError> {
+    match arguments.next() {
+        Some(TucanArgument::String(string)) => Ok(string),
+        Some(other) => Err(UrlError::UnexpectedArgument {
+            expected: "a string",
+            found: other.to_string(),
+        }),
+        None => Err(UrlError::MissingArgument),
+    }
+}
+
+fn expect<'a>(
+    arguments: &mut impl Iterator<Item = TucanArgument<'a>>,
+    expected: &'static str,
+    predicate: impl FnOnce(TucanArgument<'a>) -> bool,
+) -> Result<(), UrlError> {
+    match arguments.next() {
+        Some(argument) if predicate(argument) => Ok(()),
+        Some(other) => Err(UrlError::UnexpectedArgument {
+            expected,
+            found: other.to_string(),
+        }),
+        None => Err(UrlError::MissingArgument),
+    }
+}
+
+fn expect_number<'a>(
+    arguments: &mut impl Iterator<Item = TucanArgument<'a>>,
+    expected: &'static str,
+    predicate: impl FnOnce(u64) -> bool,
+) -> Result<(), UrlError> {
+    expect(
+        arguments,
+        expected,
+        |a| matches!(a, TucanArgument::Number(n) if predicate(n)),
+    )
 }
 
 #[allow(clippy::too_many_lines)]
 #[allow(clippy::cognitive_complexity)]
 #[allow(clippy::module_name_repetitions)]
-#[must_use]
-pub fn parse_tucan_url(url: &str) -> TucanUrl {
-    let url = Url::parse(url).unwrap();
-    assert_eq!(
-        url.origin(),
-        Origin::Tuple(
+pub fn parse_tucan_url(url: &str) -> Result<TucanUrl, UrlError> {
+    let url = Url::parse(url)?;
+    let origin = url.origin();
+    if origin
+        != Origin::Tuple(
             "https".into(),
             Host::Domain("www.tucan.tu-darmstadt.de".into()),
             443,
         )
-    );
-    assert_eq!(url.path(), "/scripts/mgrqispi.dll");
+    {
+        return Err(UrlError::InvalidOrigin(origin.ascii_serialization()));
+    }
+    if url.path() != "/scripts/mgrqispi.dll" {
+        return Err(UrlError::InvalidPath(url.path().to_string()));
+    }
     let query_pairs = url.query_pairs();
     let query_pairs = query_pairs.collect::<HashMap<_, _>>();
-    let app_name = query_pairs.get("APPNAME").unwrap().as_ref();
-    let arguments = query_pairs.get("ARGUMENTS").unwrap().as_ref();
-    let prgname = query_pairs.get("PRGNAME").unwrap().as_ref();
-    let mut arguments = parse_arguments(arguments);
-    assert_eq!(app_name, "CampusNet");
+    let query_pair = |name| {
+        query_pairs
+            .get(name)
+            .map(AsRef::as_ref)
+            .ok_or(UrlError::MissingQueryParameter(name))
+    };
+    let app_name = query_pair("APPNAME")?;
+    let arguments = query_pair("ARGUMENTS")?;
+    let prgname = query_pair("PRGNAME")?;
+    if app_name != "CampusNet" {
+        return Err(UrlError::InvalidAppname(app_name.to_string()));
+    }
+    let mut arguments = parse_arguments(arguments)?.into_iter().peekable();
 
     let session_nr = if prgname == "ACTION" {
         1
     } else {
-        number(&mut arguments)
+        number(&mut arguments)?
     };
     let session_nr = if session_nr == 1 {
         None
@@ -303,200 +522,378 @@ pub fn parse_tucan_url(url: &str) -> TucanUrl {
 
     let program = match prgname {
         "STARTPAGE_DISPATCH" => {
-            number(&mut arguments);
-            assert_eq!(number(&mut arguments), 0);
-
-            TucanProgram::StartpageDispatch(StartpageDispatch)
+            let menu = number(&mut arguments)?;
+            expect_number(&mut arguments, "0", |n| n == 0)?;
+            TucanProgram::StartpageDispatch(StartpageDispatch { menu })
         }
         "EXTERNALPAGES" => TucanProgram::Externalpages(Externalpages {
-            id: number(&mut arguments),
-            name: string(&mut arguments).to_string(),
+            id: number(&mut arguments)?,
+            name: string(&mut arguments)?.to_string(),
+        }),
+        "MLSSTART" => TucanProgram::Mlsstart(Mlsstart {
+            menu: number(&mut arguments)?,
         }),
-        "MLSSTART" => {
-            number(&mut arguments);
-            TucanProgram::Mlsstart(Mlsstart)
-        }
         "MYMODULES" => {
-            number(&mut arguments);
-            assert_eq!(number(&mut arguments), 999);
-            TucanProgram::Mymodules(Mymodules)
+            let menu = number(&mut arguments)?;
+            expect_number(&mut arguments, "999", |n| n == 999)?;
+            TucanProgram::Mymodules(Mymodules { menu })
         }
         "PROFCOURSES" => {
-            number(&mut arguments);
-            assert_eq!(number(&mut arguments), 999);
-            TucanProgram::Profcourses(Profcourses)
-        }
-        "STUDENTCHOICECOURSES" => {
-            number(&mut arguments);
-            TucanProgram::Studentchoicecourses(Studentchoicecourses)
+            let menu = number(&mut arguments)?;
+            expect_number(&mut arguments, "999", |n| n == 999)?;
+            TucanProgram::Profcourses(Profcourses { menu })
         }
+        "STUDENTCHOICECOURSES" => TucanProgram::Studentchoicecourses(Studentchoicecourses {
+            menu: number(&mut arguments)?,
+        }),
         "REGISTRATION" => {
-            number(&mut arguments);
-            match arguments.peek().unwrap() {
+            let menu = number(&mut arguments)?;
+            match arguments.peek().ok_or(UrlError::MissingArgument)? {
                 TucanArgument::Number(_) => {
-                    let a = number(&mut arguments).to_be_bytes();
-                    assert_eq!(number(&mut arguments), 0);
-                    let b = number(&mut arguments).to_be_bytes();
-                    let c = number(&mut arguments).to_be_bytes();
+                    let a = number(&mut arguments)?;
+                    expect_number(&mut arguments, "0", |n| n == 0)?;
+                    let b = number(&mut arguments)?;
+                    let c = number(&mut arguments)?;
                     TucanProgram::Registration(Registration {
-                        path: vec![a, b, c].concat(),
+                        menu,
+                        path: MenuPath::new([a, b, c]),
                     })
                 }
                 TucanArgument::String(_) => {
-                    assert_eq!(string(&mut arguments), "");
-                    TucanProgram::RootRegistration(RootRegistration {})
+                    expect(&mut arguments, "an empty string", |a| {
+                        a == TucanArgument::String("")
+                    })?;
+                    TucanProgram::RootRegistration(RootRegistration { menu })
                 }
             }
         }
         "MYEXAMS" => {
-            number(&mut arguments);
-            TucanProgram::Myexams(Myexams)
-        }
-        "COURSERESULTS" => {
-            number(&mut arguments);
-            TucanProgram::Courseresults(Courseresults)
-        }
-        "EXAMRESULTS" => {
-            number(&mut arguments);
-            TucanProgram::Examresults(Examresults)
+            let menu = number(&mut arguments)?;
+            let semester = match arguments.peek() {
+                Some(TucanArgument::Number(_)) => Some(number(&mut arguments)?),
+                _ => None,
+            };
+            TucanProgram::Myexams(Myexams { menu, semester })
         }
+        "COURSERESULTS" => TucanProgram::Courseresults(Courseresults {
+            menu: number(&mut arguments)?,
+        }),
+        "EXAMRESULTS" => TucanProgram::Examresults(Examresults {
+            menu: number(&mut arguments)?,
+        }),
         "STUDENT_RESULT" => {
-            number(&mut arguments);
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            TucanProgram::StudentResult(StudentResult)
+            let menu = number(&mut arguments)?;
+            for _ in 0..6 {
+                expect_number(&mut arguments, "0", |n| n == 0)?;
+            }
+            TucanProgram::StudentResult(StudentResult { menu })
         }
         "MODULEDETAILS" => {
-            number(&mut arguments);
-            let program = TucanProgram::Moduledetails(Moduledetails {
-                id: number(&mut arguments).to_be_bytes().to_vec(),
-            });
-            string(&mut arguments);
-            program
+            let menu = number(&mut arguments)?;
+            let id = ModuleId::new(number(&mut arguments)?);
+            let checksum = match arguments.peek() {
+                Some(TucanArgument::String(_)) => Some(string(&mut arguments)?.to_string()),
+                _ => None,
+            };
+            TucanProgram::Moduledetails(Moduledetails { menu, id, checksum })
         }
         "COURSEDETAILS" => {
-            number(&mut arguments);
-            assert!([0, 376_333_755_785_484].contains(&number(&mut arguments)));
+            let menu = number(&mut arguments)?;
+            expect_number(&mut arguments, "0 or 376333755785484", |n| {
+                [0, 376_333_755_785_484].contains(&n)
+            })?;
+            let course = number(&mut arguments)?;
+            let group = number(&mut arguments)?;
             let prog = TucanProgram::Coursedetails(Coursedetails {
-                id: vec![
-                    number(&mut arguments).to_be_bytes(), // this *should* be unique per course
-                    number(&mut arguments).to_be_bytes(), // this *should* be different per sub-group in a course and the course itself - but you can't find out which of these is the top-level course page without fetching
-                ]
-                .concat(),
+                menu,
+                id: CourseId::new(course, group),
             });
-            assert_eq!(number(&mut arguments), 0);
-            assert_eq!(number(&mut arguments), 0);
-            assert!([
-                Some(TucanArgument::Number(0)),
-                Some(TucanArgument::Number(3)),
-                None
-            ]
-            .contains(&arguments.next()));
-            assert!(matches!(
-                arguments.next(),
-                None | Some(TucanArgument::String(_))
-            ));
+            expect_number(&mut arguments, "0", |n| n == 0)?;
+            expect_number(&mut arguments, "0", |n| n == 0)?;
+            if arguments.peek().is_some() {
+                expect_number(&mut arguments, "0 or 3", |n| [0, 3].contains(&n))?;
+            }
+            if arguments.peek().is_some() {
+                string(&mut arguments)?;
+            }
             prog
         }
         "PERSADDRESS" => {
-            number(&mut arguments);
-            assert!(matches!(
-                arguments.next(),
-                None | Some(TucanArgument::String(_))
-            ));
-            TucanProgram::Persaddress(Persaddress)
+            let menu = number(&mut arguments)?;
+            if arguments.peek().is_some() {
+                string(&mut arguments)?;
+            }
+            TucanProgram::Persaddress(Persaddress { menu })
         }
         "EXAMDETAILS" => {
-            assert!(matches!(arguments.next(), Some(TucanArgument::Number(318))));
-            let id = number(&mut arguments).to_be_bytes();
-            assert!(matches!(arguments.next(), Some(TucanArgument::Number(0))));
-            assert!(matches!(arguments.next(), Some(TucanArgument::String("M"))));
-            number(&mut arguments); // nobody knows what this is
-            TucanProgram::Examdetails(Examdetails { id: id.to_vec() })
+            let menu = number(&mut arguments)?;
+            let id = ExamId::new(number(&mut arguments)?);
+            if arguments.peek().is_some() {
+                expect_number(&mut arguments, "0", |n| n == 0)?;
+                expect(&mut arguments, "M", |a| a == TucanArgument::String("M"))?;
+                number(&mut arguments)?; // nobody knows what this is
+            }
+            TucanProgram::Examdetails(Examdetails { menu, id })
         }
         "COURSEPREP" => {
-            assert!(matches!(arguments.next(), Some(TucanArgument::Number(318))));
-            assert!(matches!(arguments.next(), Some(TucanArgument::Number(0))));
-            let id = number(&mut arguments);
+            let menu = number(&mut arguments)?;
+            expect_number(&mut arguments, "0", |n| n == 0)?;
             TucanProgram::Courseprep(Courseprep {
-                id: id.try_into().unwrap(),
+                menu,
+                id: number(&mut arguments)?,
             })
         }
-        other => {
-            panic!("invalid appname: {other}");
+        "CHANGELANGUAGE" => {
+            let id = number(&mut arguments)?;
+            TucanProgram::Changelanguage(Changelanguage {
+                language: Language::from_tucan_id(id).ok_or_else(|| {
+                    UrlError::UnexpectedArgument {
+                        expected: "a language id",
+                        found: TucanArgument::Number(id).to_string(),
+                    }
+                })?,
+            })
+        }
+        "LOGOUT" => {
+            expect_number(&mut arguments, "1", |n| n == 1)?;
+            TucanProgram::Logout(Logout)
         }
+        other => TucanProgram::Unknown(Unknown {
+            prgname: other.to_string(),
+            args: arguments.join(","),
+        }),
     };
 
-    assert!(
-        arguments.peek().is_none(),
-        "too many arguments while parsing {} {:?}",
-        prgname,
-        arguments.collect::<Vec<_>>()
-    );
+    if arguments.peek().is_some() {
+        return Err(UrlError::TooManyArguments(arguments.join(",")));
+    }
 
-    TucanUrl {
+    Ok(TucanUrl {
         session_nr,
         program,
-    }
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_tucan_url;
+    use std::collections::HashMap;
+
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use url::Url;
+
+    use crate::{
+        ids::{CourseId, ExamId, MenuPath, ModuleId},
+        models::Language,
+    };
+
+    use super::{
+        parse_arguments, parse_tucan_url, Changelanguage, Coursedetails, Courseprep, Courseresults,
+        Examdetails, Examresults, Externalpages, Logout, Mlsstart, Moduledetails, Myexams,
+        Mymodules, Persaddress, Profcourses, Registration, RootRegistration, StartpageDispatch,
+        StudentResult, Studentchoicecourses, TucanProgram, TucanUrl, Unknown, UrlError,
+    };
+
+    fn tucan_program() -> impl Strategy<Value = TucanProgram> {
+        let menu = any::<u64>;
+        prop_oneof![
+            menu().prop_map(|menu| Mlsstart { menu }.into()),
+            menu().prop_map(|menu| Mymodules { menu }.into()),
+            menu().prop_map(|menu| Profcourses { menu }.into()),
+            menu().prop_map(|menu| Studentchoicecourses { menu }.into()),
+            (menu(), any::<[u64; 3]>()).prop_map(|(menu, path)| Registration {
+                menu,
+                path: MenuPath::new(path),
+            }
+            .into()),
+            menu().prop_map(|menu| RootRegistration { menu }.into()),
+            (menu(), proptest::option::of(any::<u64>())).prop_map(|(menu, semester)| Myexams {
+                menu,
+                semester
+            }
+            .into()),
+            menu().prop_map(|menu| Courseresults { menu }.into()),
+            menu().prop_map(|menu| Examresults { menu }.into()),
+            menu().prop_map(|menu| StudentResult { menu }.into()),
+            // commas separate the arguments so they can't be part of a checksum
+            (menu(), any::<u64>(), proptest::option::of("[^,]*")).prop_map(
+                |(menu, id, checksum)| Moduledetails {
+                    menu,
+                    id: ModuleId::new(id),
+                    checksum,
+                }
+                .into()
+            ),
+            (menu(), any::<(u64, u64)>()).prop_map(|(menu, (course, group))| Coursedetails {
+                menu,
+                id: CourseId::new(course, group),
+            }
+            .into()),
+            menu().prop_map(|menu| StartpageDispatch { menu }.into()),
+            (any::<u64>(), "[^,]*").prop_map(|(id, name)| Externalpages { id, name }.into()),
+            menu().prop_map(|menu| Persaddress { menu }.into()),
+            (menu(), any::<u64>()).prop_map(|(menu, id)| Examdetails {
+                menu,
+                id: ExamId::new(id),
+            }
+            .into()),
+            (menu(), any::<u64>()).prop_map(|(menu, id)| Courseprep { menu, id }.into()),
+            prop_oneof![Just(Language::German), Just(Language::English)]
+                .prop_map(|language| Changelanguage { language }.into()),
+            Just(Logout.into()),
+        ]
+    }
+
+    /// The program and the arguments of a url, so urls that only differ in how many zeros the
+    /// numbers are padded with compare equal.
+    fn normalize(url: &str) -> (String, String) {
+        let url = Url::parse(url).unwrap();
+        let query = url.query_pairs().collect::<HashMap<_, _>>();
+        (
+            query["PRGNAME"].to_string(),
+            parse_arguments(&query["ARGUMENTS"])
+                .unwrap()
+                .iter()
+                .join(","),
+        )
+    }
+
+    /// Parses a url taken from TUCaN and checks that it is built the same way again.
+    fn parse_sample(url: &str) -> TucanUrl {
+        let parsed = parse_tucan_url(url).unwrap();
+        let regenerated = parsed.program.to_tucan_url(parsed.session_nr).unwrap();
+        assert_eq!(normalize(&regenerated), normalize(url));
+        parsed
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(program in tucan_program(), session_nr in proptest::option::of(2..=u64::MAX)) {
+            let url = program.to_tucan_url(session_nr).unwrap();
+            prop_assert_eq!(parse_tucan_url(&url), Ok(TucanUrl { session_nr, program }));
+        }
+
+        #[test]
+        fn test_no_panic(url in "https://www\\.tucan\\.tu-darmstadt\\.de/scripts/mgrqispi\\.dll\\?APPNAME=CampusNet&PRGNAME=[A-Z_]{0,20}&ARGUMENTS=(-[NA][0-9A-Za-z]{0,20},?){0,8}") {
+            let _ = parse_tucan_url(&url);
+        }
+    }
+
+    #[test]
+    fn test_unknown_program() {
+        assert_eq!(
+            parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=SCHEDULER&ARGUMENTS=-N428926119975172,-N000268,-A,-A,-N1"),
+            Ok(TucanUrl {
+                session_nr: Some(428_926_119_975_172),
+                program: Unknown {
+                    prgname: "SCHEDULER".to_string(),
+                    args: "-N268,-A,-A,-N1".to_string(),
+                }
+                .into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        assert!(matches!(
+            parse_tucan_url("not a url"),
+            Err(UrlError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            parse_tucan_url("https://example.org/scripts/mgrqispi.dll"),
+            Err(UrlError::InvalidOrigin(_))
+        ));
+        assert_eq!(
+            parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MLSSTART"),
+            Err(UrlError::MissingQueryParameter("ARGUMENTS"))
+        );
+        assert_eq!(
+            parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MLSSTART&ARGUMENTS=-N1,-X19"),
+            Err(UrlError::InvalidArgument("-X19".to_string()))
+        );
+        assert_eq!(
+            parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MYMODULES&ARGUMENTS=-N1,-N275"),
+            Err(UrlError::MissingArgument)
+        );
+        assert_eq!(
+            parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MLSSTART&ARGUMENTS=-N1,-N19,-N0"),
+            Err(UrlError::TooManyArguments("-N0".to_string()))
+        );
+    }
 
     #[test]
     fn test_sample_urls() {
         // unauthenticated start page
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N000000000000001,-N000344,-Awelcome");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N000000000000001,-N000344,-Awelcome");
 
         // authenticated start page
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MLSSTART&ARGUMENTS=-N707546050471776,-N000019,");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MLSSTART&ARGUMENTS=-N707546050471776,-N000019,");
+
+        // logout button
+        let url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=LOGOUT&ARGUMENTS=-N707546050471776,-N001");
+        assert_eq!(url.program, Logout.into());
 
         // Veranstaltungen
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000273,-Astudveranst%2Ehtml");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000273,-Astudveranst%2Ehtml");
 
         // Veranstaltungen -> Meine Module
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MYMODULES&ARGUMENTS=-N428926119975172,-N000275,-N999");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MYMODULES&ARGUMENTS=-N428926119975172,-N000275,-N999");
 
         // Veranstaltungen -> Meine Veranstaltungen
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=PROFCOURSES&ARGUMENTS=-N428926119975172,-N000274,-N999");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=PROFCOURSES&ARGUMENTS=-N428926119975172,-N000274,-N999");
 
         // Veranstaltungen -> Meine Wahlbereiche
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=STUDENTCHOICECOURSES&ARGUMENTS=-N428926119975172,-N000307,");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=STUDENTCHOICECOURSES&ARGUMENTS=-N428926119975172,-N000307,");
 
         // Veranstaltungen -> Anmeldung
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N428926119975172,-N000311,-A");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N428926119975172,-N000311,-A");
 
         // Veranstaltungen -> Anmeldung -> Pflichtbereich
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N988222970824392,-N000311,-N376333755785484,-N0,-N356173456785530,-N000000000000000");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=REGISTRATION&ARGUMENTS=-N988222970824392,-N000311,-N376333755785484,-N0,-N356173456785530,-N000000000000000");
 
         // Prüfungen
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000280,-Astudpruefungen%2Ehtml");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000280,-Astudpruefungen%2Ehtml");
 
         // Prüfungen -> Meine Prüfungen
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MYEXAMS&ARGUMENTS=-N428926119975172,-N000318,");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MYEXAMS&ARGUMENTS=-N428926119975172,-N000318,");
 
         // Prüfungen -> Semesterergebnisse
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000323,-Astudergebnis%2Ehtml");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXTERNALPAGES&ARGUMENTS=-N428926119975172,-N000323,-Astudergebnis%2Ehtml");
 
         // Prüfungen -> Semesterergebnisse -> Modulergebnisse
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSERESULTS&ARGUMENTS=-N428926119975172,-N000324,");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSERESULTS&ARGUMENTS=-N428926119975172,-N000324,");
 
         // Prüfungen -> Semesterergebnisse -> Prüfungsergebnisse
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXAMRESULTS&ARGUMENTS=-N428926119975172,-N000325,");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=EXAMRESULTS&ARGUMENTS=-N428926119975172,-N000325,");
 
         // Prüfungen -> Leistungsspiegel
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=STUDENT_RESULT&ARGUMENTS=-N428926119975172,-N000316,-N0,-N000000000000000,-N000000000000000,-N000000000000000,-N0,-N000000000000000");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=STUDENT_RESULT&ARGUMENTS=-N428926119975172,-N000316,-N0,-N000000000000000,-N000000000000000,-N000000000000000,-N0,-N000000000000000");
 
         // Moduldetails
 
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MODULEDETAILS&ARGUMENTS=-N988222970824392,-N000311,-N376373060881867,-A3f5EHWl9PqwMeD2AvWmMWDl-QUpCmjaN7ZKJmNFt7-UpvMAx4omKmd6gmUR9mfft3oRQP-PaxNZtPqGdRUpsOZmeQNHv7URzmQVdOBBF3SftxMo8PU5S7dwZfbZYmdPfQd5ycYntWopZmoUBYDotPMPNmkZdPILZ7gmT4SPXHjV-cBUxxNPWR-m9QkZLvUovfgPXvqR5YBG-eZo8WqmAHjHfeMpqRkZ97DKZQIo5PfP9HSRBeqAHvDZjrUUeHWV6xZR7YIL3OuULPQHHVNK8f-5wvZ5kYUUvYWlNQoljQIU5eUBjHDPmmZLb4YGhPIUTmuWXYfnAvfWAYWW54D6hQ-58HWPpmQBNWqeFYM5HvDUgcupLmMfAxM5D4MoAcuopQuPjfYHvfqLqeqwZeMWXVDZjPMHVcocZcNmt7ZDjQZedWfmyfDWUWSAeHDajVdmUOjBtmNWpvqP9OqG3VNHlPQPKvocZWqZYVWoxfSLlcDPQQWKZegeNQY5afu5COzH-fDoKWU79CQoErUPHYDHVQtin");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MODULEDETAILS&ARGUMENTS=-N988222970824392,-N000311,-N376373060881867,-A3f5EHWl9PqwMeD2AvWmMWDl-QUpCmjaN7ZKJmNFt7-UpvMAx4omKmd6gmUR9mfft3oRQP-PaxNZtPqGdRUpsOZmeQNHv7URzmQVdOBBF3SftxMo8PU5S7dwZfbZYmdPfQd5ycYntWopZmoUBYDotPMPNmkZdPILZ7gmT4SPXHjV-cBUxxNPWR-m9QkZLvUovfgPXvqR5YBG-eZo8WqmAHjHfeMpqRkZ97DKZQIo5PfP9HSRBeqAHvDZjrUUeHWV6xZR7YIL3OuULPQHHVNK8f-5wvZ5kYUUvYWlNQoljQIU5eUBjHDPmmZLb4YGhPIUTmuWXYfnAvfWAYWW54D6hQ-58HWPpmQBNWqeFYM5HvDUgcupLmMfAxM5D4MoAcuopQuPjfYHvfqLqeqwZeMWXVDZjPMHVcocZcNmt7ZDjQZedWfmyfDWUWSAeHDajVdmUOjBtmNWpvqP9OqG3VNHlPQPKvocZWqZYVWoxfSLlcDPQQWKZegeNQY5afu5COzH-fDoKWU79CQoErUPHYDHVQtin");
 
         // Kursdetails
-        let _url = parse_tucan_url("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N967307082288504,-N000311,-N0,-N379144023730730,-N379144023752731,-N0,-N0");
+        let _url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=COURSEDETAILS&ARGUMENTS=-N967307082288504,-N000311,-N0,-N379144023730730,-N379144023752731,-N0,-N0");
+    }
+
+    #[test]
+    fn test_module_checksum() {
+        let url = parse_sample("https://www.tucan.tu-darmstadt.de/scripts/mgrqispi.dll?APPNAME=CampusNet&PRGNAME=MODULEDETAILS&ARGUMENTS=-N988222970824392,-N000311,-N376373060881867,-Aabc-DEF");
+        assert_eq!(
+            url.program,
+            Moduledetails {
+                menu: 311,
+                id: ModuleId::new(376_373_060_881_867),
+                checksum: Some("abc-DEF".to_string()),
+            }
+            .into()
+        );
+
+        assert_eq!(
+            TucanProgram::from(Moduledetails::new(ModuleId::tucanscheiss())).to_tucan_url(None),
+            Err(UrlError::NoTucanPage("module TUCANSCHEISS".to_string()))
+        );
     }
 }
@@ -20,10 +20,10 @@ import Credits from "./routes/Credits";
 import { SearchCourses } from "./routes/SearchCourses";
 import { NavigationContext } from "./NavigationContext";
 import MyCourses from "./routes/MyCourses";
-import { SearchModulesOpenSearch } from "./routes/SearchModulesOpenSearch";
 import MyExams from "./routes/MyExams";
 import Exam from "./routes/Exam";
 import CourseGroup from "./routes/CourseGroup";
+import Account from "./routes/Account";
 
 function App() {
   const [isLoading, startTransition] = useTransition();
@@ -44,15 +44,12 @@ function App() {
                 <Route path="logout" element={<Logout />} />
                 <Route path="search-modules" element={<SearchModules />} />
                 <Route path="my-exams" element={<MyExams />} />
-                <Route
-                  path="search-modules-opensearch"
-                  element={<SearchModulesOpenSearch />}
-                />
                 <Route path="module/:id" element={<Module />} />
                 <Route path="course/:id" element={<Course />} />
                 <Route path="course-group/:id" element={<CourseGroup />} />
                 <Route path="exam/:id" element={<Exam />} />
                 <Route path="credits" element={<Credits />} />
+                <Route path="account" element={<Account />} />
                 <Route index element={<Welcome />} />
                 <Route
                   path="*"
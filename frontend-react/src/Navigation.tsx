@@ -90,11 +90,6 @@ export default function Navigation({ isLoading }: { isLoading: boolean }) {
                   Modulsuche
                 </Link>
               </li>
-              <li className="nav-item">
-                <Link className="nav-link" to="/search-modules-opensearch">
-                  Modulsuche (OpenSearch)
-                </Link>
-              </li>
               <li className="nav-item">
                 <Link className="nav-link" to="/search-courses">
                   Veranstaltungssuche
@@ -112,6 +107,13 @@ export default function Navigation({ isLoading }: { isLoading: boolean }) {
                   </Link>
                 </li>
               )}
+              {isLoggedIn && (
+                <li className="nav-item">
+                  <Link className="nav-link" to="/account">
+                    Meine Daten
+                  </Link>
+                </li>
+              )}
               {isLoggedIn && (
                 <li className="nav-item">
                   <Link className="nav-link" to="/logout">
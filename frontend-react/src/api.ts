@@ -1,16 +1,25 @@
 // This file is automatically generated at startup. Do not modify.
 import { genericFetch } from "./api_base"
-export async function course(input: string): Promise<WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXBbXSwgQ291cnNlRXZlbnRbXV0> {
-        return await genericFetch("http://localhost:8080/course", input) as WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXBbXSwgQ291cnNlRXZlbnRbXV0
+export async function course(input: CourseId): Promise<WithTucanUrl_Course_CourseGroupList_CourseEventList> {
+        return await genericFetch("http://localhost:8080/course", input) as WithTucanUrl_Course_CourseGroupList_CourseEventList
 }
-export async function course_group(input: string): Promise<WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXAsIENvdXJzZUdyb3VwRXZlbnRbXV0> {
-        return await genericFetch("http://localhost:8080/course-group", input) as WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXAsIENvdXJzZUdyb3VwRXZlbnRbXV0
+export async function course_group(input: CourseId): Promise<WithTucanUrl_Course_CourseGroup_CourseGroupEventList> {
+        return await genericFetch("http://localhost:8080/course-group", input) as WithTucanUrl_Course_CourseGroup_CourseGroupEventList
 }
-export async function exam(input: string): Promise<WithTucanUrlW0V4YW0sIE1vZHVsZVtdLCBDb3Vyc2VbXV0> {
-        return await genericFetch("http://localhost:8080/exam", input) as WithTucanUrlW0V4YW0sIE1vZHVsZVtdLCBDb3Vyc2VbXV0
+export async function create_calendar_token(input: null): Promise<string> {
+        return await genericFetch("http://localhost:8080/calendar-token", input) as string
 }
-export async function get_modules(input: string | null): Promise<WithTucanUrlTW9kdWxlTWVudVJlc3BvbnNl> {
-        return await genericFetch("http://localhost:8080/modules", input) as WithTucanUrlTW9kdWxlTWVudVJlc3BvbnNl
+export async function credentials(input: null): Promise<CredentialStatus> {
+        return await genericFetch("http://localhost:8080/credentials", input) as CredentialStatus
+}
+export async function delete_account(input: null): Promise<null> {
+        return await genericFetch("http://localhost:8080/delete-account", input) as null
+}
+export async function exam(input: ExamId): Promise<WithTucanUrl_Exam_ModuleList_CourseList> {
+        return await genericFetch("http://localhost:8080/exam", input) as WithTucanUrl_Exam_ModuleList_CourseList
+}
+export async function get_modules(input: MenuPath | null): Promise<WithTucanUrl_ModuleMenuResponse> {
+        return await genericFetch("http://localhost:8080/modules", input) as WithTucanUrl_ModuleMenuResponse
 }
 export async function index(input: null): Promise<string> {
         return await genericFetch("http://localhost:8080/", input) as string
@@ -21,40 +30,81 @@ export async function login(input: Login): Promise<LoginResult> {
 export async function logout(input: null): Promise<null> {
         return await genericFetch("http://localhost:8080/logout", input) as null
 }
-export async function module(input: string): Promise<WithTucanUrlTW9kdWxlUmVzcG9uc2U> {
-        return await genericFetch("http://localhost:8080/module", input) as WithTucanUrlTW9kdWxlUmVzcG9uc2U
+export async function module(input: ModuleId): Promise<WithTucanUrl_ModuleResponse> {
+        return await genericFetch("http://localhost:8080/module", input) as WithTucanUrl_ModuleResponse
+}
+export async function my_courses(input: null): Promise<WithTucanUrl_CourseOrCourseGroupList> {
+        return await genericFetch("http://localhost:8080/my-courses", input) as WithTucanUrl_CourseOrCourseGroupList
+}
+export async function my_exams(input: null): Promise<WithTucanUrl_Module_Exam_List_Course_Exam_List> {
+        return await genericFetch("http://localhost:8080/my-exams", input) as WithTucanUrl_Module_Exam_List_Course_Exam_List
+}
+export async function my_modules(input: null): Promise<WithTucanUrl_ModuleList> {
+        return await genericFetch("http://localhost:8080/my-modules", input) as WithTucanUrl_ModuleList
 }
-export async function my_courses(input: null): Promise<WithTucanUrlQ291cnNlT3JDb3Vyc2VHcm91cFtd> {
-        return await genericFetch("http://localhost:8080/my-courses", input) as WithTucanUrlQ291cnNlT3JDb3Vyc2VHcm91cFtd
+export async function revoke_calendar_token(input: null): Promise<null> {
+        return await genericFetch("http://localhost:8080/revoke-calendar-token", input) as null
 }
-export async function my_exams(input: null): Promise<WithTucanUrlW1tNb2R1bGUsIEV4YW1dW10sIFtDb3Vyc2UsIEV4YW1dW11d> {
-        return await genericFetch("http://localhost:8080/my-exams", input) as WithTucanUrlW1tNb2R1bGUsIEV4YW1dW10sIFtDb3Vyc2UsIEV4YW1dW11d
+export async function revoke_credentials(input: null): Promise<null> {
+        return await genericFetch("http://localhost:8080/revoke-credentials", input) as null
 }
-export async function my_modules(input: null): Promise<WithTucanUrlTW9kdWxlW10> {
-        return await genericFetch("http://localhost:8080/my-modules", input) as WithTucanUrlTW9kdWxlW10
+export async function revoke_session(input: number): Promise<null> {
+        return await genericFetch("http://localhost:8080/revoke-session", input) as null
 }
-export async function search_course(input: string): Promise<SearchResult[]> {
-        return await genericFetch("http://localhost:8080/search-course", input) as SearchResult[]
+export async function search(input: UnifiedSearchQuery): Promise<UnifiedSearchResponse> {
+        return await genericFetch("http://localhost:8080/search", input) as UnifiedSearchResponse
 }
-export async function search_module(input: string): Promise<SearchResult[]> {
-        return await genericFetch("http://localhost:8080/search-modules", input) as SearchResult[]
+export async function search_course(input: SearchQuery): Promise<SearchResponse_CourseId> {
+        return await genericFetch("http://localhost:8080/search-course", input) as SearchResponse_CourseId
 }
-export async function search_module_opensearch(input: string): Promise<SearchResult[]> {
-        return await genericFetch("http://localhost:8080/search-modules-opensearch", input) as SearchResult[]
+export async function search_module(input: SearchQuery): Promise<SearchResponse_ModuleId> {
+        return await genericFetch("http://localhost:8080/search-modules", input) as SearchResponse_ModuleId
+}
+export async function sessions(input: null): Promise<SessionInfo[]> {
+        return await genericFetch("http://localhost:8080/sessions", input) as SessionInfo[]
+}
+export async function similar_modules(input: SimilarModulesQuery): Promise<SearchHit_ModuleId[]> {
+        return await genericFetch("http://localhost:8080/similar-modules", input) as SearchHit_ModuleId[]
+}
+export async function store_credentials(input: StoreCredentials): Promise<null> {
+        return await genericFetch("http://localhost:8080/store-credentials", input) as null
+}
+export async function suggest(input: string): Promise<Suggestion[]> {
+        return await genericFetch("http://localhost:8080/suggest", input) as Suggestion[]
+}
+export async function takeout(input: null): Promise<Takeout> {
+        return await genericFetch("http://localhost:8080/takeout", input) as Takeout
+}
+export type ApiError =
+ | { type: "Unauthorized" }
+ | { type: "SessionExpired" }
+ | { type: "NotFound" }
+ | { type: "BadRequest", value: string }
+ | { type: "InvalidQuery", value: QueryError }
+ | { type: "UpstreamUnavailable" }
+ | { type: "Internal" }
+
+export type CalendarToken =
+{
+  token: string,
+  matriculation_number: number,
+  created: string,
 }
 export type Course =
 {
-  tucan_id: string,
+  tucan_id: CourseId,
   tucan_last_checked: string,
   title: string,
   course_id: string,
   sws: number,
   content: string,
   done: boolean,
+  title_en: string,
+  content_en: string,
 }
 export type CourseEvent =
 {
-  course: number[],
+  course: CourseId,
   timestamp_start: string,
   timestamp_end: string,
   room: string,
@@ -62,26 +112,38 @@ export type CourseEvent =
 }
 export type CourseGroup =
 {
-  tucan_id: string,
-  course: string,
+  tucan_id: CourseId,
+  course: CourseId,
   title: string,
   done: boolean,
 }
 export type CourseGroupEvent =
 {
-  course: number[],
+  course: CourseId,
   timestamp_start: string,
   timestamp_end: string,
   room: string,
   teachers: string,
 }
+export type CourseId = string & { __brand: "CourseId" }
 export type CourseOrCourseGroup =
  | { type: "Course", value: [Course, CourseGroup[], CourseEvent[]] }
  | { type: "CourseGroup", value: [CourseGroup, CourseGroupEvent[]] }
 
+export type CredentialAuditEntry =
+{
+  event: string,
+  created: string,
+}
+export type CredentialStatus =
+{
+  consented: string | null,
+  last_used: string | null,
+  audit_log: CredentialAuditEntry[],
+}
 export type Exam =
 {
-  tucan_id: string,
+  tucan_id: ExamId,
   exam_type: string,
   semester: string,
   exam_time_start: string | null,
@@ -94,6 +156,17 @@ export type Exam =
   room: string | null,
   done: boolean,
 }
+export type ExamId = string & { __brand: "ExamId" }
+export type FacetCount =
+{
+  value: string,
+  count: number,
+}
+export type KindWeight =
+{
+  kind: SearchKind,
+  weight: number,
+}
 export type Login =
 {
   username: string,
@@ -103,28 +176,32 @@ export type LoginResult =
 {
   success: boolean,
 }
+export type MenuPath = string & { __brand: "MenuPath" }
 export type Module =
 {
-  tucan_id: string,
+  tucan_id: ModuleId,
   tucan_last_checked: string,
   title: string,
   module_id: string,
   credits: number | null,
   content: string,
   done: boolean,
+  title_en: string,
+  content_en: string,
 }
+export type ModuleId = string & { __brand: "ModuleId" }
 export type ModuleMenu =
 {
-  tucan_id: string,
+  tucan_id: MenuPath,
   tucan_last_checked: string,
   name: string,
   done: boolean,
-  parent: string,
+  parent: MenuPath | null,
 }
 export type ModuleMenuPathPart =
 {
-  parent: number[] | null,
-  tucan_id: string,
+  parent: MenuPath | null,
+  tucan_id: MenuPath,
   name: string,
   leaf: boolean,
 }
@@ -139,54 +216,225 @@ export type ModuleResponse =
   module: Module,
   path: ModuleMenuPathPart[][],
 }
+export type QueryError =
+{
+  message: string,
+  position: number,
+}
 export type Registration =
 {
   submenus: ModuleMenu[],
   modules_and_courses: [Module, Course[]][],
 }
-export type SearchResult =
+export type SearchFacets =
+{
+  credits: FacetCount[],
+  sws: FacetCount[],
+  semesters: FacetCount[],
+}
+export type SearchFilters =
+{
+  credits_min: number | null,
+  credits_max: number | null,
+  sws_min: number | null,
+  sws_max: number | null,
+  semester: string | null,
+  language: string | null,
+  menu: MenuPath | null,
+}
+export type SearchHit_CourseId =
+{
+  id: CourseId,
+  title: string,
+  excerpt: string,
+  rank: number,
+}
+export type SearchHit_ExamId =
+{
+  id: ExamId,
+  title: string,
+  excerpt: string,
+  rank: number,
+}
+export type SearchHit_ModuleId =
+{
+  id: ModuleId,
+  title: string,
+  excerpt: string,
+  rank: number,
+}
+export type SearchHit_string =
+{
+  id: string,
+  title: string,
+  excerpt: string,
+  rank: number,
+}
+export type SearchKind =
+ | { type: "Module" }
+ | { type: "Course" }
+ | { type: "CourseGroup" }
+ | { type: "Exam" }
+ | { type: "Lecturer" }
+
+export type SearchQuery =
+{
+  query: string,
+  page: number,
+  page_size: number | null,
+  filters: SearchFilters,
+}
+export type SearchResponse_CourseId =
+{
+  results: SearchResult_CourseId[],
+  total: number,
+  page: number,
+  page_size: number,
+  facets: SearchFacets,
+}
+export type SearchResponse_ModuleId =
+{
+  results: SearchResult_ModuleId[],
+  total: number,
+  page: number,
+  page_size: number,
+  facets: SearchFacets,
+}
+export type SearchResult_CourseId =
 {
-  tucan_id: string,
+  tucan_id: CourseId,
   title: string,
   excerpt: string,
   rank: number,
 }
-export type WithTucanUrlQ291cnNlT3JDb3Vyc2VHcm91cFtd =
+export type SearchResult_ModuleId =
+{
+  tucan_id: ModuleId,
+  title: string,
+  excerpt: string,
+  rank: number,
+}
+export type SessionInfo =
+{
+  handle: number,
+  created: string,
+  last_used: string,
+  expires: string,
+  user_agent: string | null,
+  current: boolean,
+}
+export type SimilarModulesQuery =
+{
+  module: ModuleId,
+  limit: number | null,
+  registrable_only: boolean,
+  exclude_registered: boolean,
+}
+export type StoreCredentials =
+{
+  username: string,
+  password: string,
+  consent: boolean,
+}
+export type Suggestion =
+{
+  text: string,
+  kind: SearchKind,
+  module: ModuleId | null,
+  score: number,
+}
+export type Takeout =
+{
+  user: User | null,
+  sessions: SessionInfo[],
+  credentials: CredentialStatus,
+  calendar_token: CalendarToken | null,
+  modules: Module[],
+  courses: Course[],
+  course_groups: CourseGroup[],
+  exams: Exam[],
+}
+export type UnifiedSearchHit =
+ | { type: "Module", value: SearchHit_ModuleId }
+ | { type: "Course", value: SearchHit_CourseId }
+ | { type: "CourseGroup", value: SearchHit_CourseId }
+ | { type: "Exam", value: SearchHit_ExamId }
+ | { type: "Lecturer", value: SearchHit_string }
+
+export type UnifiedSearchQuery =
+{
+  query: string,
+  page: number,
+  page_size: number | null,
+  kinds: KindWeight[],
+}
+export type UnifiedSearchResponse =
+{
+  results: UnifiedSearchHit[],
+  total: number,
+  page: number,
+  page_size: number,
+  kinds: FacetCount[],
+}
+export type User =
+{
+  matriculation_number: number,
+  title: string,
+  academic_title: string,
+  post_name: string,
+  first_name: string,
+  middle_name: string,
+  last_name: string,
+  pre_name: string,
+  redirect_messages_to_university_email: boolean,
+  subject: string,
+  email: string,
+  department: number,
+  post_title: string,
+  street: string,
+  address_addition: string,
+  country: string,
+  plz: number,
+  city: string,
+  phone_number: string,
+  done: boolean,
+}
+export type WithTucanUrl_CourseOrCourseGroupList =
 {
   tucan_url: string,
   inner: CourseOrCourseGroup[],
 }
-export type WithTucanUrlTW9kdWxlTWVudVJlc3BvbnNl =
+export type WithTucanUrl_Course_CourseGroupList_CourseEventList =
 {
   tucan_url: string,
-  inner: ModuleMenuResponse,
+  inner: [Course, CourseGroup[], CourseEvent[]],
 }
-export type WithTucanUrlTW9kdWxlUmVzcG9uc2U =
+export type WithTucanUrl_Course_CourseGroup_CourseGroupEventList =
 {
   tucan_url: string,
-  inner: ModuleResponse,
+  inner: [Course, CourseGroup, CourseGroupEvent[]],
 }
-export type WithTucanUrlTW9kdWxlW10 =
+export type WithTucanUrl_Exam_ModuleList_CourseList =
 {
   tucan_url: string,
-  inner: Module[],
+  inner: [Exam, Module[], Course[]],
 }
-export type WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXAsIENvdXJzZUdyb3VwRXZlbnRbXV0 =
+export type WithTucanUrl_ModuleList =
 {
   tucan_url: string,
-  inner: [Course, CourseGroup, CourseGroupEvent[]],
+  inner: Module[],
 }
-export type WithTucanUrlW0NvdXJzZSwgQ291cnNlR3JvdXBbXSwgQ291cnNlRXZlbnRbXV0 =
+export type WithTucanUrl_ModuleMenuResponse =
 {
   tucan_url: string,
-  inner: [Course, CourseGroup[], CourseEvent[]],
+  inner: ModuleMenuResponse,
 }
-export type WithTucanUrlW0V4YW0sIE1vZHVsZVtdLCBDb3Vyc2VbXV0 =
+export type WithTucanUrl_ModuleResponse =
 {
   tucan_url: string,
-  inner: [Exam, Module[], Course[]],
+  inner: ModuleResponse,
 }
-export type WithTucanUrlW1tNb2R1bGUsIEV4YW1dW10sIFtDb3Vyc2UsIEV4YW1dW11d =
+export type WithTucanUrl_Module_Exam_List_Course_Exam_List =
 {
   tucan_url: string,
   inner: [[Module, Exam][], [Course, Exam][]],
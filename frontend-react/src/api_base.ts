@@ -1,3 +1,19 @@
+import type { ApiError } from "./api";
+
+// an error response of the backend, `error.type` tells what went wrong
+export class ApiRequestError extends Error {
+  constructor(readonly status: number, readonly error: ApiError) {
+    super(
+      error.type === "BadRequest"
+        ? `${error.type}: ${error.value}`
+        : error.type === "InvalidQuery"
+        ? `${error.type}: ${error.value.message} at position ${error.value.position}`
+        : `${status} ${error.type}`,
+    );
+    this.name = "ApiRequestError";
+  }
+}
+
 export async function genericFetch(
   url: string,
   input: unknown,
@@ -7,6 +23,7 @@ export async function genericFetch(
     method: "POST",
     headers: {
       "Content-Type": "application/json",
+      // required by the CSRF protection of all routes, see csrf.rs
       "x-csrf-protection": "tucant",
     },
     body: JSON.stringify(input),
@@ -15,17 +32,18 @@ export async function genericFetch(
     if (response.status === 401) {
       return null;
     }
-    let errorMessage;
+    let error: ApiError;
     try {
-      errorMessage = `${response.status} ${
-        response.statusText
-      }: ${await response.text()}`;
+      error = (await response.json()) as ApiError;
     } catch (err) {
-      errorMessage = `${response.status} ${
-        response.statusText
-      }, while loading error body: ${String(err)}`;
+      // e.g. a proxy in front of the backend
+      throw new Error(
+        `${response.status} ${response.statusText}, while loading error body: ${String(
+          err,
+        )}`,
+      );
     }
-    throw new Error(errorMessage);
+    throw new ApiRequestError(response.status, error);
   }
   return (await response.json()) as unknown;
 }
@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+import { useState } from "react";
+import { create_calendar_token, revoke_calendar_token } from "../api";
+
+export function CalendarSubscription() {
+  const [url, setUrl] = useState<string | null>(null);
+
+  return (
+    <div className="mb-3">
+      <button
+        className="btn btn-outline-primary me-2"
+        onClick={() => {
+          void create_calendar_token(null).then(setUrl);
+        }}
+      >
+        Kalender abonnieren
+      </button>
+      <button
+        className="btn btn-outline-danger"
+        onClick={() => {
+          void revoke_calendar_token(null).then(() => setUrl(null));
+        }}
+      >
+        Kalender-Link widerrufen
+      </button>
+      {url != null && (
+        <input className="form-control mt-2" readOnly value={url} />
+      )}
+    </div>
+  );
+}
@@ -5,19 +5,29 @@
 import dompurify from "dompurify";
 import { useState, useTransition } from "react";
 import useSWR from "swr";
-import { SearchResult } from "../api";
+import {
+  SearchQuery,
+  SearchResponse_CourseId,
+  SearchResponse_ModuleId,
+  SearchResult_CourseId,
+  SearchResult_ModuleId,
+} from "../api";
+import { ApiRequestError } from "../api_base";
 import { Link } from "../Navigation";
 import InitialFetch from "../routes/InitialFetch";
 import SignOut from "../routes/Logout";
 
 export default function SearchPage(props: {
   title: string;
-  function: (input: string) => Promise<SearchResult[]>;
+  function: (
+    input: SearchQuery,
+  ) => Promise<SearchResponse_ModuleId | SearchResponse_CourseId | null>;
   base_path: string;
 }) {
   const [form, setForm] = useState({
     q: "",
   });
+  const [page, setPage] = useState(0);
 
   const [isLoading, startTransition] = useTransition();
 
@@ -33,17 +43,35 @@ export default function SearchPage(props: {
         ...form,
         [target.name]: value,
       });
+      setPage(0);
     });
   };
 
-  const { data } = useSWR([`search_${props.base_path}`, form.q], {
-    fetcher: ([_, q]) => props.function(q),
+  const { data, error } = useSWR([`search_${props.base_path}`, form.q, page], {
+    fetcher: ([_, q, page]) =>
+      props.function({
+        query: q,
+        page,
+        page_size: null,
+        filters: {
+          credits_min: null,
+          credits_max: null,
+          sws_min: null,
+          sws_max: null,
+          semester: null,
+          language: null,
+          menu: null,
+        },
+      }),
   });
 
   if (data === null) {
     return <SignOut />;
   }
 
+  const results: (SearchResult_ModuleId | SearchResult_CourseId)[] =
+    data?.results ?? [];
+
   return (
     <main className="container">
       <h1 className="text-center">{props.title}</h1>
@@ -104,13 +132,28 @@ export default function SearchPage(props: {
                 -: a dash means a word is not allowed to be contained in the
                 document
               </li>
+              <li>
+                field:value: filters by credits, sws, lecturer, semester or
+                language, e.g. credits:&gt;=6 lecturer:Weinmann
+                semester:&quot;WiSe 2022/23&quot; lang:en
+              </li>
             </ul>
           </div>
         </div>
       </form>
 
+      {error instanceof ApiRequestError &&
+        error.error.type === "InvalidQuery" && (
+          <div className="alert alert-warning" role="alert">
+            {error.error.value.message} (at position{" "}
+            {error.error.value.position})
+          </div>
+        )}
+
+      {data && <p>{data.total} Ergebnisse</p>}
+
       <div className="list-group">
-        {data?.map((e) => (
+        {results.map((e) => (
           <Link
             key={e.tucan_id}
             className="list-group-item list-group-item-action"
@@ -138,6 +181,27 @@ export default function SearchPage(props: {
           </Link>
         ))}
       </div>
+
+      {data && (
+        <nav className="d-flex justify-content-between mt-3">
+          <button
+            className="btn btn-outline-secondary"
+            type="button"
+            disabled={page === 0}
+            onClick={() => setPage(page - 1)}
+          >
+            Zurück
+          </button>
+          <button
+            className="btn btn-outline-secondary"
+            type="button"
+            disabled={(page + 1) * data.page_size >= data.total}
+            onClick={() => setPage(page + 1)}
+          >
+            Weiter
+          </button>
+        </nav>
+      )}
     </main>
   );
 }
@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+import useSWR from "swr";
+import { revoke_session, sessions } from "../api";
+import { formatLocalDate } from "../api_base";
+
+export function Sessions() {
+  const { data, mutate } = useSWR("sessions", {
+    fetcher: () => sessions(null),
+  });
+
+  return (
+    <>
+      <h2 className="mt-4">Angemeldete Geräte</h2>
+      <ul className="list-group">
+        {data?.map((session) => (
+          <li
+            key={session.handle}
+            className="list-group-item d-flex justify-content-between align-items-center"
+          >
+            <div>
+              {session.user_agent ?? "Unbekanntes Gerät"}
+              {session.current && " (dieses Gerät)"}
+              <br />
+              <small className="text-muted">
+                Angemeldet {formatLocalDate(session.created)}, zuletzt aktiv{" "}
+                {formatLocalDate(session.last_used)}
+              </small>
+            </div>
+            {!session.current && (
+              <button
+                className="btn btn-outline-danger btn-sm"
+                onClick={() => {
+                  void revoke_session(session.handle).then(() => mutate());
+                }}
+              >
+                Abmelden
+              </button>
+            )}
+          </li>
+        ))}
+      </ul>
+    </>
+  );
+}
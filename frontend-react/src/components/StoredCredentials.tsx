@@ -0,0 +1,122 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+import * as React from "react";
+import { useState } from "react";
+import useSWR from "swr";
+import { credentials, revoke_credentials, store_credentials } from "../api";
+import { formatLocalDate } from "../api_base";
+
+const EVENTS: Record<string, string> = {
+  stored: "Gespeichert",
+  used: "Für eine automatische Anmeldung verwendet",
+  login_failed: "Von TUCaN abgelehnt und gelöscht",
+  wrong_user: "Für ein anderes Konto gültig und gelöscht",
+  revoked: "Widerrufen",
+};
+
+export function StoredCredentials() {
+  const { data, mutate } = useSWR("credentials", {
+    fetcher: () => credentials(null),
+  });
+
+  const [error, setError] = useState<string | null>(null);
+  const [form, setForm] = useState({
+    username: "",
+    password: "",
+    consent: false,
+  });
+
+  const handleSubmit = (event: React.FormEvent<HTMLFormElement>) => {
+    event.preventDefault();
+    setError(null);
+    store_credentials(form)
+      .then(() => {
+        setForm({ username: "", password: "", consent: false });
+        return mutate();
+      })
+      .catch((error) => setError(String(error)));
+  };
+
+  return (
+    <>
+      <h2 className="mt-4">Automatische Anmeldung</h2>
+      <p>
+        TUCaN-Sitzungen laufen schnell ab. Wenn du deine Zugangsdaten
+        speicherst, kann tucant sich im Hintergrund neu anmelden, um deine
+        Module und Prüfungen aktuell zu halten. Sie werden verschlüsselt
+        gespeichert, nur dafür verwendet und jede Verwendung wird unten
+        protokolliert.
+      </p>
+      {error && (
+        <div className="alert alert-danger" role="alert">
+          {error}
+        </div>
+      )}
+      {data?.consented != null ? (
+        <p>
+          Gespeichert seit {formatLocalDate(data.consented)}
+          {data.last_used != null &&
+            `, zuletzt verwendet ${formatLocalDate(data.last_used) ?? ""}`}
+          .{" "}
+          <button
+            className="btn btn-outline-danger btn-sm"
+            onClick={() => {
+              void revoke_credentials(null).then(() => mutate());
+            }}
+          >
+            Zugangsdaten löschen
+          </button>
+        </p>
+      ) : (
+        <form onSubmit={handleSubmit}>
+          <input
+            className="form-control mb-2"
+            placeholder="TU-ID"
+            autoComplete="username"
+            required
+            value={form.username}
+            onChange={(e) => setForm({ ...form, username: e.target.value })}
+          />
+          <input
+            className="form-control mb-2"
+            type="password"
+            placeholder="Passwort"
+            autoComplete="current-password"
+            required
+            value={form.password}
+            onChange={(e) => setForm({ ...form, password: e.target.value })}
+          />
+          <div className="form-check mb-2">
+            <input
+              id="credentialConsent"
+              className="form-check-input"
+              type="checkbox"
+              required
+              checked={form.consent}
+              onChange={(e) => setForm({ ...form, consent: e.target.checked })}
+            />
+            <label className="form-check-label" htmlFor="credentialConsent">
+              Ich bin einverstanden, dass tucant meine Zugangsdaten speichert
+              und sich damit bei TUCaN anmeldet, bis ich sie lösche.
+            </label>
+          </div>
+          <button className="btn btn-primary" type="submit">
+            Zugangsdaten speichern
+          </button>
+        </form>
+      )}
+      {data != null && data.audit_log.length > 0 && (
+        <ul className="list-group mt-2">
+          {data.audit_log.map((entry, i) => (
+            <li key={i} className="list-group-item">
+              {formatLocalDate(entry.created)}:{" "}
+              {EVENTS[entry.event] ?? entry.event}
+            </li>
+          ))}
+        </ul>
+      )}
+    </>
+  );
+}
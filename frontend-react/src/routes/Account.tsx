@@ -0,0 +1,55 @@
+// SPDX-FileCopyrightText: The tucant Contributors
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+import { useNavigate } from "react-router-dom";
+import { delete_account, takeout } from "../api";
+import { Sessions } from "../components/Sessions";
+import { StoredCredentials } from "../components/StoredCredentials";
+
+export default function Account() {
+  const navigate = useNavigate();
+
+  return (
+    <main className="container">
+      <h1 className="text-center">Meine Daten</h1>
+      <p>
+        Hier kannst du alle Daten herunterladen, die tucant zu deiner
+        Matrikelnummer gespeichert hat, oder sie löschen. Die Modul- und
+        Veranstaltungsdaten selbst werden von allen Nutzenden geteilt und
+        bleiben erhalten.
+      </p>
+      <button
+        className="btn btn-primary me-2"
+        onClick={() => {
+          void takeout(null).then((data) => {
+            const url = URL.createObjectURL(
+              new Blob([JSON.stringify(data, null, 2)], {
+                type: "application/json",
+              }),
+            );
+            const link = document.createElement("a");
+            link.href = url;
+            link.download = "tucant-takeout.json";
+            link.click();
+            URL.revokeObjectURL(url);
+          });
+        }}
+      >
+        Daten herunterladen
+      </button>
+      <button
+        className="btn btn-danger"
+        onClick={() => {
+          if (window.confirm("Wirklich alle deine Daten löschen?")) {
+            void delete_account(null).then(() => navigate("/logout"));
+          }
+        }}
+      >
+        Konto löschen
+      </button>
+      <Sessions />
+      <StoredCredentials />
+    </main>
+  );
+}
@@ -4,7 +4,7 @@
 
 import { useParams } from "react-router-dom";
 import dompurify from "dompurify";
-import { course } from "../api";
+import { course, CourseId } from "../api";
 import useSWR from "swr";
 import SignOut from "./Logout";
 import { TucanUrlLink } from "../components/TucanUrlLink";
@@ -14,7 +14,7 @@ export default function CourseRoute() {
   const { id } = useParams();
 
   const { data } = useSWR(["course", id ?? ""], {
-    fetcher: ([_, id]) => course(id),
+    fetcher: ([_, id]) => course(id as CourseId),
   });
 
   if (data === null) {
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 import { useParams } from "react-router-dom";
-import { course_group } from "../api";
+import { course_group, CourseId } from "../api";
 import useSWR from "swr";
 import SignOut from "./Logout";
 import { TucanUrlLink } from "../components/TucanUrlLink";
@@ -12,7 +12,7 @@ export default function CourseGroupRoute() {
   const { id } = useParams();
 
   const { data } = useSWR(["course-group", id ?? ""], {
-    fetcher: ([_, id]) => course_group(id),
+    fetcher: ([_, id]) => course_group(id as CourseId),
   });
 
   if (data === null) {
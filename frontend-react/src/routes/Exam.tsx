@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 import { useParams } from "react-router-dom";
-import { exam } from "../api";
+import { exam, ExamId } from "../api";
 import useSWR from "swr";
 import SignOut from "./Logout";
 import { TucanUrlLink } from "../components/TucanUrlLink";
@@ -14,7 +14,7 @@ export default function Exam() {
   const { id } = useParams();
 
   const { data } = useSWR(["exam", id ?? ""], {
-    fetcher: ([_, id]) => exam(id),
+    fetcher: ([_, id]) => exam(id as ExamId),
   });
 
   if (data === null) {
@@ -4,7 +4,7 @@
 
 import { useParams } from "react-router-dom";
 import dompurify from "dompurify";
-import { module } from "../api";
+import { module, ModuleId, similar_modules } from "../api";
 import useSWR from "swr";
 import { Link } from "../Navigation";
 import SignOut from "./Logout";
@@ -14,7 +14,17 @@ export default function Module() {
   const { id } = useParams();
 
   const { data } = useSWR(["course", id ?? ""], {
-    fetcher: ([_, id]) => module(id),
+    fetcher: ([_, id]) => module(id as ModuleId),
+  });
+
+  const { data: similar } = useSWR(["similar_modules", id ?? ""], {
+    fetcher: ([_, id]) =>
+      similar_modules({
+        module: id as ModuleId,
+        limit: 5,
+        registrable_only: false,
+        exclude_registered: true,
+      }),
   });
 
   if (data === null) {
@@ -54,6 +64,22 @@ export default function Module() {
           />
         </>
       )}
+      {similar && similar.length > 0 && (
+        <>
+          <h2>Ähnliche Module</h2>
+          <div className="list-group">
+            {similar.map((hit) => (
+              <Link
+                key={hit.id}
+                className="list-group-item list-group-item-action"
+                to={`/module/${hit.id}`}
+              >
+                {hit.title}
+              </Link>
+            ))}
+          </div>
+        </>
+      )}
     </main>
   );
 }
@@ -4,7 +4,7 @@
 
 import { useParams } from "react-router-dom";
 import useSWR from "swr";
-import { get_modules } from "../api";
+import { get_modules, MenuPath } from "../api";
 import { ModuleList } from "../components/ModuleList";
 import { TucanUrlLink } from "../components/TucanUrlLink";
 import { Link } from "../Navigation";
@@ -14,7 +14,7 @@ export default function Modules() {
   const { id } = useParams();
 
   const { data } = useSWR(["module_menu", id ?? null], {
-    fetcher: ([_, id]) => get_modules(id),
+    fetcher: ([_, id]) => get_modules(id as MenuPath | null),
   });
 
   if (data === null) {
@@ -5,6 +5,7 @@
 import useSWR from "swr";
 import { my_exams } from "../api";
 import { formatLocalDate } from "../api_base";
+import { CalendarSubscription } from "../components/CalendarSubscription";
 import { TucanUrlLink } from "../components/TucanUrlLink";
 import { Link } from "../Navigation";
 import SignOut from "./Logout";
@@ -24,6 +25,7 @@ export default function MyExams() {
       {data != null && (
         <>
           <TucanUrlLink data={data} />
+          <CalendarSubscription />
           <div className="list-group">
             {data.inner[0].map((e) => (
               <Link